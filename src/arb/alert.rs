@@ -0,0 +1,94 @@
+use super::*;
+use crate::global;
+use serde::Serialize;
+use std::sync::Mutex;
+use tokio::time::{Duration, Instant};
+use tracing::warn;
+
+const ALERT_TIMEOUT: Duration = Duration::from_secs(3);
+
+lazy_static::lazy_static! {
+    static ref CLIENT: reqwest::Client = reqwest::Client::builder()
+        .timeout(ALERT_TIMEOUT)
+        .build()
+        .expect("Failed to build alert client");
+    // route hash -> last time it was alerted
+    static ref LAST_ALERTS: Mutex<HashMap<u64, Instant>> = Mutex::new(HashMap::new());
+}
+
+/// JSON body posted to `alerts.webhook_url`.
+#[derive(Debug, Serialize)]
+struct Opportunity {
+    timestamp: i64,
+    pools: Vec<String>,
+    dexes: Vec<String>,
+    // base mint first, then the mint each hop swaps into
+    mints: Vec<String>,
+    amount_in: u64,
+    profit: i64,
+}
+
+impl Opportunity {
+    fn from_swap(swap: &SwapRoutes) -> Self {
+        let mut mint = swap.mint;
+        let mut mints = Vec::with_capacity(swap.routes.len() + 1);
+        mints.push(mint.to_string());
+        for pool in &swap.routes {
+            mint = pool.get_other_mint(&mint);
+            mints.push(mint.to_string());
+        }
+
+        Opportunity {
+            timestamp: chrono::Utc::now().timestamp(),
+            pools: swap
+                .routes
+                .iter()
+                .map(|pool| pool.get_address().to_string())
+                .collect(),
+            dexes: swap
+                .routes
+                .iter()
+                .map(|pool| format!("{:?}", pool.to_pool_type()))
+                .collect(),
+            mints,
+            amount_in: swap.amount_in,
+            profit: swap.profit,
+        }
+    }
+}
+
+fn claim(route_hash: u64, cooldown: Duration) -> bool {
+    let mut last_alerts = LAST_ALERTS.lock().unwrap();
+    let now = Instant::now();
+    last_alerts.retain(|_, last| now.duration_since(*last) < cooldown);
+    if last_alerts.contains_key(&route_hash) {
+        return false;
+    }
+    last_alerts.insert(route_hash, now);
+    true
+}
+
+/// Post a profitable route to `alerts.webhook_url`, once per `alerts.cooldown_secs` for the
+/// same pools. The request runs in the background so it never holds up submission.
+pub fn notify(swap: &SwapRoutes) {
+    let Some(url) = global::get_alert_webhook_url() else {
+        return;
+    };
+    let cooldown = Duration::from_secs(global::get_alert_cooldown_secs());
+    if !claim(swap.to_hash(), cooldown) {
+        return;
+    }
+
+    let opportunity = Opportunity::from_swap(swap);
+    tokio::spawn(async move {
+        let result = CLIENT
+            .post(&url)
+            .json(&opportunity)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = result {
+            warn!("Alert webhook failed: {}", e);
+        }
+    });
+}
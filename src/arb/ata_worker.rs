@@ -14,7 +14,7 @@ use std::sync::Arc;
 use std::time::Duration;
 use std::{collections::HashMap, sync::OnceLock};
 use tokio::sync::mpsc;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 struct AtaKey(pub Pubkey);
@@ -133,6 +133,49 @@ impl AtaWorker {
         is_created_all
     }
 
+    pub fn known_atas(pools: &[PoolType]) -> HashSet<Pubkey> {
+        let owner = global::get_pubkey();
+        let mut atas: HashSet<Pubkey> = HashSet::with_capacity(pools.len() * 2);
+        for pool in pools {
+            let (mint_a, mint_b) = pool.get_mints();
+            for mint in [mint_a, mint_b] {
+                if Self::check_ata_ready(&mint) {
+                    atas.insert(onchain::get_user_token_address(&owner, &mint));
+                }
+            }
+        }
+
+        atas
+    }
+
+    /// `known_atas` for any owner, looked up on chain. The cache only tracks the bot wallet.
+    pub async fn fetch_known_atas(owner: &Pubkey, pools: &[PoolType]) -> Result<HashSet<Pubkey>> {
+        if *owner == global::get_pubkey() {
+            return Ok(Self::known_atas(pools));
+        }
+
+        let mut ata_vec: Vec<Pubkey> = Vec::with_capacity(pools.len() * 2);
+        for pool in pools {
+            let (mint_a, mint_b) = pool.get_mints();
+            for mint in [mint_a, mint_b] {
+                let ata = onchain::get_user_token_address(owner, &mint);
+                if !ata_vec.contains(&ata) {
+                    ata_vec.push(ata);
+                }
+            }
+        }
+
+        let accounts = global::get_rpc_client()
+            .get_multiple_accounts_with_commitment(&ata_vec, CommitmentConfig::confirmed())
+            .await?;
+
+        Ok(ata_vec
+            .into_iter()
+            .zip(accounts.value)
+            .filter_map(|(ata, account)| account.map(|_| ata))
+            .collect())
+    }
+
     pub fn request_ata_creation(mints: Vec<Pubkey>) {
         Self::get_or_init().request_many(mints);
     }
@@ -146,7 +189,10 @@ impl AtaWorker {
     }
 
     pub fn set_ata_state(mint: Pubkey, state: bool) {
-        let done_cache = DONE_CACHE.get().unwrap();
+        // nothing is cached before the worker starts
+        let Some(done_cache) = DONE_CACHE.get() else {
+            return;
+        };
         let key = AtaKey(mint);
 
         if state {
@@ -207,7 +253,7 @@ async fn check_and_create_ata(mint: &Pubkey) -> Result<()> {
                 .await?;
             }
         } else {
-            println!("Skip token 2022 {}", mint);
+            debug!("Skip token 2022 {}", mint);
         }
     }
 
@@ -242,8 +288,8 @@ async fn updater(pools: &[Arc<TokenPool>]) -> Result<()> {
     let mut ata_vec: Vec<Pubkey> = Vec::with_capacity(pools.len() * 2);
     let mut token_map: HashMap<Pubkey, Pubkey> = HashMap::new();
     for pool in pools {
-        let ata_mint_a = onchain::get_associated_token_address(&owner, &pool.mint_a);
-        let ata_mint_b = onchain::get_associated_token_address(&owner, &pool.mint_b);
+        let ata_mint_a = onchain::get_user_token_address(&owner, &pool.mint_a);
+        let ata_mint_b = onchain::get_user_token_address(&owner, &pool.mint_b);
         ata_vec.push(ata_mint_a);
         ata_vec.push(ata_mint_b);
         token_map.insert(ata_mint_a, pool.mint_a);
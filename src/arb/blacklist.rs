@@ -0,0 +1,76 @@
+use crate::global;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+// pool -> time it leaves the blacklist
+static BLACKLIST: Lazy<DashMap<Pubkey, Instant>> = Lazy::new(DashMap::new);
+// pool -> recent failures of routes starting at it
+static FAILURES: Lazy<Mutex<HashMap<Pubkey, VecDeque<Instant>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Count a route that reverted on chain against its first-hop pool, and blacklist the pool once
+/// it fails `bot.revert_blacklist_threshold` times within the window. Routes rejected before
+/// sending say nothing about the pool and aren't counted.
+pub fn record_failure(pool: &Pubkey) {
+    let threshold = global::get_revert_blacklist_threshold();
+    if threshold == 0 {
+        return;
+    }
+
+    let now = Instant::now();
+    let window = Duration::from_secs(global::get_revert_blacklist_window_secs());
+    let mut failures = FAILURES.lock().unwrap();
+    let pool_failures = failures.entry(*pool).or_default();
+    pool_failures.push_back(now);
+    while pool_failures
+        .front()
+        .is_some_and(|failed| now.duration_since(*failed) > window)
+    {
+        pool_failures.pop_front();
+    }
+
+    if pool_failures.len() >= threshold as usize {
+        let cooldown = Duration::from_secs(global::get_revert_blacklist_cooldown_secs());
+        warn!(
+            "Blacklisted pool {} for {:?} after {} failures",
+            pool,
+            cooldown,
+            pool_failures.len()
+        );
+        failures.remove(pool);
+        BLACKLIST.insert(*pool, now + cooldown);
+    }
+}
+
+pub fn is_blacklisted(pool: &Pubkey) -> bool {
+    if BLACKLIST.is_empty() {
+        return false;
+    }
+
+    let Some(until) = BLACKLIST.get(pool).map(|entry| *entry.value()) else {
+        return false;
+    };
+    if Instant::now() < until {
+        return true;
+    }
+
+    if BLACKLIST.remove(pool).is_some() {
+        info!("Pool {} left the blacklist", pool);
+    }
+    false
+}
+
+/// Blacklisted pools and how long each stays excluded.
+pub fn entries() -> Vec<(Pubkey, Duration)> {
+    let now = Instant::now();
+    BLACKLIST
+        .iter()
+        .filter(|entry| *entry.value() > now)
+        .map(|entry| (*entry.key(), entry.value().duration_since(now)))
+        .collect()
+}
@@ -0,0 +1,142 @@
+use crate::{
+    arb::{
+        blacklist,
+        pair_breaker::{self, MintPair},
+    },
+    global, metric,
+};
+use anchor_client::solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+};
+use std::sync::OnceLock;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+// submissions waiting to be queued, new ones are dropped when full
+const QUEUE_CAPACITY: usize = 1024;
+// getSignatureStatuses accepts at most 256 signatures per call
+const STATUS_BATCH_SIZE: usize = 256;
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+// past this the blockhash has expired and the transaction can no longer land
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(90);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteOutcome {
+    Landed,
+    Failed,
+    Expired,
+}
+
+#[derive(Debug, Clone)]
+pub struct SubmittedArb {
+    pub signature: Signature,
+    pub pools: Vec<Pubkey>,
+    pub pairs: Vec<MintPair>,
+    // base mint amount_in and profit are denominated in
+    pub mint: Pubkey,
+    pub amount_in: u64,
+    pub profit: i64,
+    pub submitted: Instant,
+}
+
+pub struct ConfirmationTracker {
+    tx: mpsc::Sender<SubmittedArb>,
+}
+
+static CONFIRMATION_TRACKER: OnceLock<ConfirmationTracker> = OnceLock::new();
+
+impl ConfirmationTracker {
+    pub fn get_or_init() -> &'static ConfirmationTracker {
+        CONFIRMATION_TRACKER.get_or_init(|| {
+            let (tx, rx) = mpsc::channel::<SubmittedArb>(QUEUE_CAPACITY);
+            tokio::spawn(track_loop(rx));
+            ConfirmationTracker { tx }
+        })
+    }
+
+    /// Queue a submitted arb for confirmation tracking, never waits on the tracker.
+    pub fn track(&self, arb: SubmittedArb) {
+        match self.tx.try_send(arb) {
+            std::result::Result::Ok(()) => {}
+            Err(TrySendError::Full(arb)) => {
+                warn!("Confirmation queue full, not tracking {}", arb.signature);
+            }
+            Err(TrySendError::Closed(_)) => {
+                error!("Confirmation tracker stopped");
+            }
+        }
+    }
+}
+
+async fn track_loop(mut rx: mpsc::Receiver<SubmittedArb>) {
+    info!("Confirmation tracker started");
+
+    let rpc_client = global::get_rpc_client();
+    let mut pending: Vec<SubmittedArb> = Vec::new();
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        interval.tick().await;
+        loop {
+            match rx.try_recv() {
+                std::result::Result::Ok(arb) => pending.push(arb),
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    if pending.is_empty() {
+                        info!("Confirmation tracker stopped");
+                        return;
+                    }
+                    break;
+                }
+            }
+        }
+        if pending.is_empty() {
+            continue;
+        }
+
+        let mut still_pending = Vec::with_capacity(pending.len());
+        for batch in pending.chunks(STATUS_BATCH_SIZE) {
+            let signatures: Vec<Signature> = batch.iter().map(|arb| arb.signature).collect();
+            let statuses = match rpc_client.get_signature_statuses(&signatures).await {
+                std::result::Result::Ok(response) => response.value,
+                Err(e) => {
+                    warn!("Failed to fetch signature statuses {}", e);
+                    still_pending.extend_from_slice(batch);
+                    continue;
+                }
+            };
+
+            for (arb, status) in batch.iter().zip(statuses) {
+                let outcome = match status {
+                    Some(status) if status.err.is_some() => Some(RouteOutcome::Failed),
+                    Some(status) if status.satisfies_commitment(CommitmentConfig::confirmed()) => {
+                        Some(RouteOutcome::Landed)
+                    }
+                    _ if arb.submitted.elapsed() >= CONFIRMATION_TIMEOUT => {
+                        Some(RouteOutcome::Expired)
+                    }
+                    _ => None,
+                };
+
+                match outcome {
+                    Some(outcome) => {
+                        match outcome {
+                            // reverts are mostly lost races, they don't count against the pairs
+                            RouteOutcome::Failed => {
+                                if let Some(first_pool) = arb.pools.first() {
+                                    blacklist::record_failure(first_pool);
+                                }
+                            }
+                            RouteOutcome::Landed => pair_breaker::record_success(&arb.pairs),
+                            RouteOutcome::Expired => {}
+                        }
+                        metric::record_route(arb, outcome);
+                    }
+                    None => still_pending.push(arb.clone()),
+                }
+            }
+        }
+        pending = still_pending;
+    }
+}
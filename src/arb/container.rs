@@ -5,6 +5,7 @@ use std::{
     collections::BinaryHeap,
     sync::{Arc, OnceLock},
 };
+use tokio::time::Duration;
 
 #[derive(Clone)]
 pub struct RouteStore {
@@ -63,6 +64,38 @@ impl RouteStore {
         out
     }
 
+    /// Pop up to `n` routes highest weight first. Routes quoted more than `ttl` ago are dropped
+    /// first, the rest beyond `n` stay queued. Returns the routes and the expired ones.
+    pub fn pop_fresh(
+        &self,
+        n: usize,
+        ttl: Option<Duration>,
+    ) -> (Vec<ProfitableRoute>, Vec<ProfitableRoute>) {
+        let mut expired = Vec::new();
+        if let Some(ttl) = ttl {
+            let stale: Vec<u64> = self
+                .map
+                .iter()
+                .filter(|entry| entry.value().1.quote_time.elapsed() > ttl)
+                .map(|entry| *entry.key())
+                .collect();
+            for key in stale {
+                // a fresher quote may have replaced it since
+                let removed = self
+                    .map
+                    .remove_if(&key, |_, (_weight, route)| route.quote_time.elapsed() > ttl);
+                if let Some((_key, (_weight, route))) = removed {
+                    expired.push(route);
+                }
+            }
+        }
+        if !expired.is_empty() {
+            self.clean_weight();
+        }
+
+        (self.pop_top_n(n), expired)
+    }
+
     pub fn drain(&self, n: usize) -> Vec<ProfitableRoute> {
         let mut out = Vec::with_capacity(n);
         let mut old_heap = {
@@ -151,11 +184,20 @@ impl RouteContainer {
         RouteStore::global().insert(key, route.route.profit, route);
     }
 
+    /// Queue a route for sending, weighted by its profit in lamports less the priority fee it
+    /// will pay.
     #[inline]
     pub fn smart_insert(route: ProfitableRoute) {
         let key = route.route.to_mint_hash();
+        // the queue only takes routes whose profit can be priced in SOL
+        let Some(priority_fee) = crate::transaction::expected_priority_fee(&route.route) else {
+            return;
+        };
+        let Some(weight) = optimization::net_profit_lamports(&route.route, priority_fee) else {
+            return;
+        };
         // RouteStore::global().insert(key, _to_scaled(route.product), route);
-        RouteStore::global().smart_insert(key, route.route.profit, route);
+        RouteStore::global().smart_insert(key, weight, route);
     }
 
     #[inline]
@@ -173,6 +215,14 @@ impl RouteContainer {
         RouteStore::global().drain(n)
     }
 
+    #[inline]
+    pub fn pop_fresh(
+        n: usize,
+        ttl: Option<Duration>,
+    ) -> (Vec<ProfitableRoute>, Vec<ProfitableRoute>) {
+        RouteStore::global().pop_fresh(n, ttl)
+    }
+
     #[inline]
     pub fn count() -> usize {
         RouteStore::global().len()
@@ -188,3 +238,44 @@ impl RouteContainer {
         RouteStore::global().clean_weight();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(profit: i64, age_ms: u64) -> ProfitableRoute {
+        let quote_time = tokio::time::Instant::now() - Duration::from_millis(age_ms);
+        ProfitableRoute {
+            route: SwapRoutes {
+                routes: vec![],
+                profit,
+                amount_in: 0,
+                threshold: 0,
+                min_amount_out: 0,
+                mint: Pubkey::default(),
+            },
+            quote_time,
+            sent_time: quote_time,
+        }
+    }
+
+    #[test]
+    fn pop_fresh_drops_expired_and_keeps_the_rest_queued() {
+        let store = RouteStore::new();
+        for (key, (profit, age_ms)) in [(10, 0), (30, 5_000), (20, 0), (5, 0)].iter().enumerate() {
+            store.smart_insert(key as u64, *profit, route(*profit, *age_ms));
+        }
+
+        let (routes, expired) = store.pop_fresh(2, Some(Duration::from_millis(1_000)));
+        let profits: Vec<i64> = routes.iter().map(|r| r.route.profit).collect();
+        assert_eq!(profits, vec![20, 10]);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].route.profit, 30);
+
+        let (routes, expired) = store.pop_fresh(2, None);
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].route.profit, 5);
+        assert!(expired.is_empty());
+        assert_eq!(store.len(), 0);
+    }
+}
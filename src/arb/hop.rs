@@ -1,5 +1,11 @@
 use super::*;
-use crate::{pool_index::TokenPoolType, streaming};
+use crate::{arb::route::disconnected_hop, pool_index::TokenPoolType, streaming};
+use dashmap::DashSet;
+use once_cell::sync::Lazy;
+use tracing::warn;
+
+// routes already reported as malformed, the same route comes up again every pass
+static MALFORMED_ROUTES: Lazy<DashSet<u64>> = Lazy::new(DashSet::new);
 
 impl Hop {
     pub fn to_pool_type(&self) -> Option<PoolType> {
@@ -56,6 +62,8 @@ impl Hop {
 }
 
 impl Route {
+    /// The hops' pools, `None` when one isn't loaded or doesn't trade the mint the path reaches
+    /// it with.
     #[inline]
     pub fn to_vec_owned(&self) -> Option<Vec<PoolType>> {
         let mut pools = Vec::with_capacity(self.hops.len());
@@ -63,6 +71,17 @@ impl Route {
             let pool_type = hop.to_pool_type()?;
             pools.push(pool_type);
         }
+
+        if let Some(i) = disconnected_hop(&self.start, pools.iter().map(PoolType::get_mints)) {
+            if MALFORMED_ROUTES.insert(self.to_hash()) {
+                warn!(
+                    "Route skipped, pool {} at hop {} doesn't trade the mint the path reaches it with",
+                    pools[i].get_address(),
+                    i
+                );
+            }
+            return None;
+        }
         Some(pools)
     }
 }
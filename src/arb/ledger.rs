@@ -0,0 +1,244 @@
+use super::*;
+use crate::global;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Write},
+    sync::Mutex,
+};
+use tracing::warn;
+
+// serializes appends so concurrent fills don't interleave lines
+static LEDGER_LOCK: Mutex<()> = Mutex::new(());
+
+/// One simulated fill, stored as a JSON line in `bot.ledger_path`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fill {
+    pub timestamp: i64,
+    pub mint: String,
+    pub pools: Vec<String>,
+    pub dexes: Vec<String>,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub pnl: i64,
+}
+
+/// Skipped opportunities for one reason, `profit` sums what the skipped quotes promised.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedOpportunities {
+    pub count: u64,
+    pub profit: i64,
+}
+
+/// Opportunity counts of one session so far, appended to `bot.ledger_path` alongside the fills.
+/// The last record of a session holds its totals.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpportunityRecord {
+    // unix timestamp the session started at
+    pub session: i64,
+    pub timestamp: i64,
+    pub detected: u64,
+    pub submitted: u64,
+    pub landed: u64,
+    // skip reason -> skipped opportunities
+    pub skipped: BTreeMap<String, SkippedOpportunities>,
+}
+
+impl OpportunityRecord {
+    /// Same counts, whenever they were taken.
+    pub fn same_counts(&self, other: &OpportunityRecord) -> bool {
+        self.session == other.session
+            && self.detected == other.detected
+            && self.submitted == other.submitted
+            && self.landed == other.landed
+            && self.skipped == other.skipped
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LedgerLine {
+    Fill(Fill),
+    Opportunities(OpportunityRecord),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DexStats {
+    pub fills: u64,
+    pub wins: u64,
+    pub pnl: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LedgerSummary {
+    pub fills: u64,
+    pub wins: u64,
+    pub pnl: i64,
+    pub per_dex: HashMap<String, DexStats>,
+    // pnl per base mint, `pnl` adds up amounts of different mints
+    pub per_mint: HashMap<String, i64>,
+    // last opportunity record of every session
+    pub sessions: BTreeMap<i64, OpportunityRecord>,
+    // lines that didn't parse, e.g. one cut short by a crash, left out of the totals
+    pub bad_lines: u64,
+}
+
+impl LedgerSummary {
+    pub fn win_rate(&self) -> f64 {
+        if self.fills == 0 {
+            0.0
+        } else {
+            self.wins as f64 / self.fills as f64
+        }
+    }
+}
+
+pub fn record_fill(
+    route: &SwapRoutes,
+    amount_in: u64,
+    amount_out: u64,
+    timestamp: i64,
+) -> Result<()> {
+    let fill = Fill {
+        timestamp,
+        mint: route.mint.to_string(),
+        pools: route
+            .routes
+            .iter()
+            .map(|pool| pool.get_address().to_string())
+            .collect(),
+        dexes: route
+            .routes
+            .iter()
+            .map(|pool| format!("{:?}", pool.to_pool_type()))
+            .collect(),
+        amount_in,
+        amount_out,
+        pnl: amount_out as i64 - amount_in as i64,
+    };
+    append(&serde_json::to_string(&fill)?)
+}
+
+pub fn record_opportunities(record: &OpportunityRecord) -> Result<()> {
+    append(&serde_json::to_string(record)?)
+}
+
+fn append(line: &str) -> Result<()> {
+    let _guard = LEDGER_LOCK.lock().unwrap();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&global::get_config().bot.ledger_path)?;
+    writeln!(file, "{}", line)?;
+
+    Ok(())
+}
+
+/// Cumulative PnL, win rate and per-DEX stats over every fill in the ledger.
+/// A fill counts once for each distinct DEX on its route.
+/// Opportunity records are collected per session instead.
+pub fn summary() -> Result<LedgerSummary> {
+    // read without LEDGER_LOCK, appends aren't held up by a long ledger. A line still being
+    // written is skipped as a bad line
+    let file = match OpenOptions::new()
+        .read(true)
+        .open(&global::get_config().bot.ledger_path)
+    {
+        std::result::Result::Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(LedgerSummary::default());
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    summarize(BufReader::new(file))
+}
+
+fn summarize(reader: impl BufRead) -> Result<LedgerSummary> {
+    let mut summary = LedgerSummary::default();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fill = match serde_json::from_str(&line) {
+            std::result::Result::Ok(LedgerLine::Fill(fill)) => fill,
+            std::result::Result::Ok(LedgerLine::Opportunities(record)) => {
+                summary.sessions.insert(record.session, record);
+                continue;
+            }
+            Err(e) => {
+                warn!("Skipping ledger line {}: {}", index + 1, e);
+                summary.bad_lines += 1;
+                continue;
+            }
+        };
+        let win = fill.pnl > 0;
+
+        summary.fills += 1;
+        summary.pnl += fill.pnl;
+        if win {
+            summary.wins += 1;
+        }
+        *summary.per_mint.entry(fill.mint.clone()).or_default() += fill.pnl;
+
+        let mut dexes = fill.dexes.clone();
+        dexes.sort();
+        dexes.dedup();
+        for dex in dexes {
+            let stats = summary.per_dex.entry(dex).or_default();
+            stats.fills += 1;
+            stats.pnl += fill.pnl;
+            if win {
+                stats.wins += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_and_opportunity_records_share_the_ledger() {
+        let fill = r#"{"timestamp":1,"mint":"m","pools":[],"dexes":[],"amount_in":10,"amount_out":12,"pnl":2}"#;
+        assert!(matches!(
+            serde_json::from_str::<LedgerLine>(fill).unwrap(),
+            LedgerLine::Fill(fill) if fill.pnl == 2
+        ));
+
+        let record = OpportunityRecord {
+            session: 100,
+            detected: 5,
+            ..Default::default()
+        };
+        let line = serde_json::to_string(&record).unwrap();
+        assert!(matches!(
+            serde_json::from_str::<LedgerLine>(&line).unwrap(),
+            LedgerLine::Opportunities(record) if record.session == 100 && record.detected == 5
+        ));
+    }
+
+    #[test]
+    fn bad_lines_are_skipped_and_counted() {
+        let ledger = [
+            r#"{"timestamp":1,"mint":"m","pools":[],"dexes":["A"],"amount_in":10,"amount_out":12,"pnl":2}"#,
+            "not json",
+            r#"{"session":100,"timestamp":2,"detected":5,"submitted":1,"landed":0,"skipped":{}}"#,
+            // torn by a crash mid-append
+            r#"{"timestamp":3,"mint":"m","pools":[],"dex"#,
+        ]
+        .join("\n");
+
+        let summary = summarize(ledger.as_bytes()).unwrap();
+        assert_eq!(summary.fills, 1);
+        assert_eq!(summary.pnl, 2);
+        assert_eq!(summary.sessions[&100].detected, 5);
+        assert_eq!(summary.bad_lines, 2);
+    }
+}
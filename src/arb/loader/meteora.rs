@@ -32,18 +32,21 @@ impl MeteoraLoader {
         let accounts = rpc_client.get_multiple_accounts(&all_keys).await?;
 
         let mut iter = accounts.into_iter();
-        let mint_x_account = iter.next().unwrap().unwrap();
-        let mint_y_account = iter.next().unwrap().unwrap();
+        let mint_x_account = iter
+            .next()
+            .flatten()
+            .ok_or_else(|| anyhow::anyhow!("Mint {} not found", lb_pair.token_x_mint))?;
+        let mint_y_account = iter
+            .next()
+            .flatten()
+            .ok_or_else(|| anyhow::anyhow!("Mint {} not found", lb_pair.token_y_mint))?;
 
+        // Bin arrays that are not initialized yet are simply left out of the quote
         let bin_arrays = iter
             .zip(bin_array_pubkeys)
-            .map(|(account, key)| {
-                (
-                    key,
-                    BinArrayAccount::deserialize(&account.unwrap().data)
-                        .unwrap()
-                        .0,
-                )
+            .filter_map(|(account, key)| {
+                let bin_array = BinArrayAccount::deserialize(&account?.data).ok()?.0;
+                Some((key, bin_array))
             })
             .collect::<HashMap<_, _>>();
 
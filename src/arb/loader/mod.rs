@@ -12,3 +12,95 @@ pub mod vertigo;
 pub use vertigo::*;
 pub mod solfi;
 pub use solfi::*;
+
+/// Fetch a pool over RPC, picking the loader from the account owner.
+pub async fn load_pool(rpc_client: Arc<RpcClient>, pool_address: Pubkey) -> Result<PoolType> {
+    use crate::dex::{meteora, pumpfun, raydium, solfi, vertigo, whirlpool};
+    use crate::streaming::{AccountTypeInfo, parser};
+
+    let account = rpc_client.get_account(&pool_address).await?;
+    let owner = account.owner;
+
+    // a size mismatch means the program changed its layout, the pool would be misread
+    let expected_len = [
+        (meteora::dlmm::program_id(), AccountTypeInfo::DlmmPair),
+        (meteora::damm::program_id(), AccountTypeInfo::Dammv2Pool),
+        (raydium::amm::program_id(), AccountTypeInfo::RaydiumAmmPool),
+        (
+            raydium::cpmm::program_id(),
+            AccountTypeInfo::RaydiumCpmmPool,
+        ),
+        (
+            raydium::clmm::program_id(),
+            AccountTypeInfo::RaydiumClmmPool,
+        ),
+        (whirlpool::program_id(), AccountTypeInfo::Whirlpool),
+    ]
+    .into_iter()
+    .find_map(|(program, account_type)| (program == owner).then_some(account_type))
+    .and_then(parser::expected_pool_len);
+    if let Some(expected_len) = expected_len.filter(|len| *len != account.data.len()) {
+        return Err(anyhow::anyhow!(
+            "Pool {} is {} bytes, the layout owned by {} expects {}",
+            pool_address,
+            account.data.len(),
+            owner,
+            expected_len
+        ));
+    }
+
+    let pool = if owner == meteora::dlmm::program_id() {
+        PoolType::Meteora(
+            pool_address,
+            MeteoraLoader::load_dlmm(rpc_client, pool_address).await?,
+        )
+    } else if owner == meteora::damm::program_id() {
+        PoolType::MeteoraDammv2(
+            pool_address,
+            MeteoraLoader::load_damm(rpc_client, pool_address).await?,
+        )
+    } else if owner == pumpfun::program_id() {
+        PoolType::Pump(
+            pool_address,
+            PumpfunLoader::load_pumpfun(rpc_client, pool_address).await?,
+        )
+    } else if owner == raydium::amm::program_id() {
+        PoolType::RaydiumAmm(
+            pool_address,
+            RaydiumLoader::load_amm(rpc_client, pool_address).await?,
+        )
+    } else if owner == raydium::cpmm::program_id() {
+        PoolType::RaydiumCpmm(
+            pool_address,
+            RaydiumLoader::load_cpmm(rpc_client, pool_address).await?,
+        )
+    } else if owner == raydium::clmm::program_id() {
+        PoolType::RaydiumClmm(
+            pool_address,
+            RaydiumLoader::load_clmm(rpc_client, pool_address).await?,
+        )
+    } else if owner == whirlpool::program_id() {
+        PoolType::Whirlpool(
+            pool_address,
+            WhirlpoolLoader::load_whirlpool(rpc_client, pool_address).await?,
+        )
+    } else if owner == vertigo::program_id() {
+        PoolType::Vertigo(
+            pool_address,
+            VertigoLoader::load_vertigo(rpc_client, pool_address).await?,
+        )
+    } else if owner == solfi::program_id() {
+        PoolType::Solfi(
+            pool_address,
+            SolfiLoader::load_solfi(rpc_client, pool_address, &account.data).await?,
+        )
+    } else {
+        return Err(anyhow::anyhow!(
+            "Unsupported pool {} owned by {}",
+            pool_address,
+            owner
+        ));
+    };
+
+    Ok(pool)
+}
@@ -1,5 +1,5 @@
 use super::*;
-use crate::dex::raydium;
+use crate::{dex::raydium, global};
 
 pub struct RaydiumLoader;
 
@@ -25,10 +25,23 @@ impl RaydiumLoader {
         rpc_client: Arc<RpcClient>,
         pool_address: Pubkey,
     ) -> Result<RaydiumCpmmData> {
-        let pool_state =
+        let mut pool_state =
             raydium::cpmm::util::fetch_pool_state(rpc_client.clone(), &pool_address).await?;
-        let pool_reserves =
+        let mut pool_reserves =
             raydium::cpmm::util::fetch_pool_reserves(rpc_client.clone(), &pool_state).await?;
+        // the pool and vault reads can straddle a swap, read both again once
+        if !pool_state.is_consistent_with(&pool_reserves) {
+            pool_state =
+                raydium::cpmm::util::fetch_pool_state(rpc_client.clone(), &pool_address).await?;
+            pool_reserves =
+                raydium::cpmm::util::fetch_pool_reserves(rpc_client.clone(), &pool_state).await?;
+            if !pool_state.is_consistent_with(&pool_reserves) {
+                return Err(anyhow::anyhow!(
+                    "Pool {}: fees exceed vault amounts",
+                    pool_address
+                ));
+            }
+        }
         let amm_config =
             raydium::cpmm::util::fetch_amm_config_state(rpc_client, &pool_state.amm_config).await?;
 
@@ -52,23 +65,25 @@ impl RaydiumLoader {
             raydium::clmm::util::fetch_bitmap_extension_state(rpc_client.clone(), &bitmap_ext)
                 .await?;
 
-        let left_ticks = raydium::clmm::swap_util::load_cur_and_next_five_tick_array(
+        let left_ticks = raydium::clmm::swap_util::load_cur_and_next_tick_arrays(
             rpc_client.clone(),
             pool_address,
             &pool_state,
             &bitmap_state,
             false,
+            global::get_clmm_tick_arrays(),
         )
-        .await;
+        .await?;
 
-        let right_ticks = raydium::clmm::swap_util::load_cur_and_next_five_tick_array(
+        let right_ticks = raydium::clmm::swap_util::load_cur_and_next_tick_arrays(
             rpc_client,
             pool_address,
             &pool_state,
             &bitmap_state,
             true,
+            global::get_clmm_tick_arrays(),
         )
-        .await;
+        .await?;
 
         Ok(RaydiumClmmData {
             pool_address,
@@ -4,12 +4,14 @@ use crate::dex::solfi;
 pub struct SolfiLoader;
 
 impl SolfiLoader {
+    /// Build a pool from the market account `load_pool` already fetched. The vaults are ATAs of
+    /// the mints stored in that account, so they can only be read once it is in hand.
     pub async fn load_solfi(
         rpc_client: Arc<RpcClient>,
         pool_address: Pubkey,
+        pool_data: &[u8],
     ) -> Result<SolfiData> {
-        let pool_state =
-            solfi::fetch_and_deserialize_pool(rpc_client.clone(), &pool_address).await?;
+        let pool_state = solfi::Pool::deserialize(&pool_address, pool_data)?;
         let vaults = pool_state.fetch_vaults(rpc_client).await?;
 
         Ok(SolfiData {
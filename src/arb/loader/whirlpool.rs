@@ -1,5 +1,5 @@
 use super::*;
-use crate::dex::whirlpool;
+use crate::{dex::whirlpool, global};
 
 pub struct WhirlpoolLoader;
 
@@ -14,8 +14,13 @@ impl WhirlpoolLoader {
         let oracle =
             whirlpool::util::fetch_and_deserialize_oracle(rpc_client.clone(), &pool_address).await;
         let tick_data: [(Pubkey, whirlpool::state::TickArray); 5] =
-            whirlpool::util::fetch_tick_arrays_or_default(rpc_client, pool_address, &pool_state)
-                .await?;
+            whirlpool::util::fetch_tick_arrays_or_default(
+                rpc_client,
+                pool_address,
+                &pool_state,
+                global::get_whirlpool_tick_arrays(),
+            )
+            .await?;
 
         Ok(WhirlpoolData {
             pool_address,
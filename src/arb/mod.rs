@@ -20,7 +20,15 @@ mod hop;
 mod pool_type;
 mod swap_math;
 pub use swap_math::*;
+mod swap_quote;
+pub use swap_quote::*;
+pub mod alert;
 pub mod ata_worker;
+pub mod blacklist;
+pub mod confirmation;
 pub mod container;
+pub mod ledger;
+pub mod pair_breaker;
 pub mod queue_sender;
 pub mod route;
+pub mod transfer_fee;
@@ -22,7 +22,7 @@ pub fn profitable_route(
         if x_u64 == 0 {
             return BAD;
         }
-        swap_compute(clock, &pool_vec, x_u64, &token, adjust_slippage).unwrap_or(BAD)
+        estimate_swap_compute(clock, &pool_vec, x_u64, &token, adjust_slippage).unwrap_or(BAD)
     };
 
     // ---- Brent's method (maximize) on [a, b] ----
@@ -52,11 +52,16 @@ pub fn profitable_route(
     let mut d: f64 = 0.0;
     let mut e: f64 = 0.0;
 
+    // x always holds the best amount seen so far, so stopping on the cap is safe
+    let max_iters = global::get_max_optimizer_iters();
+    let mut iters = 0usize;
+
     loop {
         let m = 0.5 * (a + b);
-        if (b - a) <= tol {
+        if (b - a) <= tol || iters >= max_iters {
             break;
         }
+        iters += 1;
 
         let mut u;
         let mut ok_parabolic = false;
@@ -166,9 +171,13 @@ pub fn profitable_route(
         // d không cần thiết ở bản rời rạc, giữ lại nếu bạn muốn logging
         d = e;
     }
+    metric::BRENT_ITERATIONS.record(iters, (b - a) > tol);
 
-    let optimal_amount_in =
-        adjust_amount_in(x.round().clamp(min_amount_in as f64, max_amount_in as f64) as u64);
+    let optimal_amount_in = round_to_lot(
+        pool_vec.first(),
+        &token,
+        adjust_amount_in(x.round().clamp(min_amount_in as f64, max_amount_in as f64) as u64),
+    );
     let final_profit = eval(optimal_amount_in);
 
     if final_profit <= 0 {
@@ -176,12 +185,14 @@ pub fn profitable_route(
     }
 
     let (amount_in, threshold) = compute_threshold(&route.hops[0], optimal_amount_in)?;
+    let min_out = route_min_amount_out(clock, &pool_vec, optimal_amount_in, &token)?;
 
     Some(SwapRoutes {
         routes: pool_vec,
         profit: final_profit,
         amount_in: amount_in,
         threshold: threshold,
+        min_amount_out: min_out,
         mint: route.start,
     })
 }
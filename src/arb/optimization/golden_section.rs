@@ -38,13 +38,13 @@ pub fn profitable_route(
         }
     }
 
-    let mut fc =
-        swap_compute(clock, &pool_vec, c, &token, adjust_slippage).unwrap_or(negative_u64(b));
-    let mut fd =
-        swap_compute(clock, &pool_vec, d, &token, adjust_slippage).unwrap_or(negative_u64(b));
+    let mut fc = estimate_swap_compute(clock, &pool_vec, c, &token, adjust_slippage)
+        .unwrap_or(negative_u64(b));
+    let mut fd = estimate_swap_compute(clock, &pool_vec, d, &token, adjust_slippage)
+        .unwrap_or(negative_u64(b));
 
     let mut iters = 0usize;
-    let max_iters = 128; 
+    let max_iters = global::get_max_optimizer_iters();
     // Loop until b - a <= epsilon
     while b - a > epsilon && iters < max_iters {
         iters += 1;
@@ -59,7 +59,7 @@ pub fn profitable_route(
             if d <= c {
                 d = (c + 1).min(b);
             }
-            fd = swap_compute(clock, &pool_vec, d, &token, adjust_slippage)
+            fd = estimate_swap_compute(clock, &pool_vec, d, &token, adjust_slippage)
                 .unwrap_or(negative_u64(b));
         } else {
             // Use the left to d
@@ -72,7 +72,7 @@ pub fn profitable_route(
             if c >= d {
                 c = d.saturating_sub(1).max(a);
             }
-            fc = swap_compute(clock, &pool_vec, c, &token, adjust_slippage)
+            fc = estimate_swap_compute(clock, &pool_vec, c, &token, adjust_slippage)
                 .unwrap_or(negative_u64(b));
         }
 
@@ -81,17 +81,28 @@ pub fn profitable_route(
         }
     }
 
-    let optimal_amount_in = adjust_amount_in(a);
+    // stopped on the cap before converging, take the better of the two probes
+    let capped = b > a && b - a > epsilon;
+    metric::GOLDEN_SECTION_ITERATIONS.record(iters, capped);
+    let best = match (capped, fc >= fd) {
+        (false, _) => a,
+        (true, true) => c,
+        (true, false) => d,
+    };
+
+    let optimal_amount_in = round_to_lot(pool_vec.first(), &token, adjust_amount_in(best));
     let final_profit =
-        swap_compute(clock, &pool_vec, optimal_amount_in, &token, false).unwrap_or(-1);
+        estimate_swap_compute(clock, &pool_vec, optimal_amount_in, &token, false).unwrap_or(-1);
 
     let (amount_in, threshold) = compute_threshold(&route.hops[0], optimal_amount_in)?;
+    let min_out = route_min_amount_out(clock, &pool_vec, optimal_amount_in, &token)?;
 
     Some(SwapRoutes {
         routes: pool_vec,
         profit: final_profit,
         amount_in: amount_in,
         threshold: threshold,
+        min_amount_out: min_out,
         mint: route.start,
     })
 }
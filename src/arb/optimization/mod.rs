@@ -1,9 +1,12 @@
 use super::*;
 use crate::{
     global::{self, get_config},
-    math,
+    instructions::flashloan,
+    math, metric,
     pool_index::TokenPoolType,
-    dex::pumpfun::quote,
+    dex::pumpfun::{fee_bps, quote},
+    util::amount_with_slippage,
+    wsol_mint,
 };
 
 pub mod brent_method;
@@ -16,14 +19,15 @@ pub fn compute_threshold(first_hop: &Hop, amount_in: u64) -> Option<(u64, u64)>
             if let Some(pool_type) = first_hop.to_pool_type() {
                 match pool_type {
                     PoolType::Pump(_, ref data) => {
+                        let fees = fee_bps();
                         let buy_quote = quote::buy_quote_input_internal(
                             amount_in as u128,
                             1.0f64,
                             data.reserves.base_amount as u128,
                             data.reserves.quote_amount as u128,
-                            20,
-                            5,
-                            80,
+                            fees.lp,
+                            fees.protocol,
+                            fees.coin_creator,
                             data.pool.coin_creator,
                         )
                         .ok()?;
@@ -41,11 +45,46 @@ pub fn compute_threshold(first_hop: &Hop, amount_in: u64) -> Option<(u64, u64)>
     Some((final_amount_in, threshold))
 }
 
+/// Least the route may return on chain: the quoted output of `amount_in`, `profit` over it,
+/// less `slippage_bps`. It is the aggregator's threshold, so the check the program runs is the
+/// one simulated here.
+pub fn min_amount_out(amount_in: u64, profit: i64, slippage_bps: u64) -> Option<u64> {
+    let expected_out = u64::try_from(amount_in as i128 + profit as i128).ok()?;
+    amount_with_slippage(expected_out, slippage_bps, false).ok()
+}
+
+/// `min_amount_out` of `amount_in` through `pools` as the program pays it out. The optimizers
+/// pick the amount and report its profit on the pools' estimates, which can be a unit above
+/// the program's rounding per hop, so the threshold is quoted again exactly.
+pub fn route_min_amount_out(
+    clock: &Clock,
+    pools: &[PoolType],
+    amount_in: u64,
+    token: &Pubkey,
+) -> Option<u64> {
+    let profit = swap_compute(clock, pools, amount_in, token, false).ok()?;
+    min_amount_out(amount_in, profit, global::get_slippage_bps())
+}
+
 pub fn adjust_amount_in(amount_in: u64) -> u64 {
     let percent = get_config().bot.optimization_amount_percent as u64;
     (amount_in / 100) * percent
 }
 
+/// Round down to the first hop's lot so the on-chain swap sees the amount we quoted.
+pub fn round_to_lot(first_pool: Option<&PoolType>, mint_in: &Pubkey, amount_in: u64) -> u64 {
+    let lot_size = match first_pool {
+        Some(PoolType::RaydiumAmm(_, data)) => data.pool_state.lot_size(mint_in),
+        _ => return amount_in,
+    };
+
+    if lot_size <= 1 {
+        amount_in
+    } else {
+        amount_in - amount_in % lot_size
+    }
+}
+
 pub fn profitable_route(
     route: Route,
     clock: &Clock,
@@ -97,9 +136,45 @@ pub fn profitable_route(
     }
 }
 
+/// Profit of `swap` in lamports after `fee_lamports`. Routes based on another mint have their
+/// profit converted at the cached SOL price of the deepest pool of the pair first, so they are
+/// weighed against the same lamport costs as wSOL routes. `None` when the base mint has no
+/// priced pool against SOL, its profit can't be weighed then.
+pub fn net_profit_lamports(swap: &SwapRoutes, fee_lamports: u64) -> Option<i64> {
+    let profit = metric::to_accounting_mint(swap.profit, &swap.mint, &wsol_mint())?;
+    Some(profit.saturating_sub(fee_lamports as i64))
+}
+
+/// What the wallet can put into a route: the base mint balance, plus the native SOL above the
+/// fee reserve when wSOL routes wrap it.
+fn available_capital(base_amount: u64, native: Option<u64>, fee_reserve: u64) -> u64 {
+    match native {
+        Some(lamports) => base_amount.saturating_add(lamports.saturating_sub(fee_reserve)),
+        None => base_amount,
+    }
+}
+
+/// Without a flashloan reserve for the base mint we can only trade what the wallet holds.
+fn cap_to_balance(max_amount_in: u64) -> u64 {
+    let base_mint = global::get_base_mint();
+    if flashloan::kamino::find_reserve(&base_mint).is_some() {
+        return max_amount_in;
+    }
+
+    let native = global::wraps_native_sol().then(global::get_native_lamports);
+    max_amount_in.min(available_capital(
+        global::get_base_mint_amount(),
+        native,
+        global::get_sol_fee_reserve_lamports(),
+    ))
+}
+
 pub fn find_profitable_route(route: Route, clock: &Clock) -> Option<SwapRoutes> {
     let min_amount_in = 50_000;
-    let max_amount_in = 100_000_000_000;
+    let max_amount_in = cap_to_balance(100_000_000_000);
+    if max_amount_in <= min_amount_in {
+        return None;
+    }
     let epsilon = 100_000;
     let enabled_slippage = global::enabled_slippage();
     profitable_route(
@@ -111,3 +186,24 @@ pub fn find_profitable_route(route: Route, clock: &Clock) -> Option<SwapRoutes>
         enabled_slippage,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn available_capital_adds_native_sol_above_the_reserve() {
+        // without auto-wrap only the base mint balance counts
+        assert_eq!(available_capital(1_000_000, None, 50_000_000), 1_000_000);
+        assert_eq!(
+            available_capital(1_000_000, Some(80_000_000), 50_000_000),
+            31_000_000
+        );
+        // native SOL within the reserve adds nothing
+        assert_eq!(
+            available_capital(1_000_000, Some(20_000_000), 50_000_000),
+            1_000_000
+        );
+        assert_eq!(available_capital(u64::MAX, Some(u64::MAX), 0), u64::MAX);
+    }
+}
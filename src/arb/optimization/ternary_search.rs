@@ -21,7 +21,9 @@ pub fn profitable_route(
     let mut a = min_amount_in;
     let mut b = max_amount_in;
     let mut iter = 0usize;
-    let max_iter = 200;
+    let max_iter = global::get_max_optimizer_iters();
+    // best probe so far, used when the cap stops the search early
+    let mut best = (a, i64::MIN);
 
     while a < b && b - a > epsilon && iter < max_iter {
         // third = floor((b - a) / 3)
@@ -39,9 +41,15 @@ pub fn profitable_route(
         }
 
         let f1 =
-            swap_compute(clock, &pool_vec, m1, &token, adjust_slippage).unwrap_or(negative_u64(min_amount_in));
+            estimate_swap_compute(clock, &pool_vec, m1, &token, adjust_slippage).unwrap_or(negative_u64(min_amount_in));
         let f2 =
-            swap_compute(clock, &pool_vec, m2, &token, adjust_slippage).unwrap_or(negative_u64(min_amount_in));
+            estimate_swap_compute(clock, &pool_vec, m2, &token, adjust_slippage).unwrap_or(negative_u64(min_amount_in));
+
+        for (m, f) in [(m1, f1), (m2, f2)] {
+            if f > best.1 {
+                best = (m, f);
+            }
+        }
 
         // If f1 < f2, the max is right m1 => drop [a, m1]
         // else [m2, b]
@@ -54,17 +62,23 @@ pub fn profitable_route(
         iter += 1;
     }
 
-    let optimal_amount_in = adjust_amount_in(a);
+    let capped = iter >= max_iter && a < b && b - a > epsilon;
+    metric::TERNARY_SEARCH_ITERATIONS.record(iter, capped);
+    let best = if capped { best.0 } else { a };
+
+    let optimal_amount_in = round_to_lot(pool_vec.first(), &token, adjust_amount_in(best));
     let final_profit =
-        swap_compute(clock, &pool_vec, optimal_amount_in, &token, false).unwrap_or(-1);
+        estimate_swap_compute(clock, &pool_vec, optimal_amount_in, &token, false).unwrap_or(-1);
 
     let (amount_in, threshold) = compute_threshold(&route.hops[0], optimal_amount_in)?;
+    let min_out = route_min_amount_out(clock, &pool_vec, optimal_amount_in, &token)?;
 
     Some(SwapRoutes {
         routes: pool_vec,
         profit: final_profit,
         amount_in,
         threshold,
+        min_amount_out: min_out,
         mint: route.start,
     })
 }
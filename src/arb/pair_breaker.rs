@@ -0,0 +1,195 @@
+use crate::{
+    arb::{PoolType, Route},
+    global, wsol_mint,
+};
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Mints of a pool, in ascending order.
+pub type MintPair = (Pubkey, Pubkey);
+
+// pair -> simulations in a row of routes trading it that drifted from the quote
+static FAILURES: Lazy<DashMap<MintPair, u32>> = Lazy::new(DashMap::new);
+// pair -> time route building resumes for it
+static PAUSED: Lazy<DashMap<MintPair, Instant>> = Lazy::new(DashMap::new);
+
+fn mint_pair(mint_a: Pubkey, mint_b: Pubkey) -> MintPair {
+    if mint_a < mint_b {
+        (mint_a, mint_b)
+    } else {
+        (mint_b, mint_a)
+    }
+}
+
+/// Distinct pairs the pools of a quoted route trade.
+pub fn route_pairs(pools: &[PoolType]) -> Vec<MintPair> {
+    let mut pairs: Vec<MintPair> = pools
+        .iter()
+        .map(|pool| {
+            let (mint_a, mint_b) = pool.get_mints();
+            mint_pair(mint_a, mint_b)
+        })
+        .collect();
+    pairs.sort();
+    pairs.dedup();
+    pairs
+}
+
+/// Pairs a failure counts against. Pairs of two base mints (wSOL/USDC) sit on most routes, a
+/// pause there would stop nearly everything, so they are never counted.
+fn counted_pairs(pairs: &[MintPair], base_mints: &[Pubkey]) -> Vec<MintPair> {
+    pairs
+        .iter()
+        .filter(|(mint_a, mint_b)| !(base_mints.contains(mint_a) && base_mints.contains(mint_b)))
+        .copied()
+        .collect()
+}
+
+/// Count one outcome against every pair of a route, a success clears their streaks. Returns
+/// the pairs that reached `threshold` failures in a row, they are paused until `now + cooldown`.
+fn record_outcome(
+    pairs: &[MintPair],
+    failed: bool,
+    threshold: u32,
+    cooldown: Duration,
+    now: Instant,
+) -> Vec<(MintPair, u32)> {
+    let mut tripped = Vec::new();
+    for pair in pairs {
+        if !failed {
+            FAILURES.remove(pair);
+            continue;
+        }
+
+        let failures = {
+            let mut failures = FAILURES.entry(*pair).or_insert(0);
+            *failures += 1;
+            *failures
+        };
+        if failures >= threshold {
+            FAILURES.remove(pair);
+            PAUSED.insert(*pair, now + cooldown);
+            tripped.push((*pair, failures));
+        }
+    }
+    tripped
+}
+
+fn record(pairs: &[MintPair], failed: bool) {
+    let threshold = global::get_pair_failure_threshold();
+    if threshold == 0 {
+        return;
+    }
+
+    let mut base_mints = global::get_base_mints().unwrap_or_default();
+    base_mints.push(wsol_mint());
+    let pairs = counted_pairs(pairs, &base_mints);
+    let cooldown = Duration::from_secs(global::get_pair_cooldown_secs());
+    for ((mint_a, mint_b), failures) in
+        record_outcome(&pairs, failed, threshold, cooldown, Instant::now())
+    {
+        warn!(
+            "Paused routes through pair {}/{} for {:?} after {} simulations in a row drifted from the quote",
+            mint_a, mint_b, cooldown, failures
+        );
+    }
+}
+
+/// A route through `pairs` failed its simulation or simulated short of the quote, the data it
+/// was quoted on is stale or wrong. Lost races and local rejects don't count.
+pub fn record_failure(pairs: &[MintPair]) {
+    record(pairs, true);
+}
+
+/// A route through `pairs` went out, or landed when confirmations are tracked.
+pub fn record_success(pairs: &[MintPair]) {
+    record(pairs, false);
+}
+
+fn is_paused(pair: &MintPair) -> bool {
+    let Some(until) = PAUSED.get(pair).map(|entry| *entry.value()) else {
+        return false;
+    };
+    if Instant::now() < until {
+        return true;
+    }
+
+    if PAUSED.remove(pair).is_some() {
+        info!("Routes through pair {}/{} resumed", pair.0, pair.1);
+    }
+    false
+}
+
+/// Whether any hop of `route` trades a paused pair, such routes aren't quoted.
+pub fn route_paused(route: &Route) -> bool {
+    if PAUSED.is_empty() {
+        return false;
+    }
+
+    route
+        .hops
+        .iter()
+        .any(|hop| is_paused(&mint_pair(hop.from, hop.to)))
+}
+
+/// Paused pairs and how long each stays paused.
+pub fn entries() -> Vec<(MintPair, Duration)> {
+    let now = Instant::now();
+    PAUSED
+        .iter()
+        .filter(|entry| *entry.value() > now)
+        .map(|entry| (*entry.key(), entry.value().duration_since(now)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pauses_after_consecutive_failures() {
+        let pair = mint_pair(Pubkey::new_unique(), Pubkey::new_unique());
+        let other = mint_pair(Pubkey::new_unique(), Pubkey::new_unique());
+        let cooldown = Duration::from_secs(60);
+        let now = Instant::now();
+
+        assert!(record_outcome(&[pair, other], true, 3, cooldown, now).is_empty());
+        assert!(record_outcome(&[pair, other], true, 3, cooldown, now).is_empty());
+        // a success on `other` restarts its streak, `pair` keeps counting
+        assert!(record_outcome(&[other], false, 3, cooldown, now).is_empty());
+        assert_eq!(
+            record_outcome(&[pair, other], true, 3, cooldown, now),
+            vec![(pair, 3)]
+        );
+
+        assert!(is_paused(&pair));
+        assert!(!is_paused(&other));
+        assert_eq!(*PAUSED.get(&pair).unwrap(), now + cooldown);
+    }
+
+    #[test]
+    fn pairs_of_two_base_mints_are_never_counted() {
+        let (wsol, usdc, token) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let hub = mint_pair(wsol, usdc);
+        let pairs = [hub, mint_pair(wsol, token), mint_pair(usdc, token)];
+
+        assert_eq!(counted_pairs(&pairs, &[wsol, usdc]), pairs[1..].to_vec());
+        assert_eq!(counted_pairs(&pairs, &[wsol]), pairs.to_vec());
+    }
+
+    #[test]
+    fn pause_expires_after_the_cooldown() {
+        let pair = mint_pair(Pubkey::new_unique(), Pubkey::new_unique());
+        record_outcome(&[pair], true, 1, Duration::ZERO, Instant::now());
+
+        assert!(!is_paused(&pair));
+        assert!(!PAUSED.contains_key(&pair));
+    }
+}
@@ -1,328 +1,132 @@
 use super::*;
 use crate::{
     arb::PoolType,
-    dex::{meteora, pumpfun, raydium, whirlpool},
+    math::ONE_Q64,
     pool_index::TokenPoolType,
-    streaming::global_data,
-    wsol_mint,
+    streaming::{global_data, price},
 };
 use anchor_client::solana_sdk::{clock::Clock, pubkey::Pubkey};
-use anyhow::Result;
-use commons::quote as dlmm_quote;
+use anyhow::{Result, anyhow};
 use std::panic::{AssertUnwindSafe, catch_unwind};
 
 impl PoolType {
-    // return price and quote_mint
-    pub fn get_price(&self, base_mint: &Pubkey) -> (f64, &Pubkey) {
+    // return price and quote_mint, None when the spot price isn't finite and positive
+    #[inline]
+    pub fn get_price(&self, base_mint: &Pubkey) -> Option<(f64, Pubkey)> {
+        let (price, quote_mint) = self.spot_price(base_mint);
+        Some((price::sanitize(price)?, quote_mint))
+    }
+
+    // return reserves in the same order as get_mints
+    pub fn get_reserves(&self) -> (f64, f64) {
         match self {
             PoolType::Meteora(_, data) => {
-                let price =
-                    meteora::utils::compute_price(data.lb_pair.active_id, data.lb_pair.bin_step);
-                if &data.lb_pair.token_x_mint == base_mint {
-                    (price, &data.lb_pair.token_y_mint)
-                } else {
-                    (1.0 / price, &data.lb_pair.token_x_mint)
-                }
-            }
-            PoolType::Pump(_, data) => {
-                let base_amount = data.reserves.base_amount;
-                let quote = data.reserves.quote_amount as f64;
-                let base = base_amount as f64;
-
-                if &data.pool.base_mint == base_mint {
-                    (quote / base, &data.pool.quote_mint)
-                } else {
-                    (base / quote, &data.pool.base_mint)
+                let mut amount_x = 0f64;
+                let mut amount_y = 0f64;
+                for bin_array in data.bin_arrays.values() {
+                    for bin in bin_array.bins.iter() {
+                        amount_x += bin.amount_x as f64;
+                        amount_y += bin.amount_y as f64;
+                    }
                 }
+                (amount_x, amount_y)
             }
+            PoolType::Pump(_, data) => (
+                data.reserves.base_amount as f64,
+                data.reserves.quote_amount as f64,
+            ),
             PoolType::MeteoraDammv2(_, data) => {
-                let price = data.pool_state.get_price();
-                if &data.pool_state.token_a_mint == base_mint {
-                    (price, &data.pool_state.token_b_mint)
-                } else {
-                    (1.0 / price, &data.pool_state.token_a_mint)
-                }
-            }
-            PoolType::RaydiumAmm(_, data) => {
-                let pc_vault = data.vaults.pc_vault_amount as f64;
-                let coin_vault = data.vaults.coin_vault_amount as f64;
-
-                if &data.pool_state.coin_mint == base_mint {
-                    (pc_vault / coin_vault, &data.pool_state.pc_mint)
-                } else {
-                    (coin_vault / pc_vault, &data.pool_state.coin_mint)
-                }
-            }
-            PoolType::RaydiumCpmm(_, data) => {
-                let token_0_amount = data.vaults.token_0_amount as f64;
-                let token_1_amount = data.vaults.token_1_amount as f64;
-
-                if &data.pool_state.token_0_mint == base_mint {
-                    (
-                        token_1_amount / token_0_amount,
-                        &data.pool_state.token_1_mint,
-                    )
-                } else {
-                    (
-                        token_0_amount / token_1_amount,
-                        &data.pool_state.token_0_mint,
-                    )
-                }
+                virtual_reserves(data.pool_state.liquidity, data.pool_state.sqrt_price)
             }
+            PoolType::RaydiumAmm(_, data) => (
+                data.vaults.pc_vault_amount as f64,
+                data.vaults.coin_vault_amount as f64,
+            ),
+            PoolType::RaydiumCpmm(_, data) => (
+                data.vaults.token_0_amount as f64,
+                data.vaults.token_1_amount as f64,
+            ),
             PoolType::RaydiumClmm(_, data) => {
-                // price token 1 / token 0
-                let price = data.pool_state.get_price();
-                if &data.pool_state.token_mint_0 == base_mint {
-                    (price, &data.pool_state.token_mint_1)
-                } else {
-                    (1.0 / price, &data.pool_state.token_mint_0)
-                }
+                virtual_reserves(data.pool_state.liquidity, data.pool_state.sqrt_price_x64)
             }
             PoolType::Whirlpool(_, data) => {
-                // price token 1 / token 0
-                let price = data.pool_state.get_price();
-                if &data.pool_state.token_mint_a == base_mint {
-                    (price, &data.pool_state.token_mint_b)
-                } else {
-                    (1.0 / price, &data.pool_state.token_mint_a)
-                }
-            }
-            PoolType::Vertigo(_, data) => {
-                if &data.pool_state.mint_a == base_mint {
-                    (data.pool_state.get_price_a_in_b(), &data.pool_state.mint_b)
-                } else {
-                    (data.pool_state.get_price_b_in_a(), &data.pool_state.mint_a)
-                }
-            }
-            PoolType::Solfi(_, data) => {
-                if &data.pool_state.mint_a == base_mint {
-                    (data.reserves.get_price_a_in_b(), &data.pool_state.mint_b)
-                } else {
-                    (data.reserves.get_price_b_in_a(), &data.pool_state.mint_a)
-                }
+                virtual_reserves(data.pool_state.liquidity, data.pool_state.sqrt_price)
             }
+            PoolType::Vertigo(_, data) => (
+                data.pool_state.token_a_reserves as f64,
+                data.pool_state.token_b_reserves as f64,
+            ),
+            PoolType::Solfi(_, data) => (
+                data.reserves.vault_a_amount as f64,
+                data.reserves.vault_b_amount as f64,
+            ),
         }
     }
 
-    #[inline]
-    pub fn compute_price(&self, mint_in: &Pubkey, amount_in: u64) -> (f64, u64) {
-        let clock = match global_data::get_clock() {
-            Some(c) => c,
-            None => return (0.0, 0),
+    // both sides of the pool valued in base_mint using the pool's own spot price
+    pub fn estimated_tvl(&self, base_mint: &Pubkey) -> f64 {
+        let (mint_a, mint_b) = self.get_mints();
+        let (reserve_a, reserve_b) = self.get_reserves();
+        let Some((price, _)) = self.get_price(base_mint) else {
+            return 0.0;
         };
 
-        let amount_out: u64 = catch_unwind(AssertUnwindSafe(|| {
-            self.compute_swap(&clock, mint_in, amount_in)
-        }))
-        .ok()
-        .and_then(|r| r.ok())
-        .map(|v| v.max(0) as u64)
-        .unwrap_or(0);
+        let tvl = if &mint_a == base_mint {
+            reserve_a + reserve_b / price
+        } else if &mint_b == base_mint {
+            reserve_b + reserve_a / price
+        } else {
+            0.0
+        };
+
+        if tvl.is_finite() { tvl } else { 0.0 }
+    }
+
+    #[inline]
+    pub fn compute_price(&self, mint_in: &Pubkey, amount_in: u64) -> (f64, u64) {
+        // no clock yet quotes as 0 like any other failed quote
+        let amount_out: u64 =
+            catch_unwind(AssertUnwindSafe(|| self.compute_swap(mint_in, amount_in)))
+                .ok()
+                .and_then(|r| r.ok())
+                .map(|v| v.max(0) as u64)
+                .unwrap_or(0);
 
         (amount_out as f64 / amount_in as f64, amount_out)
     }
 
-    pub fn compute_swap(
+    /// Quote `current_amount` of `mint_in` at the latest streamed clock.
+    pub fn compute_swap(&self, mint_in: &Pubkey, current_amount: u64) -> Result<u64> {
+        let clock = global_data::get_clock().ok_or_else(|| anyhow!("Clock not loaded yet"))?;
+        self.compute_swap_at(&clock, mint_in, current_amount)
+    }
+
+    /// Quote at an explicit clock, e.g. a historical slot and timestamp when replaying a route.
+    /// Errors when the pool doesn't trade `mint_in`, rather than quoting some direction.
+    pub fn compute_swap_at(
         &self,
         clock: &Clock,
         mint_in: &Pubkey,
         current_amount: u64,
     ) -> Result<u64> {
-        let current_timestamp = clock.unix_timestamp as u64;
-        let current_slot = clock.slot;
-
-        let (amount_out, _mint_out) = match self {
-            PoolType::Pump(_, data) => {
-                if mint_in != &wsol_mint() {
-                    let sell_quote = pumpfun::quote::sell_base_input_internal(
-                        current_amount as u128,
-                        0f64,
-                        data.reserves.base_amount as u128,
-                        data.reserves.quote_amount as u128,
-                        20,
-                        5,
-                        80,
-                        data.pool.coin_creator,
-                    )?;
-
-                    (sell_quote.min_quote as u64, &data.pool.quote_mint)
-                } else {
-                    let buy_quote = pumpfun::quote::buy_quote_input_internal(
-                        current_amount as u128,
-                        0f64,
-                        data.reserves.base_amount as u128,
-                        data.reserves.quote_amount as u128,
-                        20,
-                        5,
-                        80,
-                        data.pool.coin_creator,
-                    )?;
-
-                    (buy_quote.base as u64, &data.pool.base_mint)
-                }
-            }
-            PoolType::Meteora(address, data) => {
-                let quote = dlmm_quote::quote_exact_in(
-                    *address,
-                    &data.lb_pair,
-                    current_amount,
-                    &data.lb_pair.token_y_mint != mint_in,
-                    data.bin_arrays.clone(),
-                    None,
-                    clock,
-                    &data.mint_x_account,
-                    &data.mint_y_account,
-                )?;
-
-                let token_out_mint = if &data.lb_pair.token_x_mint == mint_in {
-                    &data.lb_pair.token_y_mint
-                } else {
-                    &data.lb_pair.token_x_mint
-                };
-
-                (quote.amount_out, token_out_mint)
-            }
-            PoolType::MeteoraDammv2(_, data) => {
-                let quote = meteora::damm::get_quote(
-                    &data.pool_state,
-                    current_timestamp,
-                    current_slot,
-                    current_amount,
-                    &data.pool_state.token_a_mint == mint_in,
-                    false,
-                )?;
-
-                let token_out_mint = if &data.pool_state.token_a_mint == mint_in {
-                    &data.pool_state.token_b_mint
-                } else {
-                    &data.pool_state.token_a_mint
-                };
-
-                (quote.output_amount, token_out_mint)
-            }
-            PoolType::RaydiumAmm(_, data) => {
-                let (swap_direction, token_out_mint) = if mint_in == &data.pool_state.coin_mint {
-                    (
-                        raydium::amm::SwapDirection::Coin2PC,
-                        &data.pool_state.pc_mint,
-                    )
-                } else {
-                    (
-                        raydium::amm::SwapDirection::PC2Coin,
-                        &data.pool_state.coin_mint,
-                    )
-                };
-
-                let quote = raydium::amm::swap_compute(
-                    &data.pool_state,
-                    &data.vaults,
-                    swap_direction,
-                    current_amount,
-                    true,
-                    0,
-                )?;
-
-                (quote, token_out_mint)
-            }
-            PoolType::RaydiumCpmm(_, data) => {
-                let (a_to_b, token_out_mint) = if &data.pool_state.token_0_mint == mint_in {
-                    (true, &data.pool_state.token_1_mint)
-                } else {
-                    (false, &data.pool_state.token_0_mint)
-                };
-
-                let quote = raydium::cpmm::swap_calculate(
-                    &data.amm_config,
-                    &data.pool_state,
-                    &data.vaults,
-                    current_amount,
-                    a_to_b,
-                )?;
-
-                (quote.other_amount_threshold, token_out_mint)
-            }
-            PoolType::RaydiumClmm(_, data) => {
-                let (a_to_b, token_out_mint) = if &data.pool_state.token_mint_0 == mint_in {
-                    (true, &data.pool_state.token_mint_1)
-                } else {
-                    (false, &data.pool_state.token_mint_0)
-                };
-
-                let mut tick_clone = if a_to_b {
-                    data.right_ticks.clone()
-                } else {
-                    data.left_ticks.clone()
-                };
-                let (amount_out, _) =
-                    raydium::clmm::swap_util::get_out_put_amount_and_remaining_accounts(
-                        current_amount,
-                        None,
-                        a_to_b,
-                        true,
-                        0,
-                        &data.pool_state,
-                        &data.tick_array_bitmap_ext,
-                        &mut tick_clone,
-                    )
-                    .unwrap_or_default();
-
-                (amount_out, token_out_mint)
-            }
-            PoolType::Whirlpool(_, data) => {
-                let (a_to_b, token_out_mint) = if &data.pool_state.token_mint_a == mint_in {
-                    (true, &data.pool_state.token_mint_b)
-                } else {
-                    (false, &data.pool_state.token_mint_a)
-                };
-
-                let tick_arrays = data
-                    .tick_data
-                    .clone()
-                    .map(|(_, tick_array)| Some(tick_array));
-                let quote = whirlpool::quote::swap_quote_by_input_token(
-                    current_amount,
-                    a_to_b,
-                    0,
-                    data.pool_state.clone(),
-                    data.oracle.clone(),
-                    tick_arrays,
-                    current_timestamp,
-                    None,
-                    None,
-                )
-                .unwrap_or_default();
-
-                (quote.token_min_out, token_out_mint)
-            }
-            PoolType::Vertigo(_, data) => {
-                let (amount_out, token_out_mint) = if &data.pool_state.mint_a == mint_in {
-                    let amount_out = data
-                        .pool_state
-                        .calculate_buy_amount_out(current_amount, current_slot)?;
-                    (amount_out, &data.pool_state.mint_b)
-                } else {
-                    let amount_out = data
-                        .pool_state
-                        .calculate_sell_amount_in(current_amount, current_slot)?;
-                    (amount_out, &data.pool_state.mint_a)
-                };
-
-                (amount_out, token_out_mint)
-            }
-            PoolType::Solfi(_, data) => {
-                let (a_to_b, token_out_mint) = if &data.pool_state.mint_a == mint_in {
-                    (true, &data.pool_state.mint_b)
-                } else {
-                    (false, &data.pool_state.mint_a)
-                };
-
-                let amount_out = data.reserves.swap_quote(current_amount, a_to_b);
+        self.check_trades(mint_in)?;
+        Ok(self.quote_in(clock, mint_in, current_amount)?)
+    }
 
-                (amount_out, token_out_mint)
-            }
-        };
+    /// Errors when `mint` is neither of the pool's mints, quotes would take it for the other side.
+    pub fn check_trades(&self, mint: &Pubkey) -> Result<()> {
+        let (mint_a, mint_b) = self.get_mints();
+        if mint != &mint_a && mint != &mint_b {
+            return Err(anyhow!(
+                "Pool {} trades {} and {}, not {}",
+                self.get_address(),
+                mint_a,
+                mint_b,
+                mint
+            ));
+        }
 
-        Ok(amount_out)
+        Ok(())
     }
 
     #[inline]
@@ -342,94 +146,13 @@ impl PoolType {
 
     #[inline]
     pub fn get_other_mint(&self, mint: &Pubkey) -> Pubkey {
-        match self {
-            PoolType::Meteora(_, data) => {
-                if &data.lb_pair.token_x_mint == mint {
-                    data.lb_pair.token_y_mint
-                } else {
-                    data.lb_pair.token_x_mint
-                }
-            }
-            PoolType::Pump(_, data) => {
-                if &data.pool.base_mint == mint {
-                    data.pool.quote_mint
-                } else {
-                    data.pool.base_mint
-                }
-            }
-            PoolType::MeteoraDammv2(_, data) => {
-                if &data.pool_state.token_a_mint == mint {
-                    data.pool_state.token_b_mint
-                } else {
-                    data.pool_state.token_a_mint
-                }
-            }
-            PoolType::RaydiumAmm(_, data) => {
-                if &data.pool_state.pc_mint == mint {
-                    data.pool_state.coin_mint
-                } else {
-                    data.pool_state.pc_mint
-                }
-            }
-            PoolType::RaydiumCpmm(_, data) => {
-                if &data.pool_state.token_0_mint == mint {
-                    data.pool_state.token_1_mint
-                } else {
-                    data.pool_state.token_0_mint
-                }
-            }
-            PoolType::RaydiumClmm(_, data) => {
-                if &data.pool_state.token_mint_0 == mint {
-                    data.pool_state.token_mint_1
-                } else {
-                    data.pool_state.token_mint_0
-                }
-            }
-            PoolType::Whirlpool(_, data) => {
-                if &data.pool_state.token_mint_a == mint {
-                    data.pool_state.token_mint_b
-                } else {
-                    data.pool_state.token_mint_a
-                }
-            }
-            PoolType::Vertigo(_, data) => {
-                if &data.pool_state.mint_a == mint {
-                    data.pool_state.mint_b
-                } else {
-                    data.pool_state.mint_a
-                }
-            }
-            PoolType::Solfi(_, data) => {
-                if &data.pool_state.mint_a == mint {
-                    data.pool_state.mint_b
-                } else {
-                    data.pool_state.mint_a
-                }
-            }
-        }
+        let (mint_a, mint_b) = self.mints();
+        if &mint_a == mint { mint_b } else { mint_a }
     }
 
     #[inline]
     pub fn get_mints(&self) -> (Pubkey, Pubkey) {
-        match self {
-            PoolType::Meteora(_, data) => (data.lb_pair.token_x_mint, data.lb_pair.token_y_mint),
-            PoolType::Pump(_, data) => (data.pool.base_mint, data.pool.quote_mint),
-            PoolType::MeteoraDammv2(_, data) => {
-                (data.pool_state.token_a_mint, data.pool_state.token_b_mint)
-            }
-            PoolType::RaydiumAmm(_, data) => (data.pool_state.pc_mint, data.pool_state.coin_mint),
-            PoolType::RaydiumCpmm(_, data) => {
-                (data.pool_state.token_0_mint, data.pool_state.token_1_mint)
-            }
-            PoolType::RaydiumClmm(_, data) => {
-                (data.pool_state.token_mint_0, data.pool_state.token_mint_1)
-            }
-            PoolType::Whirlpool(_, data) => {
-                (data.pool_state.token_mint_a, data.pool_state.token_mint_b)
-            }
-            PoolType::Vertigo(_, data) => (data.pool_state.mint_a, data.pool_state.mint_b),
-            PoolType::Solfi(_, data) => (data.pool_state.mint_a, data.pool_state.mint_b),
-        }
+        self.mints()
     }
 
     #[inline]
@@ -448,6 +171,18 @@ impl PoolType {
     }
 }
 
+// approximate concentrated liquidity as x = L / sqrt(P), y = L * sqrt(P)
+#[inline]
+fn virtual_reserves(liquidity: u128, sqrt_price_x64: u128) -> (f64, f64) {
+    if sqrt_price_x64 == 0 {
+        return (0.0, 0.0);
+    }
+
+    let sqrt_price = sqrt_price_x64 as f64 / ONE_Q64 as f64;
+    let liquidity = liquidity as f64;
+    (liquidity / sqrt_price, liquidity * sqrt_price)
+}
+
 impl From<MeteoraDlmmData> for PoolType {
     fn from(data: MeteoraDlmmData) -> Self {
         PoolType::Meteora(data.pool_address, data)
@@ -501,3 +236,33 @@ impl From<SolfiData> for PoolType {
         PoolType::Solfi(data.pool_address, data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::solfi;
+
+    #[test]
+    fn swap_rejects_a_mint_the_pool_doesnt_trade() {
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let market = Pubkey::new_unique();
+        let pool_state = solfi::Pool::new(&market, &mint_a, &mint_b);
+        let reserves = solfi::PoolReserves {
+            vault_a_amount: 1_000_000,
+            vault_b_amount: 1_000_000,
+            vault_a: pool_state.vault_a,
+            vault_b: pool_state.vault_b,
+        };
+        let pool: PoolType = SolfiData {
+            pool_address: market,
+            pool_state,
+            reserves,
+        }
+        .into();
+
+        let err = pool
+            .compute_swap_at(&Clock::default(), &Pubkey::new_unique(), 1_000)
+            .unwrap_err();
+        assert!(err.to_string().contains(&mint_b.to_string()));
+    }
+}
@@ -1,26 +1,36 @@
 use crate::{
     arb::{
-        ProfitableRoute, Route, route::HopVecExt, safe_swap_compute, sender,
+        ProfitableRoute, Route, approximate_swap_compute, blacklist,
         container::RouteContainer,
+        optimization, pair_breaker,
+        route::{HopVecExt, SpreadFilter},
+        sender,
     },
-    global, pool_index,
+    global,
+    metric::{self, SkipReason},
+    pool_index,
     streaming::global_data,
     wsol_mint,
 };
 use anchor_client::solana_sdk::clock::Clock;
 use anchor_client::solana_sdk::pubkey::Pubkey;
 use anyhow::Result;
+use dashmap::{DashMap, mapref::entry::Entry};
+use once_cell::sync::Lazy;
 use rayon::prelude::*;
 use std::{
     panic::{AssertUnwindSafe, catch_unwind},
-    sync::Arc,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
     thread::{self},
 };
 use tokio::{
     sync::Semaphore,
     time::{Duration, MissedTickBehavior},
 };
-use tracing::info;
+use tracing::{Level, debug, debug_span, enabled, info};
 
 pub fn send_routes(batch_size: usize) {
     info!("Start thread send routes - batch size {}", batch_size);
@@ -38,7 +48,22 @@ pub fn send_routes(batch_size: usize) {
                 continue;
             }
 
-            let swaps = RouteContainer::drain(batch_size);
+            // best first, only as many as can go out now, the rest wait for the next tick
+            let capacity = sem
+                .available_permits()
+                .min(sender::available_inflight_permits());
+            if capacity == 0 {
+                continue;
+            }
+            let (swaps, expired) =
+                RouteContainer::pop_fresh(capacity, global::get_opportunity_ttl());
+            metric::ROUTES_QUEUE_EXPIRED.fetch_add(expired.len() as u64, Ordering::Relaxed);
+            for route in &expired {
+                if let Some(net_profit) = optimization::net_profit_lamports(&route.route, 0) {
+                    let key = route.route.to_hash();
+                    metric::OPPORTUNITIES.record_skipped(key, SkipReason::Expired, net_profit);
+                }
+            }
             for swap in swaps {
                 if let Ok(permit) = sem.clone().try_acquire_owned() {
                     tokio::spawn(async move {
@@ -53,6 +78,58 @@ pub fn send_routes(batch_size: usize) {
     });
 }
 
+/// Debug log hops of a profitable route that went through a thinner pool than the pair offers.
+fn log_skipped_alternatives(route: &Route) {
+    for (i, hop) in route.hops.iter().enumerate() {
+        if let Some((pool, pool_type, tvl)) = hop.deeper_alternatives().first() {
+            debug!(
+                "Hop {} {} -> {} uses {} ({:?}), deeper {:?} pool {} skipped (tvl {:.0})",
+                i + 1,
+                hop.from,
+                hop.to,
+                hop.pool,
+                hop.pool_type,
+                pool_type,
+                pool,
+                tvl
+            );
+        }
+    }
+}
+
+/// A blacklisted or paused route still counts as a missed opportunity when the optimizer finds
+/// it above the floor. Only routes whose cheap quote is profitable are optimized.
+fn record_blocked(clock: &Clock, route: &Route, amount_in: u64, base_mint: &Pubkey) {
+    let Some(pools) = route.to_vec_owned() else {
+        return;
+    };
+    if approximate_swap_compute(clock, &pools, amount_in, base_mint).unwrap_or(0) <= 0 {
+        return;
+    }
+    let Some(swap) = sender::check_route(route, global::get_minimum_profit()) else {
+        return;
+    };
+    if let Some(net_profit) = optimization::net_profit_lamports(&swap, 0) {
+        metric::OPPORTUNITIES.record_skipped(swap.to_hash(), SkipReason::Blacklist, net_profit);
+    }
+}
+
+static ROUTE_EVAL_POOL: OnceLock<rayon::ThreadPool> = OnceLock::new();
+
+fn build_eval_pool(threads: usize) -> rayon::ThreadPool {
+    // rayon sizes the pool to the core count when threads is 0
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|i| format!("route-eval-{}", i))
+        .build()
+        .expect("Failed to build route evaluation pool")
+}
+
+/// Pool candidate routes are quoted on, sized by `bot.route_eval_threads`.
+fn route_eval_pool() -> &'static rayon::ThreadPool {
+    ROUTE_EVAL_POOL.get_or_init(|| build_eval_pool(global::get_route_eval_threads()))
+}
+
 fn find_profitable_route(
     clock: &Clock,
     routes: &[Route],
@@ -60,12 +137,46 @@ fn find_profitable_route(
     amount_in: u64,
     epsilon: f64,
 ) {
+    route_eval_pool().install(|| eval_routes(clock, routes, base_mint, amount_in, epsilon));
+}
+
+// every route is quoted on its own owned copy of the pools, so they can run in any order
+fn eval_routes(clock: &Clock, routes: &[Route], base_mint: Pubkey, amount_in: u64, epsilon: f64) {
+    let spread_filter = SpreadFilter::new(global::get_typical_fee_bps());
+    let disabled_dexes = global::get_disabled_dexes();
     routes
         .par_iter()
+        .filter(|route| {
+            disabled_dexes.is_empty()
+                || !route
+                    .hops
+                    .iter()
+                    .any(|hop| disabled_dexes.contains(hop.pool_type.dex_name()))
+        })
+        .filter(|route| {
+            let rejected = spread_filter.rejects(route);
+            if rejected {
+                metric::ROUTES_SPREAD_SKIPPED.fetch_add(1, Ordering::Relaxed);
+            }
+            !rejected
+        })
         .filter(|route| route.hops.product() >= epsilon)
+        .filter(|route| {
+            let blocked = route
+                .hops
+                .iter()
+                .any(|hop| blacklist::is_blacklisted(&hop.pool))
+                || pair_breaker::route_paused(route);
+            if blocked {
+                record_blocked(clock, route, amount_in, &base_mint);
+            }
+            !blocked
+        })
         .filter_map(|r| {
             let pools = r.to_vec_owned()?;
-            match safe_swap_compute(clock, &pools, amount_in, &base_mint, false) {
+            metric::ROUTES_EVALUATED.fetch_add(1, Ordering::Relaxed);
+            // tick arrays still loading shouldn't hide a route, the optimizer quotes it exactly
+            match approximate_swap_compute(clock, &pools, amount_in, &base_mint) {
                 Ok(p) if p > 0 => Some(r),
                 _ => None,
             }
@@ -73,8 +184,16 @@ fn find_profitable_route(
         .for_each(|r| {
             let quote_time = tokio::time::Instant::now();
             let min_profit = global::get_minimum_profit();
-            let quote_result = catch_unwind(AssertUnwindSafe(|| sender::check_route(r, min_profit)));
+            let quote_result = {
+                let _span = debug_span!("optimizer").entered();
+                catch_unwind(AssertUnwindSafe(|| sender::check_route(r, min_profit)))
+            };
+            metric::OPTIMIZER_LATENCY.record(quote_time.elapsed());
             if let Ok(Some(swap)) = quote_result {
+                metric::ROUTES_PROFITABLE.fetch_add(1, Ordering::Relaxed);
+                if enabled!(Level::DEBUG) {
+                    log_skipped_alternatives(r);
+                }
                 RouteContainer::smart_insert(ProfitableRoute {
                     route: swap,
                     quote_time: quote_time,
@@ -84,45 +203,238 @@ fn find_profitable_route(
         });
 }
 
-fn find_routes(base_mint: Pubkey, epsilon: f64, delay_ms: u64) {
-    loop {
-        thread::sleep(std::time::Duration::from_millis(delay_ms));
-
+// passes run back to back on this thread, so a slow one delays the next instead of overlapping it
+fn find_routes(base_mint: Pubkey, interval: std::time::Duration) {
+    while !FINDING_STOPPED.load(Ordering::Relaxed) {
+        let pass_time = std::time::Instant::now();
         let amount_in = 50_000;
-        let clock = global_data::get_clock().unwrap();
-        let mut routes = pool_index::routes();
+        // read every pass, a config reload may have changed it
+        let epsilon = 1f64 + global::get_price_threshold();
+        let Some(clock) = global_data::get_clock() else {
+            debug!("No clock yet, skip the route pass");
+            thread::sleep(interval);
+            continue;
+        };
+        let enumeration_time = std::time::Instant::now();
+        let mut routes = {
+            let _span = debug_span!("route_enumeration").entered();
+            pool_index::routes()
+        };
         fastrand::shuffle(&mut routes);
-        find_profitable_route(&clock, &routes, base_mint, amount_in, epsilon);
+        metric::ROUTE_ENUMERATION_LATENCY.record(enumeration_time.elapsed());
+
+        {
+            let _span = debug_span!("find_profitable_route", routes = routes.len()).entered();
+            find_profitable_route(&clock, &routes, base_mint, amount_in, epsilon);
+        }
+        metric::FULL_PASSES.fetch_add(1, Ordering::Relaxed);
+
+        let elapsed = pass_time.elapsed();
+        match interval.checked_sub(elapsed) {
+            Some(remaining) => thread::sleep(remaining),
+            None => {
+                metric::FULL_PASSES_OVERRUN.fetch_add(1, Ordering::Relaxed);
+                debug!(
+                    "Route pass took {:?}, over the {:?} interval, starting the next one now",
+                    elapsed, interval
+                );
+            }
+        }
     }
 }
 
+// pool -> whether it updated again while its pass was running
+static POOL_PASSES: Lazy<DashMap<Pubkey, bool>> = Lazy::new(DashMap::new);
+
+/// Releases the pool's slot in `POOL_PASSES` if the pass panics. A pass that ends normally
+/// releases it itself, by then the slot may already belong to the next pass.
+struct PoolPassGuard(Pubkey);
+
+impl Drop for PoolPassGuard {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            POOL_PASSES.remove(&self.0);
+        }
+    }
+}
+
+/// Quote the routes through a pool that just updated. Updates arriving while the previous pass
+/// for the same pool still runs are folded into one more pass after it, so a burst can't pile
+/// up blocking tasks and the last update is still quoted.
 pub fn find_from_pool(pool_address: Pubkey) {
-    tokio::task::spawn_blocking(move || {
-        if let Some(pool) = pool_index::get(&pool_address) {
-            let mint = if pool.mint_a == wsol_mint() {
-                pool.mint_b
-            } else {
-                pool.mint_a
-            };
+    match POOL_PASSES.entry(pool_address) {
+        Entry::Occupied(mut pass) => {
+            pass.insert(true);
+            metric::POOL_PASSES_SKIPPED.fetch_add(1, Ordering::Relaxed);
+            debug!(
+                "Pass for pool {} still running, rerun after it",
+                pool_address
+            );
+            return;
+        }
+        Entry::Vacant(pass) => {
+            pass.insert(false);
+        }
+    }
+    let guard = PoolPassGuard(pool_address);
 
-            let bot_config = &global::get_config().bot;
-            let epsilon = 1f64 + bot_config.price_threshold;
-            let base_mint = global::get_base_mint().as_ref().clone();
-            let amount_in = 50_000;
-            let clock = global_data::get_clock().unwrap();
-            let routes = pool_index::get_routes_by_mint(&mint);
-            find_profitable_route(&clock, &routes, base_mint, amount_in, epsilon);
+    tokio::task::spawn_blocking(move || {
+        let _guard = guard;
+        loop {
+            pool_pass(pool_address);
+            if finish_pool_pass(&pool_address) {
+                break;
+            }
         }
     });
 }
 
-pub fn finding(delay_ms: u64) -> Result<()> {
-    let bot_config = &global::get_config().bot;
-    let routes_batch_size = bot_config.routes_batch_size;
-    let epsilon = 1f64 + bot_config.price_threshold;
+/// Release the pool's slot unless it updated during the pass, then clear the mark and return
+/// false so the caller runs once more. The check and release happen under the entry lock, so an
+/// update can't land between them and be lost.
+fn finish_pool_pass(pool_address: &Pubkey) -> bool {
+    if POOL_PASSES
+        .remove_if(pool_address, |_, rerun| !*rerun)
+        .is_some()
+    {
+        return true;
+    }
+    // updates arriving from here on are covered by the next pass, which reads the pool after this
+    if let Some(mut rerun) = POOL_PASSES.get_mut(pool_address) {
+        *rerun = false;
+    }
+    false
+}
+
+fn pool_pass(pool_address: Pubkey) {
+    if let Some(pool) = pool_index::get(&pool_address) {
+        let mint = if pool.mint_a == wsol_mint() {
+            pool.mint_b
+        } else {
+            pool.mint_a
+        };
+
+        let epsilon = 1f64 + global::get_price_threshold();
+        let base_mint = global::get_base_mint().as_ref().clone();
+        let amount_in = 50_000;
+        let Some(clock) = global_data::get_clock() else {
+            debug!("No clock yet, skip the pass for pool {}", pool_address);
+            return;
+        };
+        let routes = pool_index::get_routes_by_mint(&mint);
+        find_profitable_route(&clock, &routes, base_mint, amount_in, epsilon);
+    }
+}
+
+// set on shutdown, the full pass in progress finishes and no other starts
+static FINDING_STOPPED: AtomicBool = AtomicBool::new(false);
+
+/// Start sending routes and run full route passes on a dedicated thread, returning right away
+/// so the caller can wait for shutdown.
+pub fn finding(interval_ms: u64) -> Result<()> {
+    let routes_batch_size = global::get_config().bot.routes_batch_size;
     let base_mint = global::get_base_mint().as_ref().clone();
     send_routes(routes_batch_size as usize);
-    find_routes(base_mint, epsilon, delay_ms);
+    info!(
+        "Evaluating routes on {} threads",
+        route_eval_pool().current_num_threads()
+    );
+    thread::Builder::new()
+        .name("route-finder".to_string())
+        .spawn(move || find_routes(base_mint, std::time::Duration::from_millis(interval_ms)))?;
 
     Ok(())
 }
+
+/// Stop starting full route passes, e.g. before shutdown cleanup.
+pub fn stop_finding() {
+    FINDING_STOPPED.store(true, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arb::{PoolType, RaydiumCpmmData};
+    use crate::dex::raydium::cpmm;
+
+    fn cpmm_pool(mint_0: Pubkey, mint_1: Pubkey) -> PoolType {
+        let mut data = vec![0u8; 637];
+        data[0..8].copy_from_slice(&cpmm::POOL_DISCRIMINATOR);
+        // token_0_mint and token_1_mint are the 6th and 7th pubkeys
+        data[8 + 5 * 32..8 + 6 * 32].copy_from_slice(mint_0.as_ref());
+        data[8 + 6 * 32..8 + 7 * 32].copy_from_slice(mint_1.as_ref());
+
+        RaydiumCpmmData {
+            pool_address: Pubkey::new_unique(),
+            pool_state: cpmm::PoolState::deserialize(&data).unwrap(),
+            amm_config: cpmm::AmmConfig {
+                trade_fee_rate: 2_500,
+                ..Default::default()
+            },
+            vaults: cpmm::PoolReserves {
+                token_0_vault: Pubkey::new_unique(),
+                token_0_amount: 1_000_000_000_000,
+                token_1_vault: Pubkey::new_unique(),
+                token_1_amount: 150_000_000_000,
+            },
+        }
+        .into()
+    }
+
+    #[test]
+    fn updates_during_a_pool_pass_rerun_it() {
+        let pool = Pubkey::new_unique();
+
+        POOL_PASSES.insert(pool, false);
+        assert!(finish_pool_pass(&pool));
+        assert!(!POOL_PASSES.contains_key(&pool));
+
+        // an update marked the running pass, it runs once more with the mark cleared
+        POOL_PASSES.insert(pool, true);
+        assert!(!finish_pool_pass(&pool));
+        assert_eq!(POOL_PASSES.get(&pool).map(|rerun| *rerun), Some(false));
+        assert!(finish_pool_pass(&pool));
+        assert!(!POOL_PASSES.contains_key(&pool));
+    }
+
+    // Quote throughput of the eval pool, single threaded against one thread per core:
+    // cargo test --release route_eval_throughput -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn route_eval_throughput() {
+        let clock = Clock::default();
+        let mints = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let route = vec![
+            cpmm_pool(mints[0], mints[1]),
+            cpmm_pool(mints[1], mints[2]),
+            cpmm_pool(mints[2], mints[0]),
+        ];
+        let routes = vec![route; 500_000];
+
+        for threads in [1, 0] {
+            let pool = build_eval_pool(threads);
+            let start = std::time::Instant::now();
+            let quoted = pool.install(|| {
+                routes
+                    .par_iter()
+                    .filter(|route| {
+                        approximate_swap_compute(&clock, route, 50_000, &mints[0]).is_ok()
+                    })
+                    .count()
+            });
+            let elapsed = start.elapsed();
+            assert_eq!(quoted, routes.len());
+            println!(
+                "{} threads: {} routes in {:?}, {:.0} routes/s",
+                pool.current_num_threads(),
+                routes.len(),
+                elapsed,
+                routes.len() as f64 / elapsed.as_secs_f64()
+            );
+        }
+    }
+}
@@ -1,6 +1,19 @@
 use super::*;
-use crate::streaming::global_data;
+use crate::{
+    arb::ata_worker::AtaWorker,
+    config::RouteDirection,
+    global,
+    pool_index::{self, TokenPoolType},
+    streaming::{global_data, price},
+    transaction,
+};
 use ahash::AHasher;
+use anchor_client::solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    hash::Hash as Blockhash,
+    message::{VersionedMessage, v0},
+};
+use dashmap::DashMap;
 use std::hash::{Hash, Hasher};
 
 impl Route {
@@ -32,6 +45,33 @@ impl SwapRoutes {
         }
         h.finish()
     }
+
+    /// Compile the arb transaction for `payer` without signing it, for an external signer.
+    /// Only the bot's own wallet has a cached base balance, any other payer borrows or
+    /// wraps the full amount. The token accounts of other payers are looked up on chain.
+    pub async fn to_unsigned_message(
+        self,
+        payer: &Pubkey,
+        alt_accounts: &[AddressLookupTableAccount],
+        blockhash: Blockhash,
+    ) -> Result<VersionedMessage> {
+        let user_base_amount = if *payer == global::get_pubkey() {
+            global::get_base_mint_amount()
+        } else {
+            0
+        };
+        let known_atas = AtaWorker::fetch_known_atas(payer, &self.routes).await?;
+        let ixs = transaction::build_instructions(
+            payer,
+            None,
+            self,
+            alt_accounts,
+            user_base_amount,
+            &known_atas,
+        )?;
+        let message = v0::Message::try_compile(payer, &ixs, alt_accounts, blockhash)?;
+        Ok(VersionedMessage::V0(message))
+    }
 }
 
 impl Hop {
@@ -47,6 +87,45 @@ impl Hop {
             0f64
         }
     }
+
+    /// Other pools for the same pair that are deeper than the hop's own, deepest first,
+    /// with their TVL in the `from` mint.
+    pub fn deeper_alternatives(&self) -> Vec<(Pubkey, TokenPoolType, f64)> {
+        let tvl = |pool_key: &Pubkey| {
+            pool_index::get(pool_key)
+                .and_then(|pool| pool.to_pool_type())
+                .map(|pool_type| pool_type.estimated_tvl(&self.from))
+                .unwrap_or(0.0)
+        };
+
+        let current_tvl = tvl(&self.pool);
+        let mut alternatives: Vec<(Pubkey, TokenPoolType, f64)> =
+            pool_index::pools_for_pair(&self.from, &self.to)
+                .into_iter()
+                .filter(|(pool_key, _)| pool_key != &self.pool)
+                .map(|(pool_key, pool_type)| (pool_key, pool_type, tvl(&pool_key)))
+                .filter(|(_, _, alt_tvl)| *alt_tvl > current_tvl)
+                .collect();
+        alternatives.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+
+        alternatives
+    }
+}
+
+/// `bot.route_direction` filter for the first hop of a cycle, which always spends `bot.mint`.
+/// The hop buys when it receives a token outside the base mints and sells when it receives
+/// another base mint, whichever side of the pool either token sits on.
+#[inline]
+pub fn first_hop_allowed(
+    route_direction: RouteDirection,
+    to: &Pubkey,
+    base_mints: &[Pubkey],
+) -> bool {
+    match route_direction {
+        RouteDirection::BuyFirst => !base_mints.contains(to),
+        RouteDirection::SellFirst => base_mints.contains(to),
+        RouteDirection::Both => true,
+    }
 }
 
 pub trait HopVecExt {
@@ -74,3 +153,182 @@ impl HopVecExt for Vec<Hop> {
         p
     }
 }
+
+/// Spread between the highest and lowest price, in bps of the lowest. `None` below two prices.
+fn spread_bps(prices: impl IntoIterator<Item = f64>) -> Option<f64> {
+    let mut count = 0;
+    let (mut min, mut max) = (f64::MAX, f64::MIN);
+    for price in prices {
+        count += 1;
+        min = min.min(price);
+        max = max.max(price);
+    }
+
+    (count >= 2).then(|| (max / min - 1.0) * 10_000.0)
+}
+
+/// Spread of the cached spot prices across the pools of a pair, in bps.
+pub fn pair_spread_bps(mint_a: &Pubkey, mint_b: &Pubkey) -> Option<f64> {
+    spread_bps(
+        pool_index::find_by_pair(mint_a, mint_b)
+            .iter()
+            .filter_map(|pool| {
+                let (from, atob) = global_data::get_price(pool)?;
+                price::sanitize(if &from == mint_a { atob } else { 1.0 / atob })
+            }),
+    )
+}
+
+/// Early rejection for routes that only trade one pair, base -> quote and back through
+/// another pool. Such a route pays two pool fees, so when every pool of the pair quotes within
+/// `2 * typical_fee_bps` of the others it can't be profitable. Spreads are worked out once per
+/// pair, build one filter per pass so they follow the prices.
+pub struct SpreadFilter {
+    min_spread_bps: f64,
+    rejected_pairs: DashMap<(Pubkey, Pubkey), bool>,
+}
+
+impl SpreadFilter {
+    pub fn new(typical_fee_bps: u64) -> Self {
+        Self {
+            min_spread_bps: 2.0 * typical_fee_bps as f64,
+            rejected_pairs: DashMap::new(),
+        }
+    }
+
+    pub fn rejects(&self, route: &Route) -> bool {
+        if self.min_spread_bps <= 0.0 {
+            return false;
+        }
+        let Some(pair) = single_pair(&route.hops) else {
+            return false;
+        };
+
+        *self.rejected_pairs.entry(pair).or_insert_with(|| {
+            pair_spread_bps(&pair.0, &pair.1).is_some_and(|spread| spread < self.min_spread_bps)
+        })
+    }
+}
+
+/// Index of the first pool, given by its mints, that trades neither side of the mint the path
+/// from `start` reaches it with. Such a pool can't be a hop, the route is malformed.
+pub fn disconnected_hop(
+    start: &Pubkey,
+    pool_mints: impl IntoIterator<Item = (Pubkey, Pubkey)>,
+) -> Option<usize> {
+    let mut mint = *start;
+    for (i, (mint_a, mint_b)) in pool_mints.into_iter().enumerate() {
+        mint = if mint == mint_a {
+            mint_b
+        } else if mint == mint_b {
+            mint_a
+        } else {
+            return Some(i);
+        };
+    }
+
+    None
+}
+
+/// The pair every hop trades, mints in ascending order, `None` when the hops span several pairs.
+fn single_pair(hops: &[Hop]) -> Option<(Pubkey, Pubkey)> {
+    let ordered = |hop: &Hop| {
+        if hop.from < hop.to {
+            (hop.from, hop.to)
+        } else {
+            (hop.to, hop.from)
+        }
+    };
+    let pair = ordered(hops.first()?);
+
+    hops.iter().all(|hop| ordered(hop) == pair).then_some(pair)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hop(from: Pubkey, to: Pubkey) -> Hop {
+        Hop {
+            from,
+            to,
+            pool: Pubkey::new_unique(),
+            pool_type: TokenPoolType::RaydiumCpmm,
+            rate: 1.0,
+        }
+    }
+
+    #[test]
+    fn spread_is_measured_from_the_lowest_price() {
+        assert_eq!(
+            spread_bps([2.0, 2.01, 2.005]).map(|s| s.round()),
+            Some(50.0)
+        );
+        assert_eq!(spread_bps([3.0, 3.0]), Some(0.0));
+        assert_eq!(spread_bps([3.0]), None);
+    }
+
+    #[test]
+    fn only_single_pair_routes_are_filtered() {
+        let (base, quote, other) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let pair = single_pair(&[hop(base, quote), hop(quote, base)]);
+        assert_eq!(pair, Some((base.min(quote), base.max(quote))));
+        assert_eq!(
+            single_pair(&[hop(base, quote), hop(quote, other), hop(other, base)]),
+            None
+        );
+
+        let route = Route {
+            start: base,
+            hops: vec![hop(base, quote), hop(quote, base)],
+            product: 1.0,
+        };
+        assert!(!SpreadFilter::new(0).rejects(&route));
+    }
+
+    #[test]
+    fn pools_off_the_path_mint_are_disconnected() {
+        let (base, quote, other, stray) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        // pools may list the incoming mint on either side
+        let connected = [(base, quote), (other, quote), (base, other)];
+        assert_eq!(disconnected_hop(&base, connected), None);
+        assert_eq!(
+            disconnected_hop(&base, [(base, quote), (other, stray), (other, base)]),
+            Some(1)
+        );
+        assert_eq!(disconnected_hop(&base, [(quote, other)]), Some(0));
+    }
+
+    #[test]
+    fn first_hop_direction_ignores_pool_token_order() {
+        let (sol, usdc, bonk) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let base_mints = [sol, usdc];
+        // (mint_a, mint_b): the first pool lists the bought token as mint_a
+        let pools = [(bonk, sol), (sol, bonk), (usdc, sol)];
+        let first_hop_to =
+            |(mint_a, mint_b): (Pubkey, Pubkey)| if mint_a == sol { mint_b } else { mint_a };
+        let allowed = |direction| {
+            pools
+                .iter()
+                .map(|pool| first_hop_allowed(direction, &first_hop_to(*pool), &base_mints))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(allowed(RouteDirection::Both), [true, true, true]);
+        assert_eq!(allowed(RouteDirection::BuyFirst), [true, true, false]);
+        assert_eq!(allowed(RouteDirection::SellFirst), [false, false, true]);
+    }
+}
@@ -1,17 +1,20 @@
 use super::*;
 use crate::arb::ata_worker::AtaWorker;
+use crate::arb::confirmation::{ConfirmationTracker, SubmittedArb};
+use crate::metric::{self, SkipReason};
 use crate::polling::blockhash;
 use crate::streaming::global_data;
-use crate::{default_lta, global, streaming, transaction};
+use crate::{default_lta, global, onchain, streaming, transaction};
 use anchor_client::solana_sdk::{
-    address_lookup_table::AddressLookupTableAccount, signature::Signature,
+    address_lookup_table::AddressLookupTableAccount, hash::Hash, signature::Signature,
 };
 use anyhow::Result;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use tokio;
-use tokio::time::Instant;
-use tracing::{error, info, warn};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, TryAcquireError};
+use tokio::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 struct ArbitrageKey {
@@ -33,9 +36,22 @@ impl ArbitrageKey {
 lazy_static::lazy_static! {
     static ref RATE_LIMITER: Arc<Mutex<HashMap<ArbitrageKey, tokio::time::Instant>>> =
         Arc::new(Mutex::new(HashMap::new()));
+    static ref INFLIGHT_ARBS: Arc<Semaphore> =
+        Arc::new(Semaphore::new(global::get_max_inflight_arbs()));
+    // first hop pool -> slot of the last submission through it
+    static ref FIRST_HOP_SLOTS: Mutex<HashMap<Pubkey, u64>> = Mutex::new(HashMap::new());
+    // ordered pool addresses of a submitted route -> submission time
+    static ref ROUTE_COOLDOWNS: Mutex<HashMap<Vec<Pubkey>, Instant>> = Mutex::new(HashMap::new());
+    // (nonce account, nonce value) the last submission used and when
+    static ref NONCE_LEASE: tokio::sync::Mutex<Option<(Pubkey, Hash, Instant)>> =
+        tokio::sync::Mutex::new(None);
 }
 
 const RATE_LIMIT_DURATION: tokio::time::Duration = tokio::time::Duration::from_secs(60);
+const ROUTE_CONFIRMATION_POLL: Duration = Duration::from_millis(400);
+// a nonce value still unchanged this long after its submission is used again, the submission
+// was most likely dropped
+const NONCE_LEASE_DURATION: Duration = Duration::from_secs(30);
 
 fn should_allow_transaction(arb_key: &ArbitrageKey) -> bool {
     let mut rate_limiter = RATE_LIMITER.lock().unwrap();
@@ -57,16 +73,118 @@ fn should_allow_transaction(arb_key: &ArbitrageKey) -> bool {
     }
 }
 
+/// Submissions that can start before hitting `bot.max_inflight_arbs`.
+pub fn available_inflight_permits() -> usize {
+    INFLIGHT_ARBS.available_permits()
+}
+
+fn acquire_inflight_permit() -> Option<OwnedSemaphorePermit> {
+    match INFLIGHT_ARBS.clone().try_acquire_owned() {
+        std::result::Result::Ok(permit) => Some(permit),
+        Err(TryAcquireError::NoPermits) => {
+            warn!(
+                "Arb submission throttled: {} submissions already in flight",
+                global::get_max_inflight_arbs()
+            );
+            None
+        }
+        Err(TryAcquireError::Closed) => {
+            error!("Arb submission throttled: in-flight semaphore closed");
+            None
+        }
+    }
+}
+
+fn claim_first_hop(swap: &SwapRoutes) -> bool {
+    if !global::dedup_first_hop() {
+        return true;
+    }
+
+    let (Some(first_pool), Some(clock)) = (swap.routes.first(), global_data::get_clock()) else {
+        return true;
+    };
+
+    let pool = first_pool.get_address();
+    let mut slots = FIRST_HOP_SLOTS.lock().unwrap();
+    match slots.get(pool) {
+        Some(slot) if *slot == clock.slot => {
+            warn!(
+                "Arb submission throttled: first hop pool {} already submitted in slot {}",
+                pool, clock.slot
+            );
+            false
+        }
+        _ => {
+            slots.insert(*pool, clock.slot);
+            true
+        }
+    }
+}
+
+fn route_key(swap: &SwapRoutes) -> Vec<Pubkey> {
+    swap.routes.iter().map(|pool| *pool.get_address()).collect()
+}
+
+fn claim_route(swap: &SwapRoutes) -> bool {
+    let cooldown_ms = global::get_route_cooldown_ms();
+    if cooldown_ms == 0 {
+        return true;
+    }
+
+    let cooldown = Duration::from_millis(cooldown_ms);
+    let key = route_key(swap);
+    let mut cooldowns = ROUTE_COOLDOWNS.lock().unwrap();
+    cooldowns.retain(|_, submitted| submitted.elapsed() < cooldown);
+    if cooldowns.contains_key(&key) {
+        warn!("Arb submission throttled: identical route still cooling down");
+        return false;
+    }
+
+    cooldowns.insert(key, Instant::now());
+    true
+}
+
+fn release_route(key: &[Pubkey]) {
+    ROUTE_COOLDOWNS.lock().unwrap().remove(key);
+}
+
+/// Lift the route cooldown as soon as the transaction lands, or once the cooldown runs out.
+fn watch_route_confirmation(key: Vec<Pubkey>, signature: Signature) {
+    let cooldown = Duration::from_millis(global::get_route_cooldown_ms());
+    tokio::spawn(async move {
+        let rpc_client = global::get_rpc_client();
+        let started = Instant::now();
+        while started.elapsed() < cooldown {
+            tokio::time::sleep(ROUTE_CONFIRMATION_POLL).await;
+            if let std::result::Result::Ok(Some(_)) =
+                rpc_client.get_signature_status(&signature).await
+            {
+                break;
+            }
+        }
+        release_route(&key);
+    });
+}
+
 fn collect_alt_accounts(swap: &SwapRoutes) -> Option<Vec<AddressLookupTableAccount>> {
+    let static_alts = streaming::static_alts();
     let mut alt_accounts: Vec<AddressLookupTableAccount> =
-        Vec::with_capacity(swap.routes.len() + 1);
+        Vec::with_capacity(static_alts.len() + swap.routes.len() + 1);
+    alt_accounts.extend_from_slice(static_alts);
+
+    let mut push_alt = |alt_data: AddressLookupTableAccount| {
+        if !alt_accounts.iter().any(|alt| alt.key == alt_data.key) {
+            alt_accounts.push(alt_data);
+        }
+    };
+
     if let Some(default_lta_data) = streaming::retrieve_alt_from_alt_pk(&default_lta()) {
-        alt_accounts.push(default_lta_data);
+        push_alt(default_lta_data);
     }
 
     for pool in &swap.routes {
         if let Some(alt_data) = streaming::retrieve_alt(pool.get_address()) {
-            alt_accounts.push(alt_data);
+            push_alt(alt_data);
         }
     }
 
@@ -77,13 +195,103 @@ fn collect_alt_accounts(swap: &SwapRoutes) -> Option<Vec<AddressLookupTableAccou
     }
 }
 
+/// The nonce value for the next submission, `None` while another submission holds it. Every
+/// submission on the same value but one would fail, so a value is only handed out once until it
+/// advances or `NONCE_LEASE_DURATION` passed.
+async fn lease_nonce(nonce_account: &Pubkey) -> Option<Hash> {
+    // another submission is taking the nonce right now
+    let mut lease = NONCE_LEASE.try_lock().ok()?;
+    let nonce_hash = match onchain::get_nonce_hash(nonce_account).await {
+        std::result::Result::Ok(nonce_hash) => nonce_hash,
+        Err(e) => {
+            warn!("Failed to read nonce, using recent blockhash: {}", e);
+            return None;
+        }
+    };
+    let held = lease.is_some_and(|(account, used, at)| {
+        account == *nonce_account && used == nonce_hash && at.elapsed() < NONCE_LEASE_DURATION
+    });
+    if held {
+        return None;
+    }
+
+    *lease = Some((*nonce_account, nonce_hash, Instant::now()));
+    Some(nonce_hash)
+}
+
+/// Hand the nonce value back when its submission never went out.
+async fn release_nonce(nonce_hash: &Hash) {
+    let mut lease = NONCE_LEASE.lock().await;
+    if lease.is_some_and(|(_, used, _)| used == *nonce_hash) {
+        *lease = None;
+    }
+}
+
+/// Durable nonce when `[nonce]` is configured, readable and not held by another submission,
+/// the recent blockhash otherwise.
+async fn submission_blockhash() -> (Hash, Option<(Pubkey, Pubkey)>) {
+    match global::get_nonce_account() {
+        std::result::Result::Ok(Some((nonce_account, authority))) => {
+            if let Some(nonce_hash) = lease_nonce(&nonce_account).await {
+                return (nonce_hash, Some((nonce_account, authority)));
+            }
+        }
+        std::result::Result::Ok(None) => {}
+        Err(e) => error!("Invalid nonce config, using recent blockhash: {}", e),
+    }
+
+    (blockhash::get_current_blockhash().await.unwrap(), None)
+}
+
+/// Dry-run stand-in for sending: log the candidate to the ledger as if it landed.
+fn record_paper_fill(swap: &SwapRoutes) -> Option<Signature> {
+    let amount_out = (swap.amount_in as i64).saturating_add(swap.profit).max(0) as u64;
+    let timestamp = chrono::Utc::now().timestamp();
+    if let Err(e) = ledger::record_fill(swap, swap.amount_in, amount_out, timestamp) {
+        error!("Failed to record paper fill {}", e);
+        return None;
+    }
+
+    info!(
+        "Paper fill - amount in {} -> {} ({})",
+        swap.amount_in, amount_out, swap.profit
+    );
+    Some(Signature::default())
+}
+
 #[allow(unreachable_code)]
 #[inline]
 pub async fn send_arb(swap: SwapRoutes) -> Option<Signature> {
-    let blockhash = blockhash::get_current_blockhash().await.unwrap();
-    if let Some(alt_accounts) = collect_alt_accounts(&swap) {
+    let swap_key = swap.to_hash();
+    let Some(net_profit) = optimization::net_profit_lamports(&swap, 0) else {
+        debug!("No SOL price for base mint {}, route not sent", swap.mint);
+        return None;
+    };
+    let Some(_permit) = acquire_inflight_permit() else {
+        metric::OPPORTUNITIES.record_skipped(swap_key, SkipReason::InflightCap, net_profit);
+        return None;
+    };
+    if !claim_first_hop(&swap) || !claim_route(&swap) {
+        metric::OPPORTUNITIES.record_skipped(swap_key, SkipReason::Cooldown, net_profit);
+        return None;
+    }
+
+    if global::dry_run() {
+        let signature = record_paper_fill(&swap);
+        if signature.is_some() {
+            metric::OPPORTUNITIES.record_submitted(swap_key, net_profit);
+        }
+        return signature;
+    }
+
+    let route_key = route_key(&swap);
+    let pairs = pair_breaker::route_pairs(&swap.routes);
+    let (mint, amount_in, profit) = (swap.mint, swap.amount_in, swap.profit);
+    let (blockhash, nonce) = submission_blockhash().await;
+    let signature = if let Some(alt_accounts) = collect_alt_accounts(&swap) {
         transaction::build_and_send(
             blockhash,
+            nonce,
             swap,
             &alt_accounts,
             global::get_base_mint_amount(),
@@ -91,8 +299,43 @@ pub async fn send_arb(swap: SwapRoutes) -> Option<Signature> {
         .await
     } else {
         error!("Can't load ALT");
-        None
+        Err(transaction::SendFailure::Rejected)
+    };
+
+    match signature {
+        std::result::Result::Ok(signature) => {
+            metric::OPPORTUNITIES.record_submitted(swap_key, net_profit);
+            if global::track_confirmations() {
+                ConfirmationTracker::get_or_init().track(SubmittedArb {
+                    signature,
+                    pools: route_key.clone(),
+                    pairs: pairs.clone(),
+                    mint,
+                    amount_in,
+                    profit,
+                    submitted: Instant::now(),
+                });
+            } else {
+                pair_breaker::record_success(&pairs);
+            }
+            if global::get_route_cooldown_ms() > 0 {
+                watch_route_confirmation(route_key, signature);
+            }
+        }
+        // nothing went out, let the next pass retry the route. The blacklist only counts routes
+        // that reverted on chain, see the confirmation tracker
+        Err(failure) => {
+            release_route(&route_key);
+            if nonce.is_some() {
+                release_nonce(&blockhash).await;
+            }
+            // a simulation that disagrees with the quote is the one sign the pairs' data is off
+            if failure == transaction::SendFailure::Drifted {
+                pair_breaker::record_failure(&pairs);
+            }
+        }
     }
+    signature.ok()
 }
 
 #[allow(unused_variables)]
@@ -106,6 +349,8 @@ pub async fn do_arb_v2(profitable_route: ProfitableRoute) -> Result<bool> {
         return Ok(false);
     }
 
+    alert::notify(&swap);
+
     if !AtaWorker::create_mints(&swap.routes) {
         warn!("Creating mints..., skip");
         return Ok(false);
@@ -129,6 +374,9 @@ pub async fn do_arb_v2(profitable_route: ProfitableRoute) -> Result<bool> {
             Ok(false)
         }
     } else {
+        if let Some(net_profit) = optimization::net_profit_lamports(&swap, 0) {
+            metric::OPPORTUNITIES.record_skipped(swap.to_hash(), SkipReason::Cooldown, net_profit);
+        }
         Ok(false)
     }
 }
@@ -146,7 +394,12 @@ pub async fn do_arb(swap: SwapRoutes, now: tokio::time::Instant) -> Result<bool>
         return Ok(false);
     }
 
-    if swap.profit > global::get_minimum_profit() as i64 {
+    let swap_key = swap.to_hash();
+    let Some(net_profit) = optimization::net_profit_lamports(&swap, 0) else {
+        debug!("No SOL price for base mint {}, route not sent", swap.mint);
+        return Ok(false);
+    };
+    if net_profit > global::get_minimum_profit() as i64 {
         let arb_key = ArbitrageKey::from_swap_route(&swap);
 
         if should_allow_transaction(&arb_key) {
@@ -167,9 +420,11 @@ pub async fn do_arb(swap: SwapRoutes, now: tokio::time::Instant) -> Result<bool>
                 Ok(false)
             }
         } else {
+            metric::OPPORTUNITIES.record_skipped(swap_key, SkipReason::Cooldown, net_profit);
             Ok(false)
         }
     } else {
+        metric::OPPORTUNITIES.record_skipped(swap_key, SkipReason::Floor, net_profit);
         Ok(false)
     }
 }
@@ -233,13 +488,14 @@ pub async fn send_route(route: Route, receive_time: Instant, source: SourceType)
 }
 
 pub fn check_route(route: &Route, min_profit: u64) -> Option<SwapRoutes> {
-    if let Some(clock) = global_data::get_clock() {
-        if let Some(swap) = optimization::find_profitable_route(route.clone(), &clock) {
-            if swap.profit > min_profit as i64 {
-                return Some(swap);
-            }
-        }
+    let clock = global_data::get_clock()?;
+    let swap = optimization::find_profitable_route(route.clone(), &clock)?;
+    // a profit with no SOL price can't be held against the floor
+    let net_profit = optimization::net_profit_lamports(&swap, 0)?;
+    if net_profit > min_profit as i64 {
+        return Some(swap);
     }
+    metric::OPPORTUNITIES.record_skipped(swap.to_hash(), SkipReason::Floor, net_profit);
 
     None
 }
@@ -1,16 +1,22 @@
 use crate::{
-    arb::PoolType,
-    dex::{meteora, pumpfun, raydium, whirlpool},
+    arb::{PoolType, QuoteError, SwapQuote, transfer_fee},
+    dex::whirlpool::types::token::TransferFee,
     global,
     math::subtract_as_i64,
-    util::amount_with_slippage,
-    wsol_mint,
+    metric,
 };
 use anchor_client::solana_sdk::{clock::Clock, pubkey::Pubkey};
 use anyhow::Result;
-use commons::quote as dlmm_quote;
 use std::panic::{AssertUnwindSafe, catch_unwind};
 
+/// Which `SwapQuote` method a route walk quotes each hop with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QuoteKind {
+    Exact,
+    Estimate,
+    Approximate,
+}
+
 pub fn safe_swap_compute(
     clock: &Clock,
     routes: &[PoolType],
@@ -32,219 +38,385 @@ pub fn swap_compute(
     amount_in: u64,
     mint: &Pubkey,
     adjust_slippage: bool,
+) -> Result<i64> {
+    compute_profit(
+        clock,
+        routes,
+        amount_in,
+        mint,
+        adjust_slippage,
+        QuoteKind::Exact,
+    )
+}
+
+/// `swap_compute` on the pools' unbiased estimates instead of what the program pays out,
+/// for the optimizers to compare amounts on. See `SwapQuote::quote_in_estimate`.
+pub fn estimate_swap_compute(
+    clock: &Clock,
+    routes: &[PoolType],
+    amount_in: u64,
+    mint: &Pubkey,
+    adjust_slippage: bool,
+) -> Result<i64> {
+    compute_profit(
+        clock,
+        routes,
+        amount_in,
+        mint,
+        adjust_slippage,
+        QuoteKind::Estimate,
+    )
+}
+
+/// `safe_swap_compute` for pre-filtering routes, pools missing the data to quote exactly are
+/// quoted approximately. See `SwapQuote::quote_in_approximate`, never size a trade on it.
+pub fn approximate_swap_compute(
+    clock: &Clock,
+    routes: &[PoolType],
+    amount_in: u64,
+    mint: &Pubkey,
+) -> Result<i64> {
+    catch_unwind(AssertUnwindSafe(|| {
+        compute_profit(
+            clock,
+            routes,
+            amount_in,
+            mint,
+            false,
+            QuoteKind::Approximate,
+        )
+    }))
+    .unwrap_or(Ok(0))
+}
+
+fn compute_profit(
+    clock: &Clock,
+    routes: &[PoolType],
+    amount_in: u64,
+    mint: &Pubkey,
+    adjust_slippage: bool,
+    kind: QuoteKind,
 ) -> Result<i64> {
     let mut current_amount = amount_in;
-    let mut next_token_in = mint;
-    let current_timestamp = clock.unix_timestamp as u64;
-    let current_slot = clock.slot;
-    let slippage_bps = global::get_slippage_bps();
+    let mut next_token_in = *mint;
+    let slippage_bps = adjust_slippage.then(global::get_slippage_bps);
 
     for route in routes {
         if current_amount <= 0 {
             return Ok(0);
         }
 
-        (current_amount, next_token_in) = match route {
-            PoolType::Pump(_, data) => {
-                if next_token_in != &wsol_mint() {
-                    let sell_quote = pumpfun::quote::sell_base_input_internal(
-                        current_amount as u128,
-                        0f64,
-                        data.reserves.base_amount as u128,
-                        data.reserves.quote_amount as u128,
-                        20,
-                        5,
-                        80,
-                        data.pool.coin_creator,
-                    )?;
-
-                    (sell_quote.min_quote as u64, &data.pool.quote_mint)
-                } else {
-                    let buy_quote = pumpfun::quote::buy_quote_input_internal(
-                        current_amount as u128,
-                        0f64,
-                        data.reserves.base_amount as u128,
-                        data.reserves.quote_amount as u128,
-                        20,
-                        5,
-                        80,
-                        data.pool.coin_creator,
-                    )?;
-
-                    (buy_quote.base as u64, &data.pool.base_mint)
-                }
-            }
-            PoolType::Meteora(address, data) => {
-                let quote = dlmm_quote::quote_exact_in(
-                    *address,
-                    &data.lb_pair,
-                    current_amount,
-                    &data.lb_pair.token_y_mint != next_token_in,
-                    data.bin_arrays.clone(),
-                    None,
-                    clock,
-                    &data.mint_x_account,
-                    &data.mint_y_account,
-                )?;
-
-                let token_out_mint = if &data.lb_pair.token_x_mint == next_token_in {
-                    &data.lb_pair.token_y_mint
-                } else {
-                    &data.lb_pair.token_x_mint
-                };
-
-                if quote.failed {
-                    // println!("Meteora compute failed {}", address);
-                    return Ok(0);
-                }
-
-                (quote.amount_out, token_out_mint)
-            }
-            PoolType::MeteoraDammv2(_, data) => {
-                let quote = meteora::damm::get_quote(
-                    &data.pool_state,
-                    current_timestamp,
-                    current_slot,
-                    current_amount,
-                    &data.pool_state.token_a_mint == next_token_in,
-                    false,
-                )?;
-
-                let token_out_mint = if &data.pool_state.token_a_mint == next_token_in {
-                    &data.pool_state.token_b_mint
-                } else {
-                    &data.pool_state.token_a_mint
-                };
-
-                (quote.output_amount, token_out_mint)
-            }
-            PoolType::RaydiumAmm(_, data) => {
-                let (swap_direction, token_out_mint) =
-                    if next_token_in == &data.pool_state.coin_mint {
-                        (
-                            raydium::amm::SwapDirection::Coin2PC,
-                            &data.pool_state.pc_mint,
-                        )
-                    } else {
-                        (
-                            raydium::amm::SwapDirection::PC2Coin,
-                            &data.pool_state.coin_mint,
-                        )
-                    };
-
-                let quote = raydium::amm::swap_compute(
-                    &data.pool_state,
-                    &data.vaults,
-                    swap_direction,
-                    current_amount,
-                    true,
-                    0,
-                )?;
-
-                (quote, token_out_mint)
-            }
-            PoolType::RaydiumCpmm(_, data) => {
-                let (a_to_b, token_out_mint) = if &data.pool_state.token_0_mint == next_token_in {
-                    (true, &data.pool_state.token_1_mint)
-                } else {
-                    (false, &data.pool_state.token_0_mint)
-                };
-
-                let quote = raydium::cpmm::swap_calculate(
-                    &data.amm_config,
-                    &data.pool_state,
-                    &data.vaults,
-                    current_amount,
-                    a_to_b,
-                )?;
-
-                (quote.other_amount_threshold, token_out_mint)
-            }
-            PoolType::RaydiumClmm(_, data) => {
-                let (a_to_b, token_out_mint) = if &data.pool_state.token_mint_0 == next_token_in {
-                    (true, &data.pool_state.token_mint_1)
-                } else {
-                    (false, &data.pool_state.token_mint_0)
-                };
-
-                let mut tick_clone = if a_to_b {
-                    data.right_ticks.clone()
-                } else {
-                    data.left_ticks.clone()
-                };
-                let (amount_out, _) =
-                    raydium::clmm::swap_util::get_out_put_amount_and_remaining_accounts(
-                        current_amount,
-                        None,
-                        a_to_b,
-                        true,
-                        0,
-                        &data.pool_state,
-                        &data.tick_array_bitmap_ext,
-                        &mut tick_clone,
-                    )
-                    .unwrap_or_default();
-
-                (amount_out, token_out_mint)
-            }
-            PoolType::Whirlpool(_, data) => {
-                let (a_to_b, token_out_mint) = if &data.pool_state.token_mint_a == next_token_in {
-                    (true, &data.pool_state.token_mint_b)
-                } else {
-                    (false, &data.pool_state.token_mint_a)
-                };
-
-                let tick_arrays = data
-                    .tick_data
-                    .clone()
-                    .map(|(_, tick_array)| Some(tick_array));
-                let quote = whirlpool::quote::swap_quote_by_input_token(
-                    current_amount,
-                    a_to_b,
-                    0,
-                    data.pool_state.clone(),
-                    data.oracle.clone(),
-                    tick_arrays,
-                    current_timestamp,
-                    None,
-                    None,
-                )
-                .unwrap_or_default();
-
-                (quote.token_min_out, token_out_mint)
-            }
-            PoolType::Vertigo(_, data) => {
-                let (amount_out, token_out_mint) = if &data.pool_state.mint_a == next_token_in {
-                    let amount_out = data
-                        .pool_state
-                        .calculate_buy_amount_out(current_amount, current_slot)?;
-                    (amount_out, &data.pool_state.mint_b)
-                } else {
-                    let amount_out = data
-                        .pool_state
-                        .calculate_sell_amount_in(current_amount, current_slot)?;
-                    (amount_out, &data.pool_state.mint_a)
-                };
-
-                (amount_out, token_out_mint)
-            }
-            PoolType::Solfi(_, data) => {
-                let (a_to_b, token_out_mint) = if &data.pool_state.mint_a == next_token_in {
-                    (true, &data.pool_state.mint_b)
-                } else {
-                    (false, &data.pool_state.mint_a)
-                };
-
-                let amount_out = data.reserves.swap_quote(current_amount, a_to_b);
-
-                (amount_out, token_out_mint)
-            }
+        match swap_hop(
+            clock,
+            route,
+            current_amount,
+            &next_token_in,
+            slippage_bps,
+            kind,
+        )? {
+            Some(out) => (current_amount, next_token_in) = out,
+            None => return Ok(0),
+        }
+    }
+
+    Ok(subtract_as_i64(current_amount, amount_in))
+}
+
+#[derive(Debug, Clone)]
+pub struct HopQuote {
+    pub pool: Pubkey,
+    pub mint_in: Pubkey,
+    pub mint_out: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+/// Same walk as `swap_compute`, but keeps the output of every hop.
+/// Stops at the first hop that can't be quoted.
+pub fn simulate_route(
+    clock: &Clock,
+    routes: &[PoolType],
+    amount_in: u64,
+    mint: &Pubkey,
+    adjust_slippage: bool,
+) -> Result<Vec<HopQuote>> {
+    let mut current_amount = amount_in;
+    let mut next_token_in = *mint;
+    let slippage_bps = adjust_slippage.then(global::get_slippage_bps);
+    let mut quotes = Vec::with_capacity(routes.len());
+
+    for route in routes {
+        if current_amount == 0 {
+            break;
+        }
+
+        let Some((amount_out, mint_out)) = swap_hop(
+            clock,
+            route,
+            current_amount,
+            &next_token_in,
+            slippage_bps,
+            QuoteKind::Exact,
+        )?
+        else {
+            break;
+        };
+
+        quotes.push(HopQuote {
+            pool: *route.get_address(),
+            mint_in: next_token_in,
+            mint_out,
+            amount_in: current_amount,
+            amount_out,
+        });
+
+        current_amount = amount_out;
+        next_token_in = mint_out;
+    }
+
+    Ok(quotes)
+}
+
+/// Transfer fees charged on the way into and out of `route`, none when its quote already takes
+/// them off.
+fn hop_transfer_fees(
+    route: &PoolType,
+    mint_in: &Pubkey,
+    mint_out: &Pubkey,
+    epoch: u64,
+) -> (TransferFee, TransferFee) {
+    if route.includes_transfer_fee() {
+        return (TransferFee::default(), TransferFee::default());
+    }
+
+    (
+        transfer_fee::mint_transfer_fee(mint_in, epoch),
+        transfer_fee::mint_transfer_fee(mint_out, epoch),
+    )
+}
+
+/// Quote a single hop, returning the output amount and output mint.
+/// With `slippage_bps` the output is the minimum the pool accepts on chain, otherwise it is
+/// quoted as `kind` says.
+/// `None` means the pool could not quote the swap, a pool that doesn't trade `next_token_in` is
+/// an error.
+///
+/// Token-2022 transfer fees are charged on both legs: the pool is quoted on what it receives
+/// after the input mint's fee, and the output is what reaches the wallet after the output
+/// mint's fee, so chaining hops charges an intermediate mint's fee twice. Pools whose quote
+/// charges the fees itself (DLMM) are quoted on the full amount.
+fn swap_hop(
+    clock: &Clock,
+    route: &PoolType,
+    current_amount: u64,
+    next_token_in: &Pubkey,
+    slippage_bps: Option<u64>,
+    kind: QuoteKind,
+) -> Result<Option<(u64, Pubkey)>> {
+    let _span = tracing::trace_span!("compute_swap").entered();
+    route.check_trades(next_token_in)?;
+    let mint_out = route.get_other_mint(next_token_in);
+    let (fee_in, fee_out) = hop_transfer_fees(route, next_token_in, &mint_out, clock.epoch);
+    let received = transfer_fee::after_transfer_fee(current_amount, fee_in)?;
+
+    let started = std::time::Instant::now();
+    let quote = match slippage_bps {
+        Some(slippage_bps) => route.quote_in_min(clock, next_token_in, received, slippage_bps),
+        None => match kind {
+            QuoteKind::Exact => route.quote_in(clock, next_token_in, received),
+            QuoteKind::Estimate => route.quote_in_estimate(clock, next_token_in, received),
+            QuoteKind::Approximate => route.quote_in_approximate(clock, next_token_in, received),
+        },
+    };
+    metric::HOP_SWAP_LATENCY.record(started.elapsed());
+    if let Err(e) = &quote {
+        metric::QUOTE_ERRORS.record(e);
+    }
+
+    match quote {
+        Ok(amount_out) => {
+            let amount_out = transfer_fee::after_transfer_fee(amount_out, fee_out)?;
+            Ok(Some((amount_out, mint_out)))
+        }
+        Err(QuoteError::NoLiquidity) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arb::{MeteoraDlmmData, RaydiumCpmmData, transfer_fee::tests::mint_data},
+        dex::{meteora, raydium::cpmm},
+        streaming::{AccountDataType, AccountTypeInfo, global_data},
+        token_2022_program,
+    };
+    use anchor_client::solana_sdk::account::Account;
+    use dlmm_interface::LbPairAccount;
+    use std::collections::HashMap;
+
+    fn token_2022_mint(fee: TransferFee, from_epoch: u64) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        let account = Account {
+            lamports: 1,
+            data: mint_data(TransferFee::default(), from_epoch, fee),
+            owner: token_2022_program(),
+            executable: false,
+            rent_epoch: 0,
         };
+        global_data::add_accounts(
+            mint,
+            AccountDataType::Account(account),
+            AccountTypeInfo::Account,
+        );
+        mint
+    }
+
+    fn cpmm_pool(mint_0: Pubkey, mint_1: Pubkey, amount_0: u64, amount_1: u64) -> PoolType {
+        let mut data = vec![0u8; 637];
+        data[0..8].copy_from_slice(&cpmm::POOL_DISCRIMINATOR);
+        data[8 + 5 * 32..8 + 6 * 32].copy_from_slice(mint_0.as_ref());
+        data[8 + 6 * 32..8 + 7 * 32].copy_from_slice(mint_1.as_ref());
 
-        if adjust_slippage {
-            current_amount = amount_with_slippage(current_amount, slippage_bps, false)?;
+        RaydiumCpmmData {
+            pool_address: Pubkey::new_unique(),
+            pool_state: cpmm::PoolState::deserialize(&data).unwrap(),
+            amm_config: cpmm::AmmConfig {
+                trade_fee_rate: 2_500,
+                ..Default::default()
+            },
+            vaults: cpmm::PoolReserves {
+                token_0_vault: Pubkey::new_unique(),
+                token_0_amount: amount_0,
+                token_1_vault: Pubkey::new_unique(),
+                token_1_amount: amount_1,
+            },
         }
+        .into()
     }
 
-    Ok(subtract_as_i64(current_amount, amount_in))
+    fn dlmm_pool(mint_x: Pubkey, mint_y: Pubkey) -> PoolType {
+        let mut data = vec![0u8; meteora::dlmm::POOL_ACCOUNT_LEN];
+        data[0..8].copy_from_slice(&meteora::dlmm::POOL_DISCRIMINATOR);
+        let mut lb_pair = LbPairAccount::deserialize(&data).unwrap().0;
+        lb_pair.token_x_mint = mint_x;
+        lb_pair.token_y_mint = mint_y;
+        let mint_account = |mint: &Pubkey| match global_data::get_account(mint) {
+            Some(AccountDataType::Account(account)) => account,
+            _ => panic!("mint {} not cached", mint),
+        };
+
+        MeteoraDlmmData {
+            pool_address: Pubkey::new_unique(),
+            lb_pair,
+            mint_x_account: mint_account(&mint_x),
+            mint_y_account: mint_account(&mint_y),
+            bin_arrays: HashMap::new(),
+        }
+        .into()
+    }
+
+    // fee rounds up and is capped at the maximum
+    fn charge(amount: u64, fee_bps: u64, max_fee: u64) -> u64 {
+        amount - (amount * fee_bps).div_ceil(10_000).min(max_fee)
+    }
+
+    // `amount` after paying both fees of a hop, with the pool itself left out
+    fn after_fees(amount: u64, fee_in: TransferFee, fee_out: TransferFee) -> u64 {
+        let received = transfer_fee::after_transfer_fee(amount, fee_in).unwrap();
+        transfer_fee::after_transfer_fee(received, fee_out).unwrap()
+    }
+
+    #[test]
+    fn transfer_fees_compound_across_hops() {
+        let clock = Clock {
+            epoch: 700,
+            ..Clock::default()
+        };
+        let mints = [
+            token_2022_mint(TransferFee::new(100), 700),
+            token_2022_mint(TransferFee::new_with_max(50, 2_000), 650),
+            token_2022_mint(TransferFee::new(30), 700),
+        ];
+        let route = [
+            cpmm_pool(mints[0], mints[1], 1_000_000_000_000, 150_000_000_000),
+            cpmm_pool(mints[1], mints[2], 150_000_000_000, 40_000_000_000),
+            cpmm_pool(mints[2], mints[0], 40_000_000_000, 1_010_000_000_000),
+        ];
+        let amount_in = 5_000_000_000;
+
+        let hops = simulate_route(&clock, &route, amount_in, &mints[0], false).unwrap();
+        assert_eq!(hops.len(), 3);
+
+        // each hop pays the input mint's fee on the way into the pool, and the output mint's fee
+        // on the way out before the next hop pays it again
+        let received = charge(amount_in, 100, u64::MAX);
+        let out = route[0].quote_in(&clock, &mints[0], received).unwrap();
+        let hop_1 = charge(out, 50, 2_000);
+        assert_eq!(hops[0].amount_out, hop_1);
+        assert_eq!(hop_1, out - 2_000);
+
+        let received = charge(hop_1, 50, 2_000);
+        let out = route[1].quote_in(&clock, &mints[1], received).unwrap();
+        let hop_2 = charge(out, 30, u64::MAX);
+        assert_eq!(hops[1].amount_in, hop_1);
+        assert_eq!(hops[1].amount_out, hop_2);
+
+        let received = charge(hop_2, 30, u64::MAX);
+        let out = route[2].quote_in(&clock, &mints[2], received).unwrap();
+        let hop_3 = charge(out, 100, u64::MAX);
+        assert_eq!(hops[2].amount_out, hop_3);
+
+        let profit = swap_compute(&clock, &route, amount_in, &mints[0], false).unwrap();
+        assert_eq!(profit, hop_3 as i64 - amount_in as i64);
+
+        // a DLMM hop is quoted on the full amount, its quote takes both fees off itself
+        let dlmm = dlmm_pool(mints[0], mints[1]);
+        assert!(dlmm.includes_transfer_fee());
+        let (fee_in, fee_out) = hop_transfer_fees(&dlmm, &mints[0], &mints[1], clock.epoch);
+        assert_eq!(after_fees(amount_in, fee_in, fee_out), amount_in);
+        let (fee_in, fee_out) = hop_transfer_fees(&route[0], &mints[0], &mints[1], clock.epoch);
+        assert_eq!(
+            after_fees(amount_in, fee_in, fee_out),
+            charge(charge(amount_in, 100, u64::MAX), 50, 2_000)
+        );
+    }
+
+    #[test]
+    fn routes_through_a_pool_without_the_mint_are_rejected() {
+        let clock = Clock::default();
+        let mints = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        // the second pool doesn't trade mints[1], the first hop's output
+        let route = [
+            cpmm_pool(mints[0], mints[1], 1_000_000_000, 1_000_000_000),
+            cpmm_pool(mints[2], mints[0], 1_000_000_000, 1_000_000_000),
+        ];
+
+        let err = swap_compute(&clock, &route, 1_000_000, &mints[0], false).unwrap_err();
+        assert!(err.to_string().contains(&mints[1].to_string()));
+        assert!(simulate_route(&clock, &route, 1_000_000, &mints[0], false).is_err());
+        assert!(simulate_route(&clock, &route[..1], 1_000_000, &mints[0], false).is_ok());
+    }
+
+    #[test]
+    fn transfer_fees_wait_for_their_epoch() {
+        let clock = Clock {
+            epoch: 10,
+            ..Clock::default()
+        };
+        let (mint_a, mint_b) = (
+            token_2022_mint(TransferFee::new(100), 11),
+            token_2022_mint(TransferFee::new(100), 11),
+        );
+        let route = [cpmm_pool(mint_a, mint_b, 1_000_000_000, 1_000_000_000)];
+
+        let hops = simulate_route(&clock, &route, 1_000_000, &mint_a, false).unwrap();
+        let out = route[0].quote_in(&clock, &mint_a, 1_000_000).unwrap();
+        assert_eq!(hops[0].amount_out, out);
+    }
 }
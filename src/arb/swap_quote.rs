@@ -0,0 +1,973 @@
+use super::*;
+use crate::{
+    dex::{meteora, pumpfun, raydium, solfi, vertigo, whirlpool},
+    global,
+    util::amount_with_slippage,
+    wsol_mint,
+};
+use commons::quote as dlmm_quote;
+use std::collections::VecDeque;
+use std::fmt;
+use tracing::warn;
+
+const BPS_DENOMINATOR: u64 = 10_000;
+// raydium and orca fee rates are in hundredths of a bps
+const HUNDREDTHS_OF_BPS: u64 = 100;
+
+#[derive(Debug)]
+pub enum QuoteError {
+    /// The pool can't fill the amount, bins or ticks ran out or reserves are empty
+    NoLiquidity,
+    /// The pool has no quote for this direction
+    Unsupported,
+    /// The pool can't trade, its activation point is still ahead or its status disallows swaps
+    Disabled,
+    /// Math or account data error from the DEX implementation
+    Dex(anyhow::Error),
+}
+
+impl fmt::Display for QuoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuoteError::NoLiquidity => write!(f, "Not enough liquidity"),
+            QuoteError::Unsupported => write!(f, "Quote not supported"),
+            QuoteError::Disabled => write!(f, "Pool not tradable"),
+            QuoteError::Dex(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for QuoteError {}
+
+impl From<anyhow::Error> for QuoteError {
+    fn from(e: anyhow::Error) -> Self {
+        QuoteError::Dex(e)
+    }
+}
+
+/// Quoting interface shared by every pool data type, `PoolType` dispatches to it.
+pub trait SwapQuote {
+    /// Output amount for an exact `amount_in` of `mint_in`.
+    fn quote_in(&self, clock: &Clock, mint_in: &Pubkey, amount_in: u64) -> Result<u64, QuoteError>;
+
+    /// Unbiased estimate of `quote_in`, for comparing amounts. Where the program rounds the
+    /// output down, `quote_in` is short of the exact output by up to a unit and the optimizers
+    /// would pass over routes whose margin is in that rounding.
+    fn quote_in_estimate(
+        &self,
+        clock: &Clock,
+        mint_in: &Pubkey,
+        amount_in: u64,
+    ) -> Result<u64, QuoteError> {
+        self.quote_in(clock, mint_in, amount_in)
+    }
+
+    /// Rough `quote_in` for pools missing the data to quote exactly, e.g. a CLMM pool whose tick
+    /// arrays aren't loaded yet. Only for pre-filtering and ranking routes, never for sizing a
+    /// trade.
+    fn quote_in_approximate(
+        &self,
+        clock: &Clock,
+        mint_in: &Pubkey,
+        amount_in: u64,
+    ) -> Result<u64, QuoteError> {
+        self.quote_in(clock, mint_in, amount_in)
+    }
+
+    /// Minimum output still accepted on chain with `slippage_bps` of tolerance.
+    fn quote_in_min(
+        &self,
+        clock: &Clock,
+        mint_in: &Pubkey,
+        amount_in: u64,
+        slippage_bps: u64,
+    ) -> Result<u64, QuoteError> {
+        let amount_out = self.quote_in(clock, mint_in, amount_in)?;
+        Ok(amount_with_slippage(amount_out, slippage_bps, false)?)
+    }
+
+    /// Input amount needed to receive an exact `amount_out` of `mint_out`.
+    fn quote_out(
+        &self,
+        clock: &Clock,
+        mint_out: &Pubkey,
+        amount_out: u64,
+    ) -> Result<u64, QuoteError>;
+
+    /// Whether the quotes already take Token-2022 transfer fees off the input and the output,
+    /// so the route walk mustn't charge them again.
+    fn includes_transfer_fee(&self) -> bool {
+        false
+    }
+
+    /// Price of `base` in the other mint, and the other mint.
+    fn spot_price(&self, base: &Pubkey) -> (f64, Pubkey);
+
+    fn mints(&self) -> (Pubkey, Pubkey);
+}
+
+/// With `bot.skip_pre_activation`, refuse to quote a Meteora pool before its activation point,
+/// a slot or a unix timestamp depending on `activation_type`. Both programs encode it the same.
+fn check_activation(
+    clock: &Clock,
+    activation_type: u8,
+    activation_point: u64,
+) -> Result<(), QuoteError> {
+    if global::skip_pre_activation() && before_activation(clock, activation_type, activation_point)
+    {
+        return Err(QuoteError::Disabled);
+    }
+
+    Ok(())
+}
+
+// an unknown activation type is treated as activated, the program decides when trading
+fn before_activation(clock: &Clock, activation_type: u8, activation_point: u64) -> bool {
+    let current_point = match meteora::damm::ActivationType::try_from(activation_type) {
+        Ok(meteora::damm::ActivationType::Slot) => clock.slot,
+        Ok(meteora::damm::ActivationType::Timestamp) => clock.unix_timestamp as u64,
+        Err(_) => return false,
+    };
+    current_point < activation_point
+}
+
+impl SwapQuote for MeteoraDlmmData {
+    fn quote_in(&self, clock: &Clock, mint_in: &Pubkey, amount_in: u64) -> Result<u64, QuoteError> {
+        check_activation(
+            clock,
+            self.lb_pair.activation_type,
+            self.lb_pair.activation_point,
+        )?;
+        let quote = dlmm_quote::quote_exact_in(
+            self.pool_address,
+            &self.lb_pair,
+            amount_in,
+            &self.lb_pair.token_y_mint != mint_in,
+            self.bin_arrays.clone(),
+            None,
+            clock,
+            &self.mint_x_account,
+            &self.mint_y_account,
+        )?;
+
+        if quote.failed {
+            return Err(QuoteError::NoLiquidity);
+        }
+
+        Ok(quote.amount_out)
+    }
+
+    fn quote_out(&self, clock: &Clock, _: &Pubkey, _: u64) -> Result<u64, QuoteError> {
+        check_activation(
+            clock,
+            self.lb_pair.activation_type,
+            self.lb_pair.activation_point,
+        )?;
+        Err(QuoteError::Unsupported)
+    }
+
+    // quote_exact_in reads the fees from the mint accounts
+    fn includes_transfer_fee(&self) -> bool {
+        true
+    }
+
+    fn spot_price(&self, base: &Pubkey) -> (f64, Pubkey) {
+        let price = meteora::utils::compute_price(self.lb_pair.active_id, self.lb_pair.bin_step);
+        if &self.lb_pair.token_x_mint == base {
+            (price, self.lb_pair.token_y_mint)
+        } else {
+            (1.0 / price, self.lb_pair.token_x_mint)
+        }
+    }
+
+    fn mints(&self) -> (Pubkey, Pubkey) {
+        (self.lb_pair.token_x_mint, self.lb_pair.token_y_mint)
+    }
+}
+
+/// Pump AMM fees from the global config, or all of `fees.pumpfun` as LP fee when it is set.
+fn pump_fee_bps() -> pumpfun::FeeBps {
+    match global::get_fee_override_bps("pumpfun") {
+        Some(fee_bps) => pumpfun::FeeBps {
+            lp: fee_bps as u128,
+            protocol: 0,
+            coin_creator: 0,
+        },
+        None => pumpfun::fee_bps(),
+    }
+}
+
+impl SwapQuote for PumpAmmData {
+    fn quote_in(&self, _: &Clock, mint_in: &Pubkey, amount_in: u64) -> Result<u64, QuoteError> {
+        let fees = pump_fee_bps();
+        if mint_in != &wsol_mint() {
+            let sell_quote = pumpfun::quote::sell_base_input_internal(
+                amount_in as u128,
+                0f64,
+                self.reserves.base_amount as u128,
+                self.reserves.quote_amount as u128,
+                fees.lp,
+                fees.protocol,
+                fees.coin_creator,
+                self.pool.coin_creator,
+            )?;
+
+            Ok(sell_quote.min_quote as u64)
+        } else {
+            let buy_quote = pumpfun::quote::buy_quote_input_internal(
+                amount_in as u128,
+                0f64,
+                self.reserves.base_amount as u128,
+                self.reserves.quote_amount as u128,
+                fees.lp,
+                fees.protocol,
+                fees.coin_creator,
+                self.pool.coin_creator,
+            )?;
+
+            Ok(buy_quote.base as u64)
+        }
+    }
+
+    fn quote_out(&self, _: &Clock, mint_out: &Pubkey, amount_out: u64) -> Result<u64, QuoteError> {
+        let fees = pump_fee_bps();
+        if mint_out == &wsol_mint() {
+            let sell_quote = pumpfun::quote::sell_quote_input_internal(
+                amount_out as u128,
+                0f64,
+                self.reserves.base_amount as u128,
+                self.reserves.quote_amount as u128,
+                fees.lp,
+                fees.protocol,
+                fees.coin_creator,
+                self.pool.coin_creator,
+            )?;
+
+            Ok(sell_quote.base as u64)
+        } else {
+            let buy_quote = pumpfun::quote::buy_base_input_internal(
+                amount_out as u128,
+                0f64,
+                self.reserves.base_amount as u128,
+                self.reserves.quote_amount as u128,
+                fees.lp,
+                fees.protocol,
+                fees.coin_creator,
+                self.pool.coin_creator,
+            )?;
+
+            Ok(buy_quote.ui_quote as u64)
+        }
+    }
+
+    fn spot_price(&self, base: &Pubkey) -> (f64, Pubkey) {
+        let quote = self.reserves.quote_amount as f64;
+        let base_amount = self.reserves.base_amount as f64;
+
+        if &self.pool.base_mint == base {
+            (quote / base_amount, self.pool.quote_mint)
+        } else {
+            (base_amount / quote, self.pool.base_mint)
+        }
+    }
+
+    fn mints(&self) -> (Pubkey, Pubkey) {
+        (self.pool.base_mint, self.pool.quote_mint)
+    }
+}
+
+impl MeteoraDammv2Data {
+    /// The pool with `fees.meteora_damm` as a flat base fee: no fee schedule, no dynamic fee.
+    fn fee_overridden(&self) -> Option<meteora::damm::Pool> {
+        let fee_bps = global::get_fee_override_bps("meteora_damm")?;
+        let mut pool_state = self.pool_state.clone();
+        let fees = &mut pool_state.pool_fees;
+        fees.base_fee.cliff_fee_numerator =
+            fee_bps * meteora::damm::constants::fee::FEE_DENOMINATOR / BPS_DENOMINATOR;
+        fees.base_fee.period_frequency = 0;
+        fees.dynamic_fee.initialized = 0;
+        Some(pool_state)
+    }
+
+    fn quote(
+        &self,
+        clock: &Clock,
+        mint_in: &Pubkey,
+        amount_in: u64,
+        output_rounding: meteora::damm::Rounding,
+    ) -> Result<u64, QuoteError> {
+        check_activation(
+            clock,
+            self.pool_state.activation_type,
+            self.pool_state.activation_point,
+        )?;
+        let overridden = self.fee_overridden();
+        let quote = meteora::damm::get_quote_with_rounding(
+            overridden.as_ref().unwrap_or(&self.pool_state),
+            clock.unix_timestamp as u64,
+            clock.slot,
+            amount_in,
+            &self.pool_state.token_a_mint == mint_in,
+            global::damm_has_referral(),
+            output_rounding,
+        )?;
+
+        Ok(quote.output_amount)
+    }
+}
+
+impl SwapQuote for MeteoraDammv2Data {
+    fn quote_in(&self, clock: &Clock, mint_in: &Pubkey, amount_in: u64) -> Result<u64, QuoteError> {
+        self.quote(clock, mint_in, amount_in, meteora::damm::Rounding::Down)
+    }
+
+    fn quote_in_estimate(
+        &self,
+        clock: &Clock,
+        mint_in: &Pubkey,
+        amount_in: u64,
+    ) -> Result<u64, QuoteError> {
+        self.quote(clock, mint_in, amount_in, meteora::damm::Rounding::Nearest)
+    }
+
+    fn quote_out(&self, _: &Clock, _: &Pubkey, _: u64) -> Result<u64, QuoteError> {
+        Err(QuoteError::Unsupported)
+    }
+
+    fn spot_price(&self, base: &Pubkey) -> (f64, Pubkey) {
+        let price = self.pool_state.get_price();
+        if &self.pool_state.token_a_mint == base {
+            (price, self.pool_state.token_b_mint)
+        } else {
+            (1.0 / price, self.pool_state.token_a_mint)
+        }
+    }
+
+    fn mints(&self) -> (Pubkey, Pubkey) {
+        (self.pool_state.token_a_mint, self.pool_state.token_b_mint)
+    }
+}
+
+impl RaydiumAmmData {
+    /// The pool with `fees.raydium_amm` as its swap fee.
+    fn fee_overridden(&self) -> Option<raydium::amm::AmmInfo> {
+        let fee_bps = global::get_fee_override_bps("raydium_amm")?;
+        let mut pool_state = self.pool_state.clone();
+        pool_state.fees.swap_fee_numerator = fee_bps;
+        pool_state.fees.swap_fee_denominator = BPS_DENOMINATOR;
+        Some(pool_state)
+    }
+}
+
+impl SwapQuote for RaydiumAmmData {
+    fn quote_in(&self, _: &Clock, mint_in: &Pubkey, amount_in: u64) -> Result<u64, QuoteError> {
+        if !self.pool_state.can_swap() {
+            return Err(QuoteError::Disabled);
+        }
+
+        let swap_direction = if mint_in == &self.pool_state.coin_mint {
+            raydium::amm::SwapDirection::Coin2PC
+        } else {
+            raydium::amm::SwapDirection::PC2Coin
+        };
+
+        let overridden = self.fee_overridden();
+        Ok(raydium::amm::swap_compute(
+            overridden.as_ref().unwrap_or(&self.pool_state),
+            &self.vaults,
+            swap_direction,
+            amount_in,
+            true,
+            0,
+        )?)
+    }
+
+    fn quote_out(&self, _: &Clock, mint_out: &Pubkey, amount_out: u64) -> Result<u64, QuoteError> {
+        if !self.pool_state.can_swap() {
+            return Err(QuoteError::Disabled);
+        }
+
+        let swap_direction = if mint_out == &self.pool_state.pc_mint {
+            raydium::amm::SwapDirection::Coin2PC
+        } else {
+            raydium::amm::SwapDirection::PC2Coin
+        };
+
+        let overridden = self.fee_overridden();
+        Ok(raydium::amm::swap_compute(
+            overridden.as_ref().unwrap_or(&self.pool_state),
+            &self.vaults,
+            swap_direction,
+            amount_out,
+            false,
+            0,
+        )?)
+    }
+
+    fn spot_price(&self, base: &Pubkey) -> (f64, Pubkey) {
+        let pc_vault = self.vaults.pc_vault_amount as f64;
+        let coin_vault = self.vaults.coin_vault_amount as f64;
+
+        if &self.pool_state.coin_mint == base {
+            (pc_vault / coin_vault, self.pool_state.pc_mint)
+        } else {
+            (coin_vault / pc_vault, self.pool_state.coin_mint)
+        }
+    }
+
+    fn mints(&self) -> (Pubkey, Pubkey) {
+        (self.pool_state.pc_mint, self.pool_state.coin_mint)
+    }
+}
+
+impl RaydiumCpmmData {
+    /// The AMM config with `fees.raydium_cpmm` as its trade fee.
+    fn fee_overridden(&self) -> Option<raydium::cpmm::AmmConfig> {
+        let fee_bps = global::get_fee_override_bps("raydium_cpmm")?;
+        let mut amm_config = self.amm_config.clone();
+        amm_config.trade_fee_rate = fee_bps * HUNDREDTHS_OF_BPS;
+        Some(amm_config)
+    }
+}
+
+impl SwapQuote for RaydiumCpmmData {
+    fn quote_in(&self, _: &Clock, mint_in: &Pubkey, amount_in: u64) -> Result<u64, QuoteError> {
+        let overridden = self.fee_overridden();
+        let quote = raydium::cpmm::swap_calculate(
+            overridden.as_ref().unwrap_or(&self.amm_config),
+            &self.pool_state,
+            &self.vaults,
+            amount_in,
+            &self.pool_state.token_0_mint == mint_in,
+        )?;
+
+        Ok(quote.other_amount_threshold)
+    }
+
+    fn quote_out(&self, _: &Clock, mint_out: &Pubkey, amount_out: u64) -> Result<u64, QuoteError> {
+        let overridden = self.fee_overridden();
+        let quote = raydium::cpmm::swap_calculate_base_output(
+            overridden.as_ref().unwrap_or(&self.amm_config),
+            &self.pool_state,
+            &self.vaults,
+            amount_out,
+            &self.pool_state.token_1_mint == mint_out,
+        )?;
+
+        Ok(quote.other_amount_threshold)
+    }
+
+    fn spot_price(&self, base: &Pubkey) -> (f64, Pubkey) {
+        let token_0_amount = self.vaults.token_0_amount as f64;
+        let token_1_amount = self.vaults.token_1_amount as f64;
+
+        if &self.pool_state.token_0_mint == base {
+            (
+                token_1_amount / token_0_amount,
+                self.pool_state.token_1_mint,
+            )
+        } else {
+            (
+                token_0_amount / token_1_amount,
+                self.pool_state.token_0_mint,
+            )
+        }
+    }
+
+    fn mints(&self) -> (Pubkey, Pubkey) {
+        (self.pool_state.token_0_mint, self.pool_state.token_1_mint)
+    }
+}
+
+impl RaydiumClmmData {
+    /// Tick arrays a swap in this direction walks through.
+    fn swap_ticks(&self, a_to_b: bool) -> &VecDeque<raydium::clmm::tick_array::TickArrayState> {
+        if a_to_b {
+            &self.right_ticks
+        } else {
+            &self.left_ticks
+        }
+    }
+
+    // fees.raydium_clmm, the AMM config's trade fee isn't loaded and is quoted as 0
+    fn trade_fee_rate() -> u32 {
+        global::get_fee_override_bps("raydium_clmm")
+            .map_or(0, |fee_bps| (fee_bps * HUNDREDTHS_OF_BPS) as u32)
+    }
+
+    fn quote(&self, amount: u64, a_to_b: bool, is_base_input: bool) -> Result<u64, QuoteError> {
+        let mut tick_clone = self.swap_ticks(a_to_b).clone();
+        let trade_fee_rate = Self::trade_fee_rate();
+        let (amount, _, state) = if is_base_input {
+            raydium::clmm::swap_util::get_out_put_amount_and_remaining_accounts(
+                amount,
+                None,
+                a_to_b,
+                true,
+                trade_fee_rate,
+                &self.pool_state,
+                &self.tick_array_bitmap_ext,
+                &mut tick_clone,
+            )
+        } else {
+            raydium::clmm::swap_util::get_input_amount_for_output(
+                amount,
+                None,
+                a_to_b,
+                trade_fee_rate,
+                &self.pool_state,
+                &self.tick_array_bitmap_ext,
+                &mut tick_clone,
+            )
+        }
+        .map_err(|_| QuoteError::NoLiquidity)?;
+
+        if global::clmm_check_swap_state() {
+            self.check_swap_state(&state);
+        }
+
+        Ok(amount)
+    }
+
+    fn check_swap_state(&self, state: &raydium::clmm::swap_util::SwapState) {
+        if let Err(reason) = raydium::clmm::swap_util::check_final_state(state) {
+            warn!(
+                "CLMM pool {} quote ended inconsistent: {}",
+                self.pool_address, reason
+            );
+        }
+    }
+}
+
+impl SwapQuote for RaydiumClmmData {
+    fn quote_in(&self, _: &Clock, mint_in: &Pubkey, amount_in: u64) -> Result<u64, QuoteError> {
+        self.quote(amount_in, &self.pool_state.token_mint_0 == mint_in, true)
+    }
+
+    /// Without tick arrays for the direction, see `PoolState::approximate_price_impact`.
+    fn quote_in_approximate(
+        &self,
+        clock: &Clock,
+        mint_in: &Pubkey,
+        amount_in: u64,
+    ) -> Result<u64, QuoteError> {
+        let a_to_b = &self.pool_state.token_mint_0 == mint_in;
+        if !self.swap_ticks(a_to_b).is_empty() {
+            return self.quote_in(clock, mint_in, amount_in);
+        }
+
+        let denominator = raydium::clmm::swap_math::FEE_RATE_DENOMINATOR_VALUE;
+        let fee_rate = Self::trade_fee_rate().min(denominator);
+        let amount_less_fee =
+            (amount_in as u128 * (denominator - fee_rate) as u128 / denominator as u128) as u64;
+        self.pool_state
+            .approximate_price_impact(amount_less_fee, a_to_b)
+            .map(|(amount_out, _)| amount_out)
+            .ok_or(QuoteError::NoLiquidity)
+    }
+
+    fn quote_out(&self, _: &Clock, mint_out: &Pubkey, amount_out: u64) -> Result<u64, QuoteError> {
+        self.quote(amount_out, &self.pool_state.token_mint_1 == mint_out, false)
+    }
+
+    fn spot_price(&self, base: &Pubkey) -> (f64, Pubkey) {
+        // price token 1 / token 0
+        let price = self.pool_state.get_price();
+        if &self.pool_state.token_mint_0 == base {
+            (price, self.pool_state.token_mint_1)
+        } else {
+            (1.0 / price, self.pool_state.token_mint_0)
+        }
+    }
+
+    fn mints(&self) -> (Pubkey, Pubkey) {
+        (self.pool_state.token_mint_0, self.pool_state.token_mint_1)
+    }
+}
+
+impl WhirlpoolData {
+    /// Pool and oracle to quote with. `fees.whirlpool` replaces the fee rate and quotes the pool
+    /// as one without adaptive fee.
+    fn fee_state(
+        &self,
+    ) -> (
+        whirlpool::state::Whirlpool,
+        Option<whirlpool::state::oracle::Oracle>,
+    ) {
+        let mut pool_state = self.pool_state.clone();
+        match global::get_fee_override_bps("whirlpool") {
+            Some(fee_bps) => {
+                pool_state.fee_rate = (fee_bps * HUNDREDTHS_OF_BPS).min(u16::MAX as u64) as u16;
+                pool_state.fee_tier_index_seed = pool_state.tick_spacing.to_le_bytes();
+                (pool_state, None)
+            }
+            None => (pool_state, self.oracle.clone()),
+        }
+    }
+
+    fn quote_exact_in(
+        &self,
+        clock: &Clock,
+        mint_in: &Pubkey,
+        amount_in: u64,
+        slippage_bps: u64,
+    ) -> Result<whirlpool::types::swap::ExactInSwapQuote, QuoteError> {
+        let tick_arrays = self
+            .tick_data
+            .clone()
+            .map(|(_, tick_array)| Some(tick_array));
+        let (pool_state, oracle) = self.fee_state();
+        whirlpool::quote::swap_quote_by_input_token(
+            amount_in,
+            &self.pool_state.token_mint_a == mint_in,
+            slippage_bps.min(u16::MAX as u64) as u16,
+            pool_state,
+            oracle,
+            tick_arrays,
+            clock.unix_timestamp as u64,
+            None,
+            None,
+        )
+        .map_err(|_| QuoteError::NoLiquidity)
+    }
+}
+
+impl SwapQuote for WhirlpoolData {
+    fn quote_in(&self, clock: &Clock, mint_in: &Pubkey, amount_in: u64) -> Result<u64, QuoteError> {
+        Ok(self
+            .quote_exact_in(clock, mint_in, amount_in, 0)?
+            .token_est_out)
+    }
+
+    fn quote_in_min(
+        &self,
+        clock: &Clock,
+        mint_in: &Pubkey,
+        amount_in: u64,
+        slippage_bps: u64,
+    ) -> Result<u64, QuoteError> {
+        Ok(self
+            .quote_exact_in(clock, mint_in, amount_in, slippage_bps)?
+            .token_min_out)
+    }
+
+    fn quote_out(
+        &self,
+        clock: &Clock,
+        mint_out: &Pubkey,
+        amount_out: u64,
+    ) -> Result<u64, QuoteError> {
+        let tick_arrays = self
+            .tick_data
+            .clone()
+            .map(|(_, tick_array)| Some(tick_array));
+        let (pool_state, oracle) = self.fee_state();
+        let quote = whirlpool::quote::swap_quote_by_output_token(
+            amount_out,
+            &self.pool_state.token_mint_a == mint_out,
+            0,
+            pool_state,
+            oracle,
+            tick_arrays,
+            clock.unix_timestamp as u64,
+            None,
+            None,
+        )
+        .map_err(|_| QuoteError::NoLiquidity)?;
+
+        Ok(quote.token_est_in)
+    }
+
+    fn spot_price(&self, base: &Pubkey) -> (f64, Pubkey) {
+        // price token 1 / token 0
+        let price = self.pool_state.get_price();
+        if &self.pool_state.token_mint_a == base {
+            (price, self.pool_state.token_mint_b)
+        } else {
+            (1.0 / price, self.pool_state.token_mint_a)
+        }
+    }
+
+    fn mints(&self) -> (Pubkey, Pubkey) {
+        (self.pool_state.token_mint_a, self.pool_state.token_mint_b)
+    }
+}
+
+impl VertigoData {
+    /// The pool with `fees.vertigo` as a flat fee instead of the decaying launch fee.
+    fn fee_overridden(&self) -> Option<vertigo::Pool> {
+        let fee_bps = global::get_fee_override_bps("vertigo")?;
+        let mut pool_state = self.pool_state.clone();
+        pool_state.fee_params.royalties_bps = fee_bps.min(BPS_DENOMINATOR) as u16;
+        pool_state.fee_params.reference = 0;
+        pool_state.fee_params.normalization_period = 0;
+        Some(pool_state)
+    }
+}
+
+impl SwapQuote for VertigoData {
+    fn quote_in(&self, clock: &Clock, mint_in: &Pubkey, amount_in: u64) -> Result<u64, QuoteError> {
+        let overridden = self.fee_overridden();
+        let pool_state = overridden.as_ref().unwrap_or(&self.pool_state);
+        if &self.pool_state.mint_a == mint_in {
+            Ok(pool_state.calculate_buy_amount_out(amount_in, clock.slot)?)
+        } else {
+            Ok(pool_state.calculate_sell_amount_out(amount_in, clock.slot)?)
+        }
+    }
+
+    fn quote_out(
+        &self,
+        clock: &Clock,
+        mint_out: &Pubkey,
+        amount_out: u64,
+    ) -> Result<u64, QuoteError> {
+        let overridden = self.fee_overridden();
+        let pool_state = overridden.as_ref().unwrap_or(&self.pool_state);
+        if &self.pool_state.mint_b == mint_out {
+            Ok(pool_state.calculate_buy_amount_in(amount_out, clock.slot)?)
+        } else {
+            Ok(pool_state.calculate_sell_amount_in(amount_out, clock.slot)?)
+        }
+    }
+
+    fn spot_price(&self, base: &Pubkey) -> (f64, Pubkey) {
+        if &self.pool_state.mint_a == base {
+            (self.pool_state.get_price_a_in_b(), self.pool_state.mint_b)
+        } else {
+            (self.pool_state.get_price_b_in_a(), self.pool_state.mint_a)
+        }
+    }
+
+    fn mints(&self) -> (Pubkey, Pubkey) {
+        (self.pool_state.mint_a, self.pool_state.mint_b)
+    }
+}
+
+impl SolfiData {
+    fn is_dust(&self) -> bool {
+        self.reserves
+            .is_below_min_reserve(global::get_solfi_min_reserve())
+    }
+
+    fn fee_bps() -> u64 {
+        global::get_fee_override_bps("solfi").unwrap_or(solfi::FEE_BPS)
+    }
+}
+
+impl SwapQuote for SolfiData {
+    fn quote_in(&self, _: &Clock, mint_in: &Pubkey, amount_in: u64) -> Result<u64, QuoteError> {
+        if self.is_dust() {
+            return Err(QuoteError::NoLiquidity);
+        }
+        Ok(self.reserves.swap_quote(
+            amount_in,
+            &self.pool_state.mint_a == mint_in,
+            Self::fee_bps(),
+        ))
+    }
+
+    fn quote_out(&self, _: &Clock, mint_out: &Pubkey, amount_out: u64) -> Result<u64, QuoteError> {
+        if self.is_dust() {
+            return Err(QuoteError::NoLiquidity);
+        }
+        if &self.pool_state.mint_b == mint_out {
+            Ok(self
+                .reserves
+                .calculate_amount_a_in_for_b_out(amount_out, Self::fee_bps()))
+        } else {
+            Ok(self
+                .reserves
+                .calculate_amount_b_in_for_a_out(amount_out, Self::fee_bps()))
+        }
+    }
+
+    fn spot_price(&self, base: &Pubkey) -> (f64, Pubkey) {
+        let other = if &self.pool_state.mint_a == base {
+            self.pool_state.mint_b
+        } else {
+            self.pool_state.mint_a
+        };
+        if self.is_dust() {
+            return (0.0, other);
+        }
+
+        if &self.pool_state.mint_a == base {
+            (self.reserves.get_price_a_in_b(), other)
+        } else {
+            (self.reserves.get_price_b_in_a(), other)
+        }
+    }
+
+    fn mints(&self) -> (Pubkey, Pubkey) {
+        (self.pool_state.mint_a, self.pool_state.mint_b)
+    }
+}
+
+impl PoolType {
+    #[inline]
+    fn as_quote(&self) -> &dyn SwapQuote {
+        match self {
+            PoolType::Meteora(_, data) => data,
+            PoolType::Pump(_, data) => data,
+            PoolType::MeteoraDammv2(_, data) => data,
+            PoolType::RaydiumAmm(_, data) => data,
+            PoolType::RaydiumCpmm(_, data) => data,
+            PoolType::RaydiumClmm(_, data) => data,
+            PoolType::Whirlpool(_, data) => data,
+            PoolType::Vertigo(_, data) => data,
+            PoolType::Solfi(_, data) => data,
+        }
+    }
+}
+
+impl SwapQuote for PoolType {
+    fn quote_in(&self, clock: &Clock, mint_in: &Pubkey, amount_in: u64) -> Result<u64, QuoteError> {
+        self.as_quote().quote_in(clock, mint_in, amount_in)
+    }
+
+    fn quote_in_estimate(
+        &self,
+        clock: &Clock,
+        mint_in: &Pubkey,
+        amount_in: u64,
+    ) -> Result<u64, QuoteError> {
+        self.as_quote().quote_in_estimate(clock, mint_in, amount_in)
+    }
+
+    fn quote_in_approximate(
+        &self,
+        clock: &Clock,
+        mint_in: &Pubkey,
+        amount_in: u64,
+    ) -> Result<u64, QuoteError> {
+        self.as_quote()
+            .quote_in_approximate(clock, mint_in, amount_in)
+    }
+
+    fn quote_in_min(
+        &self,
+        clock: &Clock,
+        mint_in: &Pubkey,
+        amount_in: u64,
+        slippage_bps: u64,
+    ) -> Result<u64, QuoteError> {
+        self.as_quote()
+            .quote_in_min(clock, mint_in, amount_in, slippage_bps)
+    }
+
+    fn quote_out(
+        &self,
+        clock: &Clock,
+        mint_out: &Pubkey,
+        amount_out: u64,
+    ) -> Result<u64, QuoteError> {
+        self.as_quote().quote_out(clock, mint_out, amount_out)
+    }
+
+    fn includes_transfer_fee(&self) -> bool {
+        self.as_quote().includes_transfer_fee()
+    }
+
+    fn spot_price(&self, base: &Pubkey) -> (f64, Pubkey) {
+        self.as_quote().spot_price(base)
+    }
+
+    fn mints(&self) -> (Pubkey, Pubkey) {
+        self.as_quote().mints()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pools_before_their_activation_point_are_not_activated() {
+        let clock = Clock {
+            slot: 1_000,
+            unix_timestamp: 1_700_000_000,
+            ..Default::default()
+        };
+
+        // slot activation
+        assert!(before_activation(&clock, 0, 1_001));
+        assert!(!before_activation(&clock, 0, 1_000));
+        assert!(!before_activation(&clock, 0, 0));
+        // timestamp activation, compared with the unix timestamp rather than the slot
+        assert!(before_activation(&clock, 1, 1_700_000_001));
+        assert!(!before_activation(&clock, 1, 1_700_000_000));
+        assert!(!before_activation(&clock, 1, 1_001));
+        // unknown activation types are left to the program
+        assert!(!before_activation(&clock, 2, u64::MAX));
+    }
+
+    #[test]
+    fn vertigo_quote_in_and_quote_out_agree() {
+        let clock = Clock {
+            slot: 1_000,
+            ..Default::default()
+        };
+        let pool = VertigoData {
+            pool_address: Pubkey::new_unique(),
+            pool_state: vertigo::Pool {
+                enabled: true,
+                owner: Pubkey::new_unique(),
+                mint_a: Pubkey::new_unique(),
+                mint_b: Pubkey::new_unique(),
+                token_a_reserves: 1_000_000_000_000,
+                token_b_reserves: 50_000_000_000,
+                shift: 10_000_000_000,
+                royalties: 0,
+                vertigo_fees: 0,
+                bump: 255,
+                // launch fee decayed, 1% from here on
+                fee_params: vertigo::FeeParams {
+                    normalization_period: 100,
+                    decay: 1.0,
+                    reference: 0,
+                    royalties_bps: 100,
+                    privileged_swapper: None,
+                },
+            },
+        };
+        let (mint_a, mint_b) = (pool.pool_state.mint_a, pool.pool_state.mint_b);
+
+        for (mint_in, mint_out) in [(mint_a, mint_b), (mint_b, mint_a)] {
+            for amount_in in [1_000_000, 123_456_789, 5_000_000_000] {
+                let amount_out = pool.quote_in(&clock, &mint_in, amount_in).unwrap();
+                let back = pool.quote_out(&clock, &mint_out, amount_out).unwrap();
+                // rounding only, well within a bps
+                assert!(
+                    back.abs_diff(amount_in) * 10_000 <= amount_in,
+                    "{} in quotes {} out, which needs {} in",
+                    amount_in,
+                    amount_out,
+                    back
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fee_override_changes_the_solfi_quote() {
+        let reserves = solfi::PoolReserves {
+            vault_a_amount: 1_000_000_000,
+            vault_b_amount: 1_000_000_000,
+            vault_a: Pubkey::new_unique(),
+            vault_b: Pubkey::new_unique(),
+        };
+        let quote = || reserves.swap_quote(1_000_000, true, SolfiData::fee_bps());
+
+        // 30 bps by default, 25 bps with `fees.solfi = 25`
+        assert_eq!(quote(), 996_006);
+        global::update_config(|config| {
+            config.fees.insert("solfi".to_string(), 25);
+        });
+        assert_eq!(quote(), 996_505);
+        global::update_config(|config| {
+            config.fees.remove("solfi");
+        });
+        assert_eq!(quote(), 996_006);
+    }
+}
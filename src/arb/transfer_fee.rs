@@ -0,0 +1,137 @@
+use crate::{
+    dex::whirlpool::{try_apply_transfer_fee, types::token::TransferFee},
+    streaming::{AccountDataType, global_data},
+    token_2022_program,
+};
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::{Result, anyhow};
+
+// Token-2022 mints pad the base mint to the token account size, then store the account type
+// and the extensions as (u16 type, u16 length, value) entries
+const ACCOUNT_TYPE_OFFSET: usize = 165;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const EXTENSION_TRANSFER_FEE_CONFIG: u16 = 1;
+// two authorities and the withheld amount come before the older and newer fees
+const OLDER_TRANSFER_FEE_OFFSET: usize = 32 + 32 + 8;
+// epoch, maximum fee, basis points
+const TRANSFER_FEE_LEN: usize = 8 + 8 + 2;
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+/// (epoch it applies from, fee)
+fn read_epoch_fee(config: &[u8], offset: usize) -> Option<(u64, TransferFee)> {
+    let epoch = read_u64(config, offset)?;
+    let max_fee = read_u64(config, offset + 8)?;
+    let fee_bps = read_u16(config, offset + 16)?;
+    Some((epoch, TransferFee::new_with_max(fee_bps, max_fee)))
+}
+
+/// Value of a Token-2022 mint extension, `None` for SPL Token mints or when it is absent.
+pub fn read_extension(data: &[u8], extension_type: u16) -> Option<&[u8]> {
+    if data.get(ACCOUNT_TYPE_OFFSET) != Some(&ACCOUNT_TYPE_MINT) {
+        return None;
+    }
+
+    let mut offset = ACCOUNT_TYPE_OFFSET + 1;
+    while let (Some(extension), Some(len)) = (read_u16(data, offset), read_u16(data, offset + 2)) {
+        let value = data.get(offset + 4..offset + 4 + len as usize)?;
+        if extension == extension_type {
+            return Some(value);
+        }
+        offset += 4 + len as usize;
+    }
+
+    None
+}
+
+/// Transfer fee a Token-2022 mint charges in `epoch`, `None` without the extension.
+pub fn read_transfer_fee(data: &[u8], epoch: u64) -> Option<TransferFee> {
+    let config = read_extension(data, EXTENSION_TRANSFER_FEE_CONFIG)?;
+    let (_, older) = read_epoch_fee(config, OLDER_TRANSFER_FEE_OFFSET)?;
+    let (newer_epoch, newer) =
+        read_epoch_fee(config, OLDER_TRANSFER_FEE_OFFSET + TRANSFER_FEE_LEN)?;
+
+    Some(if epoch >= newer_epoch { newer } else { older })
+}
+
+/// Transfer fee `mint` charges in `epoch`, zero for SPL Token mints and mints not cached yet.
+pub fn mint_transfer_fee(mint: &Pubkey, epoch: u64) -> TransferFee {
+    let account = match global_data::get_account(mint) {
+        Some(AccountDataType::Account(account)) => Some(account),
+        _ => global_data::get_mint_account(mint),
+    };
+
+    account
+        .filter(|account| account.owner == token_2022_program())
+        .and_then(|account| read_transfer_fee(&account.data, epoch))
+        .unwrap_or_default()
+}
+
+/// What arrives on the other side when `amount` of a mint charging `fee` is transferred.
+pub fn after_transfer_fee(amount: u64, fee: TransferFee) -> Result<u64> {
+    try_apply_transfer_fee(amount, fee).map_err(|e| anyhow!(e))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Token-2022 mint data with a transfer fee of `older` before `newer_epoch` and `newer` from it.
+    pub fn mint_data(older: TransferFee, newer_epoch: u64, newer: TransferFee) -> Vec<u8> {
+        let mut data = vec![0u8; ACCOUNT_TYPE_OFFSET];
+        data[45] = 1; // is_initialized
+        data.push(ACCOUNT_TYPE_MINT);
+
+        let mut config = vec![0u8; OLDER_TRANSFER_FEE_OFFSET];
+        for (epoch, fee) in [(0, older), (newer_epoch, newer)] {
+            config.extend_from_slice(&epoch.to_le_bytes());
+            config.extend_from_slice(&fee.max_fee.to_le_bytes());
+            config.extend_from_slice(&fee.fee_bps.to_le_bytes());
+        }
+
+        // an unrelated extension first, the config has to be found by walking the entries
+        for (extension, value) in [
+            (3u16, vec![0u8; 32]),
+            (EXTENSION_TRANSFER_FEE_CONFIG, config),
+        ] {
+            data.extend_from_slice(&extension.to_le_bytes());
+            data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+            data.extend_from_slice(&value);
+        }
+
+        data
+    }
+
+    #[test]
+    fn reads_the_fee_of_the_current_epoch() {
+        let older = TransferFee::new_with_max(100, 5_000);
+        let newer = TransferFee::new_with_max(250, u64::MAX);
+        let data = mint_data(older, 600, newer);
+
+        assert_eq!(read_transfer_fee(&data, 599), Some(older));
+        assert_eq!(read_transfer_fee(&data, 600), Some(newer));
+        assert_eq!(read_transfer_fee(&data[..ACCOUNT_TYPE_OFFSET], 600), None);
+        assert_eq!(read_transfer_fee(&[0u8; 82], 600), None);
+    }
+
+    #[test]
+    fn fee_is_capped_at_the_maximum() {
+        let fee = TransferFee::new_with_max(100, 5_000);
+        assert_eq!(after_transfer_fee(100_000, fee).unwrap(), 99_000);
+        assert_eq!(after_transfer_fee(1_000_000, fee).unwrap(), 995_000);
+        assert_eq!(
+            after_transfer_fee(1_000, TransferFee::default()).unwrap(),
+            1_000
+        );
+    }
+}
@@ -12,6 +12,8 @@ pub struct SwapRoutes {
     pub profit: i64,
     pub amount_in: u64,
     pub threshold: u64,
+    // least the route may return, the aggregator reverts below it
+    pub min_amount_out: u64,
     pub mint: Pubkey,
 }
 
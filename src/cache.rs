@@ -101,6 +101,20 @@ where
         self.inner.remove(key);
     }
 
+    /// Keys of temporary entries with at most `within` left, expired ones not evicted yet included.
+    pub fn expiring_within(&self, within: Duration) -> Vec<K> {
+        self.inner
+            .iter()
+            .filter(|entry| {
+                entry
+                    .value()
+                    .ttl_remaining()
+                    .is_some_and(|remaining| remaining <= within)
+            })
+            .map(|entry| entry.key().clone())
+            .collect()
+    }
+
     pub fn purge_expired(&self) {
         self.inner.retain(|_, entry| !entry.is_expired());
     }
@@ -0,0 +1,234 @@
+use crate::{
+    arb::{self, PoolType, SwapQuote},
+    global, usdc_mint, util, wsol_mint,
+};
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::{Result, anyhow};
+use std::str::FromStr;
+
+const QUOTE_USAGE: &str =
+    "usage: solarb quote --pools <pk1>,<pk2>,... --amount <raw amount> [--base wsol|usdc|<mint>] [--slippage] [--slot <slot>] [--timestamp <unix secs>]";
+
+struct QuoteArgs {
+    pools: Vec<Pubkey>,
+    amount: u64,
+    base_mint: Pubkey,
+    adjust_slippage: bool,
+    // clock overrides, to reproduce a quote as of a past slot
+    slot: Option<u64>,
+    timestamp: Option<i64>,
+}
+
+fn parse_mint(value: &str) -> Result<Pubkey> {
+    match value.to_lowercase().as_str() {
+        "wsol" | "sol" => Ok(wsol_mint()),
+        "usdc" => Ok(usdc_mint()),
+        _ => Pubkey::from_str(value).map_err(|_| anyhow!("Invalid base mint {}", value)),
+    }
+}
+
+fn parse_quote_args(args: &[String]) -> Result<QuoteArgs> {
+    let mut pools = Vec::new();
+    let mut amount = None;
+    let mut base_mint = wsol_mint();
+    let mut adjust_slippage = false;
+    let mut slot = None;
+    let mut timestamp = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--pools" => {
+                let value = iter.next().ok_or_else(|| anyhow!("--pools needs a value"))?;
+                for pool in value.split(',').filter(|p| !p.is_empty()) {
+                    pools.push(
+                        Pubkey::from_str(pool.trim())
+                            .map_err(|_| anyhow!("Invalid pool address {}", pool))?,
+                    );
+                }
+            }
+            "--amount" => {
+                let value = iter.next().ok_or_else(|| anyhow!("--amount needs a value"))?;
+                amount = Some(value.parse::<u64>()?);
+            }
+            "--base" => {
+                let value = iter.next().ok_or_else(|| anyhow!("--base needs a value"))?;
+                base_mint = parse_mint(value)?;
+            }
+            "--slippage" => adjust_slippage = true,
+            "--slot" => {
+                let value = iter.next().ok_or_else(|| anyhow!("--slot needs a value"))?;
+                slot = Some(value.parse::<u64>()?);
+            }
+            "--timestamp" => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("--timestamp needs a value"))?;
+                timestamp = Some(value.parse::<i64>()?);
+            }
+            _ => return Err(anyhow!("Unknown argument {}\n{}", arg, QUOTE_USAGE)),
+        }
+    }
+
+    if pools.is_empty() {
+        return Err(anyhow!("Missing --pools\n{}", QUOTE_USAGE));
+    }
+
+    Ok(QuoteArgs {
+        pools,
+        amount: amount.ok_or_else(|| anyhow!("Missing --amount\n{}", QUOTE_USAGE))?,
+        base_mint,
+        adjust_slippage,
+        slot,
+        timestamp,
+    })
+}
+
+/// Quote a single route once and print the per-hop outputs, without starting the bot.
+pub async fn quote(args: &[String]) -> Result<()> {
+    let args = parse_quote_args(args)?;
+    let rpc_client = global::get_rpc_client();
+
+    let mut pools = Vec::with_capacity(args.pools.len());
+    for pool_address in &args.pools {
+        pools.push(arb::load_pool(rpc_client.clone(), *pool_address).await?);
+    }
+    let mut clock = util::get_clock(&rpc_client).await?;
+    if let Some(slot) = args.slot {
+        clock.slot = slot;
+    }
+    if let Some(timestamp) = args.timestamp {
+        clock.unix_timestamp = timestamp;
+    }
+
+    let hops = arb::simulate_route(
+        &clock,
+        &pools,
+        args.amount,
+        &args.base_mint,
+        args.adjust_slippage,
+    )?;
+
+    println!("Slot {} - amount in {} {}", clock.slot, args.amount, args.base_mint);
+    println!(
+        "{:<4} {:<44} {:<44} {:<44} {:>20} {:>20}",
+        "hop", "pool", "mint in", "mint out", "amount in", "amount out"
+    );
+    for (i, hop) in hops.iter().enumerate() {
+        println!(
+            "{:<4} {:<44} {:<44} {:<44} {:>20} {:>20}",
+            i + 1,
+            hop.pool.to_string(),
+            hop.mint_in.to_string(),
+            hop.mint_out.to_string(),
+            hop.amount_in,
+            hop.amount_out
+        );
+    }
+
+    if hops.len() < pools.len() {
+        println!("Route stopped at hop {}, pool could not quote", hops.len() + 1);
+        return Ok(());
+    }
+
+    match hops.last() {
+        Some(last) if last.mint_out == args.base_mint => {
+            let profit = last.amount_out as i128 - args.amount as i128;
+            println!("Profit {}", profit);
+        }
+        Some(last) => println!("Route ends in {}, not the base mint", last.mint_out),
+        None => {}
+    }
+
+    Ok(())
+}
+
+const CPMM_SWAP_DISABLED_BIT: u8 = 1 << 2;
+const CLMM_SWAP_DISABLED_BIT: u8 = 1 << 4;
+
+fn check_tradable(pool: &PoolType) -> Result<()> {
+    let tradable = match pool {
+        PoolType::Meteora(_, data) => data.lb_pair.status == 0,
+        PoolType::MeteoraDammv2(_, data) => data.pool_state.pool_status == 0,
+        PoolType::RaydiumAmm(_, data) => data.pool_state.can_swap(),
+        PoolType::RaydiumCpmm(_, data) => data.pool_state.status & CPMM_SWAP_DISABLED_BIT == 0,
+        PoolType::RaydiumClmm(_, data) => data.pool_state.status & CLMM_SWAP_DISABLED_BIT == 0,
+        PoolType::Vertigo(_, data) => data.pool_state.enabled,
+        PoolType::Pump(_, _) | PoolType::Whirlpool(_, _) | PoolType::Solfi(_, _) => true,
+    };
+
+    if tradable {
+        Ok(())
+    } else {
+        Err(anyhow!("pool is not tradable"))
+    }
+}
+
+/// Invariants any freshly deserialized pool should hold.
+fn check_pool(pool: &PoolType) -> Result<()> {
+    let (mint_a, mint_b) = pool.get_mints();
+    if mint_a == Pubkey::default() || mint_b == Pubkey::default() {
+        return Err(anyhow!("zero mint ({}, {})", mint_a, mint_b));
+    }
+    if mint_a == mint_b {
+        return Err(anyhow!("identical mints {}", mint_a));
+    }
+
+    let (reserve_a, reserve_b) = pool.get_reserves();
+    if !(reserve_a.is_finite() && reserve_b.is_finite() && reserve_a > 0.0 && reserve_b > 0.0) {
+        return Err(anyhow!(
+            "implausible reserves ({}, {})",
+            reserve_a,
+            reserve_b
+        ));
+    }
+
+    if let PoolType::MeteoraDammv2(_, data) = pool {
+        let state = &data.pool_state;
+        if !(state.sqrt_min_price..=state.sqrt_max_price).contains(&state.sqrt_price) {
+            return Err(anyhow!("sqrt price {} outside its range", state.sqrt_price));
+        }
+    }
+
+    if pool.get_price(&mint_a).is_none() {
+        return Err(anyhow!("implausible price {}", pool.spot_price(&mint_a).0));
+    }
+
+    check_tradable(pool)
+}
+
+/// Load one known pool per DEX from `[selftest.pools]` and check it parses into something sane.
+pub async fn selftest() -> Result<()> {
+    let pools = global::get_config().selftest.pools.clone();
+    if pools.is_empty() {
+        return Err(anyhow!("No pools configured under [selftest.pools]"));
+    }
+
+    let rpc_client = global::get_rpc_client();
+    let mut failed = 0;
+
+    for (dex, pool_address) in &pools {
+        let result = match Pubkey::from_str(pool_address) {
+            Ok(pool_address) => match arb::load_pool(rpc_client.clone(), pool_address).await {
+                Ok(pool) => check_pool(&pool).map(|_| pool.to_pool_type()),
+                Err(e) => Err(e),
+            },
+            Err(_) => Err(anyhow!("invalid pool address")),
+        };
+
+        match result {
+            Ok(pool_type) => println!("PASS {:<16} {} ({:?})", dex, pool_address, pool_type),
+            Err(e) => {
+                failed += 1;
+                println!("FAIL {:<16} {}: {}", dex, pool_address, e);
+            }
+        }
+    }
+
+    println!("{}/{} passed", pools.len() - failed, pools.len());
+    if failed > 0 {
+        return Err(anyhow!("{} selftest pool(s) failed", failed));
+    }
+
+    Ok(())
+}
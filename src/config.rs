@@ -1,6 +1,10 @@
-use anyhow::Result;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::{Result, anyhow};
 use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
 use std::fs;
+use std::str::FromStr;
 use toml;
 
 #[derive(Debug, Deserialize, Clone)]
@@ -9,12 +13,72 @@ pub struct Config {
     pub grpc: Grpc,
     pub bot: BotConfig,
     pub watcher: Watcher,
+    #[serde(default)]
+    pub selftest: SelfTest,
+    #[serde(default)]
+    pub price: PriceConfig,
+    #[serde(default)]
+    pub wallet: WalletConfig,
+    #[serde(default)]
+    pub nonce: NonceConfig,
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+    #[serde(default)]
+    pub whirlpool: WhirlpoolConfig,
+    #[serde(default)]
+    pub clmm: ClmmConfig,
+    #[serde(default)]
+    pub log: LogConfig,
+    #[serde(default)]
+    pub mints: MintsConfig,
+    // dex name -> fee in bps quoted instead of the one derived from the pool, emergencies only
+    #[serde(default)]
+    pub fees: BTreeMap<String, u64>,
+}
+
+/// `bot.route_direction`: what the first hop of a cycle out of `bot.mint` buys, judged by
+/// the configured base mints rather than the pool's token order.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteDirection {
+    #[default]
+    Both,
+    /// The first hop buys a token outside the base mints
+    BuyFirst,
+    /// The first hop sells into another base mint, the other token is bought with it later
+    SellFirst,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Rpc {
     pub url: String,
     pub websocket_url: String,
+    #[serde(default = "default_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+    #[serde(default = "default_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+    // getTransaction is tried on these in order until one has the transaction, `url` when empty
+    #[serde(default)]
+    pub tx_fetch_urls: Vec<String>,
+}
+
+impl Rpc {
+    /// Endpoints the watcher fetches transactions from, in the order they are tried.
+    pub fn tx_fetch_endpoints(&self) -> Vec<String> {
+        if self.tx_fetch_urls.is_empty() {
+            vec![self.url.clone()]
+        } else {
+            self.tx_fetch_urls.clone()
+        }
+    }
+}
+
+fn default_request_timeout_ms() -> u64 {
+    30_000
+}
+
+fn default_connect_timeout_ms() -> u64 {
+    5_000
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -22,6 +86,9 @@ pub struct Grpc {
     pub url: String,
     pub token: Option<String>,
     pub enabled: bool,
+    // subscribed accounts and programs are written here as json with every metrics tick
+    #[serde(default)]
+    pub subscriptions_path: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -35,18 +102,847 @@ pub struct BotConfig {
     pub routes_batch_size: u32,
     pub enabled_slippage: bool,
     pub slippage_bps: u64,
+    #[serde(default = "default_max_inflight_arbs")]
+    pub max_inflight_arbs: usize,
+    // queued profitable routes quoted longer ago than this are dropped unsent, 0 = never
+    #[serde(default = "default_opportunity_ttl_ms")]
+    pub opportunity_ttl_ms: u64,
+    #[serde(default)]
+    pub dedup_first_hop: bool,
+    #[serde(default)]
+    pub auto_wrap_sol: bool,
+    // native SOL kept for fees and rent, the rest is wrapped as capital when auto_wrap_sol is on
+    #[serde(default = "default_sol_fee_reserve_lamports")]
+    pub sol_fee_reserve_lamports: u64,
+    #[serde(default = "default_balance_refresh_secs")]
+    pub balance_refresh_secs: u64,
+    #[serde(default)]
+    pub dry_run: bool,
+    // simulated fills and per-session opportunity counts, appended as JSON lines
+    #[serde(default = "default_ledger_path")]
+    pub ledger_path: String,
+    #[serde(default)]
+    pub route_direction: RouteDirection,
+    #[serde(default = "default_route_cooldown_ms")]
+    pub route_cooldown_ms: u64,
+    #[serde(default = "default_max_tx_accounts")]
+    pub max_tx_accounts: usize,
+    #[serde(default)]
+    pub track_confirmations: bool,
+    #[serde(default = "default_solfi_min_reserve")]
+    pub solfi_min_reserve: u64,
+    // meteora pools whose activation point is still ahead of the clock aren't quoted
+    #[serde(default)]
+    pub skip_pre_activation: bool,
+    // damm v2 swaps pass the wallet's own token account as referral, quotes use the referral fee split
+    #[serde(default)]
+    pub damm_has_referral: bool,
+    // empty ATAs the bot created for intermediate mints are closed on shutdown to reclaim rent
+    #[serde(default)]
+    pub cleanup_temp_atas: bool,
+    // and this often for mints no indexed pool trades anymore, 0 = on shutdown only
+    #[serde(default = "default_temp_ata_cleanup_secs")]
+    pub temp_ata_cleanup_secs: u64,
+    #[serde(default = "default_revert_blacklist_threshold")]
+    pub revert_blacklist_threshold: u32,
+    #[serde(default = "default_revert_blacklist_window_secs")]
+    pub revert_blacklist_window_secs: u64,
+    #[serde(default = "default_revert_blacklist_cooldown_secs")]
+    pub revert_blacklist_cooldown_secs: u64,
+    // failed or unprofitable routes in a row through a mint pair before it is paused, 0 = off
+    #[serde(default = "default_pair_failure_threshold")]
+    pub pair_failure_threshold: u32,
+    #[serde(default = "default_pair_cooldown_secs")]
+    pub pair_cooldown_secs: u64,
+    #[serde(default = "default_max_optimizer_iters")]
+    pub max_optimizer_iters: usize,
+    // the clock counts as stale, and the bot unhealthy, once it is about this many slots old, 0 = off
+    #[serde(default = "default_max_clock_lag_slots")]
+    pub max_clock_lag_slots: u64,
+    // minimum time between the starts of two full route passes
+    #[serde(default = "default_finding_interval_ms")]
+    pub finding_interval_ms: u64,
+    // threads quoting candidate routes, 0 = one per core
+    #[serde(default)]
+    pub route_eval_threads: usize,
+    // tasks loading discovered pools, each fetches the mints of its batch in one call
+    #[serde(default = "default_pool_loader_concurrency")]
+    pub pool_loader_concurrency: usize,
+    // routes cycling through one pair are skipped when its pools' prices sit within twice this, 0 = off
+    #[serde(default)]
+    pub typical_fee_bps: u64,
+    // DEXes routes don't go through, named as in `watcher.programs`, their pools stay watched
+    #[serde(default)]
+    pub disabled_dexes: Vec<String>,
+    // ceiling on the compute unit price, 0 = none
+    #[serde(default)]
+    pub max_cu_price_micro_lamports: u64,
+    // send the compute unit limit instruction, off for providers rejecting it
+    #[serde(default = "default_include_cu_limit")]
+    pub include_cu_limit: bool,
+    #[serde(default)]
+    pub static_alts: Vec<String>,
+    // per-pool lookup tables are refetched this often to pick up extensions, 0 = cached forever
+    #[serde(default)]
+    pub alt_ttl_secs: u64,
+    #[serde(default)]
+    pub base_mints: Vec<String>,
+    // pools indexed and subscribed at startup, whether or not discovery ever sees them
+    #[serde(default)]
+    pub pinned_pools: Vec<PinnedPool>,
+    // mint realized profit is reported in, converted from each route's base mint, defaults to `mint`
+    #[serde(default)]
+    pub accounting_mint: Option<String>,
+    #[serde(default = "default_auto_create_base_ata")]
+    pub auto_create_base_ata: bool,
+    // simulate routes before sending and drop those whose output drifts from the quote
+    #[serde(default)]
+    pub simulate_before_send: bool,
+    // only routes at least this profitable are simulated
+    #[serde(default)]
+    pub simulate_min_profit: u64,
+    // how far below the quoted output the simulated one may land
+    #[serde(default = "default_simulate_tolerance_bps")]
+    pub simulate_tolerance_bps: u64,
+}
+
+fn default_max_inflight_arbs() -> usize {
+    4
+}
+
+fn default_opportunity_ttl_ms() -> u64 {
+    1_000
+}
+
+fn default_max_clock_lag_slots() -> u64 {
+    150
+}
+
+fn default_balance_refresh_secs() -> u64 {
+    30
+}
+
+fn default_ledger_path() -> String {
+    "ledger.jsonl".to_string()
+}
+
+fn default_route_cooldown_ms() -> u64 {
+    2_000
+}
+
+fn default_max_tx_accounts() -> usize {
+    64
+}
+
+fn default_solfi_min_reserve() -> u64 {
+    1_000
+}
+
+fn default_revert_blacklist_threshold() -> u32 {
+    3
+}
+
+fn default_revert_blacklist_window_secs() -> u64 {
+    300
+}
+
+fn default_revert_blacklist_cooldown_secs() -> u64 {
+    600
+}
+
+fn default_sol_fee_reserve_lamports() -> u64 {
+    50_000_000
+}
+
+fn default_temp_ata_cleanup_secs() -> u64 {
+    3600
+}
+
+fn default_pair_failure_threshold() -> u32 {
+    5
+}
+
+fn default_pair_cooldown_secs() -> u64 {
+    300
+}
+
+fn default_max_optimizer_iters() -> usize {
+    128
+}
+
+fn default_finding_interval_ms() -> u64 {
+    100
+}
+
+fn default_pool_loader_concurrency() -> usize {
+    10
+}
+
+fn default_auto_create_base_ata() -> bool {
+    true
+}
+
+fn default_include_cu_limit() -> bool {
+    true
+}
+
+fn default_simulate_tolerance_bps() -> u64 {
+    50
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PinnedPool {
+    pub address: String,
+    // DEX name as in `watcher.programs`, e.g. "whirlpool" or "raydium_cpmm"
+    pub dex: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Watcher {
     pub only_succeed: bool,
+    // failed transactions are watched to copy pools from other searchers' reverted (MEV) arbs
     pub only_failed: bool,
     pub max_pools: u32,
     pub max_routes: u32,
+    #[serde(default)]
+    pub programs: Option<Vec<String>>,
+    #[serde(default = "default_batch_workers")]
+    pub batch_workers: usize,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    // bound on pools waiting to be loaded, 0 = unbounded
+    #[serde(default)]
+    pub max_pool_queue: usize,
+    // lookup tables fetched at once, concurrent requests for the same table share one fetch
+    #[serde(default = "default_alt_fetch_concurrency")]
+    pub alt_fetch_concurrency: usize,
+    #[serde(default = "default_ping_interval_secs")]
+    pub ping_interval_secs: u64,
+    // the connection is dropped after this long without any message
+    #[serde(default = "default_message_timeout_secs")]
+    pub message_timeout_secs: u64,
+    // fee payers, e.g. other bots, whose transactions aren't copied
+    #[serde(default)]
+    pub ignore_signers: Vec<String>,
+}
+
+impl Watcher {
+    pub fn validate(&self) -> Result<()> {
+        if self.only_succeed && self.only_failed {
+            return Err(anyhow!(
+                "watcher.only_succeed and watcher.only_failed are both true, no transaction would be watched"
+            ));
+        }
+        if self.ping_interval_secs == 0 || self.ping_interval_secs >= self.message_timeout_secs {
+            return Err(anyhow!(
+                "watcher.ping_interval_secs ({}) must be non-zero and below watcher.message_timeout_secs ({})",
+                self.ping_interval_secs,
+                self.message_timeout_secs
+            ));
+        }
+        for signer in &self.ignore_signers {
+            Pubkey::from_str(signer)
+                .map_err(|_| anyhow!("watcher.ignore_signers has an invalid pubkey {}", signer))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn default_batch_workers() -> usize {
+    10
+}
+
+fn default_batch_size() -> usize {
+    5
+}
+
+fn default_alt_fetch_concurrency() -> usize {
+    8
+}
+
+fn default_ping_interval_secs() -> u64 {
+    25
+}
+
+fn default_message_timeout_secs() -> u64 {
+    90
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct SelfTest {
+    // label -> pool address, one known pool per DEX
+    #[serde(default)]
+    pub pools: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct PriceConfig {
+    // unix socket streaming price updates as json lines, disabled when unset
+    #[serde(default)]
+    pub socket_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct WalletConfig {
+    // keypair file, ./wallet.json when unset
+    #[serde(default)]
+    pub keypair_path: Option<String>,
+    // env var holding a base58 secret key, wins over keypair_path when set
+    #[serde(default)]
+    pub keypair_env: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct NonceConfig {
+    // durable nonce account used instead of a recent blockhash when set
+    #[serde(default)]
+    pub account: Option<String>,
+    // nonce authority, has to be the wallet since it is the only signer, defaults to it
+    #[serde(default)]
+    pub authority: Option<String>,
+}
+
+impl NonceConfig {
+    /// The wallet isn't loaded yet, that the authority is the wallet is checked at startup.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(account) = &self.account {
+            Pubkey::from_str(account)
+                .map_err(|_| anyhow!("nonce.account is not a pubkey {}", account))?;
+        }
+        if let Some(authority) = &self.authority {
+            if self.account.is_none() {
+                return Err(anyhow!("nonce.authority is set without nonce.account"));
+            }
+            Pubkey::from_str(authority)
+                .map_err(|_| anyhow!("nonce.authority is not a pubkey {}", authority))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AlertsConfig {
+    // profitable routes are posted here as json, disabled when unset
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    // the same route is posted at most once per this window
+    #[serde(default = "default_alert_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        AlertsConfig {
+            webhook_url: None,
+            cooldown_secs: default_alert_cooldown_secs(),
+        }
+    }
+}
+
+fn default_alert_cooldown_secs() -> u64 {
+    60
+}
+
+// whirlpool swaps take the current tick array and the next two in the swap direction
+pub const MAX_WHIRLPOOL_TICK_ARRAYS: usize = 3;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct WhirlpoolConfig {
+    // tick arrays loaded per swap direction, counting the current one, at most 3
+    #[serde(default = "default_whirlpool_tick_arrays")]
+    pub tick_arrays: usize,
+}
+
+impl Default for WhirlpoolConfig {
+    fn default() -> Self {
+        WhirlpoolConfig {
+            tick_arrays: default_whirlpool_tick_arrays(),
+        }
+    }
+}
+
+impl WhirlpoolConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=MAX_WHIRLPOOL_TICK_ARRAYS).contains(&self.tick_arrays) {
+            return Err(anyhow!(
+                "whirlpool.tick_arrays ({}) must be between 1 and {}",
+                self.tick_arrays,
+                MAX_WHIRLPOOL_TICK_ARRAYS
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn default_whirlpool_tick_arrays() -> usize {
+    MAX_WHIRLPOOL_TICK_ARRAYS
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ClmmConfig {
+    // initialized tick arrays loaded per swap direction, counting the current one
+    #[serde(default = "default_clmm_tick_arrays")]
+    pub tick_arrays: usize,
+    // debug: warn when a quote ends on a tick that doesn't hold its final sqrt price
+    #[serde(default)]
+    pub check_swap_state: bool,
+}
+
+impl Default for ClmmConfig {
+    fn default() -> Self {
+        ClmmConfig {
+            tick_arrays: default_clmm_tick_arrays(),
+            check_swap_state: false,
+        }
+    }
+}
+
+impl ClmmConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.tick_arrays == 0 {
+            return Err(anyhow!("clmm.tick_arrays must be at least 1"));
+        }
+
+        Ok(())
+    }
+}
+
+fn default_clmm_tick_arrays() -> usize {
+    6
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LogConfig {
+    // most verbose level printed: error, warn, info, debug or trace
+    #[serde(default = "default_log_level")]
+    pub level: String,
+    // debug events in the watcher and gRPC loops are printed 1 in this many per call site
+    #[serde(default = "default_log_sample_rate")]
+    pub sample_rate: u64,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            level: default_log_level(),
+            sample_rate: default_log_sample_rate(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct MintsConfig {
+    // mint -> decimals, prices are decimal-adjusted with these before any mint account is loaded
+    #[serde(default = "default_mint_decimals")]
+    pub decimals: BTreeMap<String, u8>,
+}
+
+impl Default for MintsConfig {
+    fn default() -> Self {
+        MintsConfig {
+            decimals: default_mint_decimals(),
+        }
+    }
+}
+
+impl MintsConfig {
+    pub fn validate(&self) -> Result<()> {
+        for mint in self.decimals.keys() {
+            Pubkey::from_str(mint)
+                .map_err(|_| anyhow!("mints.decimals has an invalid mint {}", mint))?;
+        }
+
+        Ok(())
+    }
+}
+
+// wSOL, USDC and USDT
+fn default_mint_decimals() -> BTreeMap<String, u8> {
+    [
+        ("So11111111111111111111111111111111111111112", 9),
+        ("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", 6),
+        ("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB", 6),
+    ]
+    .into_iter()
+    .map(|(mint, decimals)| (mint.to_string(), decimals))
+    .collect()
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+fn default_log_sample_rate() -> u64 {
+    1
 }
 
 pub fn read_config(path: &str) -> Result<Config> {
     let content = fs::read_to_string(path)?;
-    let config: Config = toml::from_str(&content)?;
+    parse_config(&content)
+}
+
+fn parse_config(content: &str) -> Result<Config> {
+    let config: Config = toml::from_str(content)?;
+    config.watcher.validate()?;
+    config.whirlpool.validate()?;
+    config.clmm.validate()?;
+    config.mints.validate()?;
+    config.nonce.validate()?;
+    validate_fees(&config.fees)?;
+    validate_disabled_dexes(&config.bot.disabled_dexes)?;
+    validate_route_direction(&config.bot)?;
     Ok(config)
 }
+
+fn validate_route_direction(bot: &BotConfig) -> Result<()> {
+    let other_base_mint = bot.base_mints.iter().any(|mint| *mint != bot.mint);
+    if bot.route_direction == RouteDirection::SellFirst && !other_base_mint {
+        return Err(anyhow!(
+            "bot.route_direction = \"sell_first\" needs another base mint in bot.base_mints for the first hop to sell into"
+        ));
+    }
+
+    Ok(())
+}
+
+// every DEX routes can go through, named as in `watcher.programs`
+const DEXES: [&str; 9] = [
+    "pumpfun",
+    "meteora_dlmm",
+    "meteora_damm",
+    "raydium_amm",
+    "raydium_cpmm",
+    "raydium_clmm",
+    "whirlpool",
+    "vertigo",
+    "solfi",
+];
+
+fn validate_disabled_dexes(dexes: &[String]) -> Result<()> {
+    for dex in dexes {
+        if !DEXES.contains(&dex.as_str()) {
+            return Err(anyhow!(
+                "bot.disabled_dexes has an unknown DEX {}, expected one of {}",
+                dex,
+                DEXES.join(", ")
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// DEXes whose quote takes a `fees.<dex>` override, named as in `watcher.programs`. DLMM fees are
+// computed inside the dlmm quote crate and can't be replaced.
+const FEE_OVERRIDE_DEXES: [&str; 8] = [
+    "pumpfun",
+    "meteora_damm",
+    "raydium_amm",
+    "raydium_cpmm",
+    "raydium_clmm",
+    "whirlpool",
+    "vertigo",
+    "solfi",
+];
+
+fn validate_fees(fees: &BTreeMap<String, u64>) -> Result<()> {
+    for (dex, fee_bps) in fees {
+        if !FEE_OVERRIDE_DEXES.contains(&dex.as_str()) {
+            return Err(anyhow!(
+                "fees.{} can't be overridden, expected one of {}",
+                dex,
+                FEE_OVERRIDE_DEXES.join(", ")
+            ));
+        }
+        if *fee_bps >= 10_000 {
+            return Err(anyhow!(
+                "fees.{} ({}) must be below 10000 bps",
+                dex,
+                fee_bps
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// What a config reload did: settings taken from the new file as `name old -> new`, and the
+/// sections whose other changes only apply after a restart.
+#[derive(Debug, Default)]
+pub struct ReloadReport {
+    pub changed: Vec<String>,
+    pub ignored: Vec<&'static str>,
+}
+
+fn reload_setting<T: PartialEq + Clone + Debug>(
+    changed: &mut Vec<String>,
+    name: &str,
+    current: &mut T,
+    new: &T,
+) {
+    if current != new {
+        changed.push(format!("{} {:?} -> {:?}", name, current, new));
+        *current = new.clone();
+    }
+}
+
+impl Config {
+    /// This config with the settings that are read on every use taken from `new`: profit floor,
+    /// slippage, fee and simulation policy, size caps and logging. Endpoints, the wallet, the
+    /// watched programs and whatever sizes a task or cache at startup keep their current value.
+    pub fn with_runtime_settings(&self, new: &Config) -> (Config, ReloadReport) {
+        let mut next = self.clone();
+        let mut changed = Vec::new();
+        // `section.field` of `next` takes its value from `new`
+        macro_rules! reload {
+            ($section:ident . $field:ident) => {
+                reload_setting(
+                    &mut changed,
+                    concat!(stringify!($section), ".", stringify!($field)),
+                    &mut next.$section.$field,
+                    &new.$section.$field,
+                )
+            };
+        }
+
+        reload!(bot.minimum_profit);
+        reload!(bot.price_threshold);
+        reload!(bot.optimization_method);
+        reload!(bot.optimization_amount_percent);
+        reload!(bot.max_optimizer_iters);
+        reload!(bot.enabled_slippage);
+        reload!(bot.slippage_bps);
+        reload!(bot.max_cu_price_micro_lamports);
+        reload!(bot.include_cu_limit);
+        reload!(bot.typical_fee_bps);
+        reload!(bot.disabled_dexes);
+        reload!(bot.max_tx_accounts);
+        reload!(bot.opportunity_ttl_ms);
+        reload!(bot.dedup_first_hop);
+        reload!(bot.route_cooldown_ms);
+        reload!(bot.simulate_before_send);
+        reload!(bot.simulate_min_profit);
+        reload!(bot.simulate_tolerance_bps);
+        reload!(bot.revert_blacklist_threshold);
+        reload!(bot.revert_blacklist_window_secs);
+        reload!(bot.revert_blacklist_cooldown_secs);
+        reload!(bot.pair_failure_threshold);
+        reload!(bot.pair_cooldown_secs);
+        reload!(bot.max_clock_lag_slots);
+        reload!(bot.skip_pre_activation);
+        reload!(bot.damm_has_referral);
+        reload!(bot.sol_fee_reserve_lamports);
+        reload!(log.level);
+        reload!(log.sample_rate);
+        reload_setting(&mut changed, "fees", &mut next.fees, &new.fees);
+
+        // whatever still differs needs a restart
+        let differs = |a: &dyn Debug, b: &dyn Debug| format!("{:?}", a) != format!("{:?}", b);
+        let ignored = [
+            ("rpc", differs(&next.rpc, &new.rpc)),
+            ("grpc", differs(&next.grpc, &new.grpc)),
+            ("bot", differs(&next.bot, &new.bot)),
+            ("watcher", differs(&next.watcher, &new.watcher)),
+            ("price", differs(&next.price, &new.price)),
+            ("wallet", differs(&next.wallet, &new.wallet)),
+            ("nonce", differs(&next.nonce, &new.nonce)),
+            ("alerts", differs(&next.alerts, &new.alerts)),
+            ("whirlpool", differs(&next.whirlpool, &new.whirlpool)),
+            ("clmm", differs(&next.clmm, &new.clmm)),
+            ("mints", differs(&next.mints, &new.mints)),
+        ]
+        .into_iter()
+        .filter_map(|(section, differs)| differs.then_some(section))
+        .collect();
+
+        (next, ReloadReport { changed, ignored })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watcher(only_succeed: bool, only_failed: bool) -> Watcher {
+        toml::from_str(&format!(
+            "only_succeed = {}\nonly_failed = {}\nmax_pools = 100\nmax_routes = 100",
+            only_succeed, only_failed
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn watcher_rejects_both_only_flags() {
+        assert!(watcher(true, true).validate().is_err());
+    }
+
+    #[test]
+    fn watcher_accepts_a_single_only_flag() {
+        assert!(watcher(false, false).validate().is_ok());
+        assert!(watcher(true, false).validate().is_ok());
+        assert!(watcher(false, true).validate().is_ok());
+    }
+
+    #[test]
+    fn watcher_ping_must_be_below_message_timeout() {
+        let mut config = watcher(false, false);
+        assert_eq!(config.ping_interval_secs, 25);
+        assert_eq!(config.message_timeout_secs, 90);
+
+        config.ping_interval_secs = 90;
+        assert!(config.validate().is_err());
+        config.ping_interval_secs = 0;
+        assert!(config.validate().is_err());
+        config.ping_interval_secs = 10;
+        config.message_timeout_secs = 15;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn watcher_ignore_signers_must_be_pubkeys() {
+        let mut config = watcher(false, false);
+        config.ignore_signers = vec!["So11111111111111111111111111111111111111112".to_string()];
+        assert!(config.validate().is_ok());
+        config.ignore_signers.push("not_a_signer".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn nonce_account_and_authority_must_be_pubkeys() {
+        let nonce = |toml: &str| toml::from_str::<NonceConfig>(toml).unwrap().validate();
+        let key = "So11111111111111111111111111111111111111112";
+        assert!(nonce("").is_ok());
+        assert!(nonce(&format!("account = \"{}\"", key)).is_ok());
+        assert!(nonce(&format!("account = \"{}\"\nauthority = \"{}\"", key, key)).is_ok());
+        assert!(nonce("account = \"not_a_nonce\"").is_err());
+        assert!(nonce(&format!("account = \"{}\"\nauthority = \"x\"", key)).is_err());
+        assert!(nonce(&format!("authority = \"{}\"", key)).is_err());
+    }
+
+    fn config(minimum_profit: u64, rpc_url: &str) -> Config {
+        parse_config(&format!(
+            "[rpc]\nurl = \"{}\"\nwebsocket_url = \"\"\n\
+             [grpc]\nurl = \"\"\nenabled = false\n\
+             [bot]\nmint = \"So11111111111111111111111111111111111111112\"\n\
+             minimum_profit = {}\noptimization_method = \"ternary\"\nmax_hops = 3\n\
+             price_threshold = 0.001\noptimization_amount_percent = 100\n\
+             routes_batch_size = 40\nenabled_slippage = false\nslippage_bps = 0\n\
+             [watcher]\nonly_succeed = false\nonly_failed = false\nmax_pools = 100\nmax_routes = 100",
+            rpc_url, minimum_profit
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn route_direction_must_be_known() {
+        let with_direction = |direction: &str, base_mints: &str| {
+            parse_config(
+                &include_str!("../config.toml.example")
+                    .replace(
+                        "route_direction = \"both\"",
+                        &format!("route_direction = \"{}\"", direction),
+                    )
+                    .replace("base_mints = []", &format!("base_mints = [{}]", base_mints)),
+            )
+        };
+        let usdc = "\"EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v\"";
+
+        assert!(with_direction("both", "").is_ok());
+        assert_eq!(
+            with_direction("buy_first", "").unwrap().bot.route_direction,
+            RouteDirection::BuyFirst
+        );
+        assert!(with_direction("sell_first", usdc).is_ok());
+        // nothing to sell into besides bot.mint
+        assert!(with_direction("sell_first", "").is_err());
+        assert!(with_direction("buyfirst", "").is_err());
+        assert!(with_direction("Both", "").is_err());
+    }
+
+    #[test]
+    fn mint_decimals_default_to_common_mints() {
+        let wsol = "So11111111111111111111111111111111111111112";
+        let mints: MintsConfig = toml::from_str("").unwrap();
+        assert_eq!(mints.decimals.get(wsol), Some(&9));
+        assert!(mints.validate().is_ok());
+
+        let mints: MintsConfig = toml::from_str("[decimals]\nnot_a_mint = 6").unwrap();
+        assert!(mints.validate().is_err());
+    }
+
+    #[test]
+    fn tx_fetch_falls_back_to_the_rpc_url() {
+        let mut rpc = config(0, "http://a").rpc;
+        assert_eq!(rpc.tx_fetch_endpoints(), vec!["http://a".to_string()]);
+
+        rpc.tx_fetch_urls = vec!["http://b".to_string(), "http://c".to_string()];
+        assert_eq!(rpc.tx_fetch_endpoints(), rpc.tx_fetch_urls);
+    }
+
+    #[test]
+    fn fees_only_override_known_dexes() {
+        let fees = |dex: &str, fee_bps: u64| BTreeMap::from([(dex.to_string(), fee_bps)]);
+
+        assert!(validate_fees(&fees("solfi", 25)).is_ok());
+        assert!(validate_fees(&fees("raydium_clmm", 0)).is_ok());
+        assert!(validate_fees(&fees("meteora_dlmm", 25)).is_err());
+        assert!(validate_fees(&fees("Solfi", 25)).is_err());
+        assert!(validate_fees(&fees("solfi", 10_000)).is_err());
+    }
+
+    #[test]
+    fn disabled_dexes_must_be_known() {
+        let validate = |names: &[&str]| {
+            let dexes: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+            validate_disabled_dexes(&dexes)
+        };
+
+        assert!(validate(&[]).is_ok());
+        assert!(validate(&["meteora_dlmm", "solfi"]).is_ok());
+        assert!(validate(&["orca"]).is_err());
+    }
+
+    #[test]
+    fn reload_takes_runtime_settings_only() {
+        let current = config(100_000, "http://a");
+        let (next, report) = current.with_runtime_settings(&config(250_000, "http://b"));
+
+        assert_eq!(next.bot.minimum_profit, 250_000);
+        assert_eq!(next.rpc.url, "http://a");
+        assert_eq!(report.changed, vec!["bot.minimum_profit 100000 -> 250000"]);
+        assert_eq!(report.ignored, vec!["rpc"]);
+
+        let (_, report) = current.with_runtime_settings(&current);
+        assert!(report.changed.is_empty() && report.ignored.is_empty());
+    }
+
+    #[test]
+    fn tick_array_counts_default_and_bounds() {
+        let whirlpool: WhirlpoolConfig = toml::from_str("").unwrap();
+        let clmm: ClmmConfig = toml::from_str("").unwrap();
+        assert_eq!(whirlpool.tick_arrays, 3);
+        assert_eq!(clmm.tick_arrays, 6);
+
+        for (tick_arrays, ok) in [(0, false), (1, true), (3, true), (4, false)] {
+            let whirlpool = WhirlpoolConfig { tick_arrays };
+            assert_eq!(whirlpool.validate().is_ok(), ok, "{}", tick_arrays);
+        }
+        for (tick_arrays, ok) in [(0, false), (1, true)] {
+            let clmm = ClmmConfig {
+                tick_arrays,
+                ..Default::default()
+            };
+            assert_eq!(clmm.validate().is_ok(), ok, "{}", tick_arrays);
+        }
+    }
+
+    #[test]
+    fn example_config_parses() {
+        // unit tests read their config from it
+        let config = parse_config(include_str!("../config.toml.example")).unwrap();
+        assert!(config.fees.is_empty());
+    }
+}
@@ -101,6 +101,13 @@ pub fn get_delta_amount_b_unsigned_unchecked(
             let (result, _) = prod.overflowing_shr((RESOLUTION as usize) * 2);
             Ok(result)
         }
+        Rounding::Nearest => {
+            let half = U256::from(1).safe_shl((RESOLUTION as usize) * 2 - 1)?;
+            let (result, _) = prod
+                .safe_add(half)?
+                .overflowing_shr((RESOLUTION as usize) * 2);
+            Ok(result)
+        }
     }
 }
 
@@ -45,8 +45,7 @@ impl TryFrom<u8> for CollectFeeMode {
     fn try_from(value: u8) -> Result<Self> {
         match value {
             0 => Ok(CollectFeeMode::BothToken),
-            1 => Ok(CollectFeeMode::BothToken),
-            2 => Ok(CollectFeeMode::OnlyB),
+            1 => Ok(CollectFeeMode::OnlyB),
             _ => Err(anyhow!("Invalid collect_fee_mode value: {}", value)),
         }
     }
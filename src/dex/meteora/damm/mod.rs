@@ -23,6 +23,7 @@ pub use util_math::*;
 
 pub const PROGRAM_ID: &str = "cpamdpZCGKUy5JxQXB4dcpGPiikHawvSWAd6mEn1sGG";
 pub const POOL_DISCRIMINATOR: [u8; 8] = [241, 154, 109, 4, 17, 177, 109, 188];
+pub const POOL_ACCOUNT_LEN: usize = 1112;
 
 pub fn program_id() -> Pubkey {
     Pubkey::from_str(PROGRAM_ID).unwrap()
@@ -181,8 +182,7 @@ impl DynamicFeeStruct {
             let square_vfa_bin: u128 = self
                 .volatility_accumulator
                 .safe_mul(self.bin_step.into())?
-                .checked_pow(2)
-                .unwrap();
+                .safe_pow(2)?;
             // Variable fee control, volatility accumulator, bin step are in basis point unit (10_000)
             // This is 1e20. Which > 1e9. Scale down it to 1e9 unit and ceiling the remaining.
             let v_fee = square_vfa_bin.safe_mul(self.variable_fee_control.into())?;
@@ -371,6 +371,9 @@ impl Pool {
         // Read main pool fields
         let token_a_mint = reader.read_pubkey()?;
         let token_b_mint = reader.read_pubkey()?;
+        if token_a_mint == token_b_mint {
+            return Err(anyhow!("Pool has identical mints {}", token_a_mint));
+        }
         let token_a_vault = reader.read_pubkey()?;
         let token_b_vault = reader.read_pubkey()?;
         let whitelisted_vault = reader.read_pubkey()?;
@@ -533,12 +536,16 @@ impl Pool {
         sqrt_price * sqrt_price
     }
 
+    /// Quote a swap through the curve. The program rounds the curve output `Rounding::Down`,
+    /// which is the amount actually received. `Rounding::Nearest` gives an unbiased estimate
+    /// instead, for comparing routes without shaving a unit off every hop.
     pub fn get_swap_result(
         &self,
         amount_in: u64,
         fee_mode: &FeeMode,
         trade_direction: TradeDirection,
         current_point: u64,
+        output_rounding: Rounding,
     ) -> Result<SwapResult> {
         let mut actual_protocol_fee = 0;
         let mut actual_lp_fee = 0;
@@ -573,8 +580,12 @@ impl Pool {
             output_amount,
             next_sqrt_price,
         } = match trade_direction {
-            TradeDirection::AtoB => self.get_swap_result_from_a_to_b(actual_amount_in),
-            TradeDirection::BtoA => self.get_swap_result_from_b_to_a(actual_amount_in),
+            TradeDirection::AtoB => {
+                self.get_swap_result_from_a_to_b(actual_amount_in, output_rounding)
+            }
+            TradeDirection::BtoA => {
+                self.get_swap_result_from_b_to_a(actual_amount_in, output_rounding)
+            }
         }?;
 
         let actual_amount_out = if fee_mode.fees_on_input {
@@ -608,7 +619,11 @@ impl Pool {
             referral_fee: actual_referral_fee,
         })
     }
-    fn get_swap_result_from_a_to_b(&self, amount_in: u64) -> Result<SwapAmount> {
+    fn get_swap_result_from_a_to_b(
+        &self,
+        amount_in: u64,
+        output_rounding: Rounding,
+    ) -> Result<SwapAmount> {
         // finding new target price
         let next_sqrt_price =
             get_next_sqrt_price_from_input(self.sqrt_price, self.liquidity, amount_in, true)?;
@@ -622,7 +637,7 @@ impl Pool {
             next_sqrt_price,
             self.sqrt_price,
             self.liquidity,
-            Rounding::Down,
+            output_rounding,
         )?;
 
         Ok(SwapAmount {
@@ -631,7 +646,11 @@ impl Pool {
         })
     }
 
-    fn get_swap_result_from_b_to_a(&self, amount_in: u64) -> Result<SwapAmount> {
+    fn get_swap_result_from_b_to_a(
+        &self,
+        amount_in: u64,
+        output_rounding: Rounding,
+    ) -> Result<SwapAmount> {
         // finding new target price
         let next_sqrt_price =
             get_next_sqrt_price_from_input(self.sqrt_price, self.liquidity, amount_in, false)?;
@@ -644,7 +663,7 @@ impl Pool {
             self.sqrt_price,
             next_sqrt_price,
             self.liquidity,
-            Rounding::Down,
+            output_rounding,
         )?;
 
         Ok(SwapAmount {
@@ -715,6 +734,7 @@ impl TryFrom<u8> for ActivationType {
     }
 }
 
+/// Quote as the program computes it, the curve output rounded down.
 pub fn get_quote(
     pool: &Pool,
     current_timestamp: u64,
@@ -722,6 +742,27 @@ pub fn get_quote(
     actual_amount_in: u64,
     a_to_b: bool,
     has_referral: bool,
+) -> Result<SwapResult> {
+    get_quote_with_rounding(
+        pool,
+        current_timestamp,
+        current_slot,
+        actual_amount_in,
+        a_to_b,
+        has_referral,
+        Rounding::Down,
+    )
+}
+
+/// `get_quote` with the curve output rounded by `output_rounding`, see `Pool::get_swap_result`.
+pub fn get_quote_with_rounding(
+    pool: &Pool,
+    current_timestamp: u64,
+    current_slot: u64,
+    actual_amount_in: u64,
+    a_to_b: bool,
+    has_referral: bool,
+    output_rounding: Rounding,
 ) -> Result<SwapResult> {
     let result = if pool.pool_fees.dynamic_fee.is_dynamic_fee_enable() {
         let mut pool = pool.clone();
@@ -733,6 +774,7 @@ pub fn get_quote(
             actual_amount_in,
             a_to_b,
             has_referral,
+            output_rounding,
         )
     } else {
         get_internal_quote(
@@ -742,6 +784,7 @@ pub fn get_quote(
             actual_amount_in,
             a_to_b,
             has_referral,
+            output_rounding,
         )
     };
 
@@ -755,6 +798,7 @@ fn get_internal_quote(
     actual_amount_in: u64,
     a_to_b: bool,
     has_referral: bool,
+    output_rounding: Rounding,
 ) -> Result<SwapResult> {
     let activation_type = ActivationType::try_from(pool.activation_type)?;
     let current_point = match activation_type {
@@ -770,8 +814,582 @@ fn get_internal_quote(
 
     let fee_mode = &FeeMode::get_fee_mode(pool.collect_fee_mode, trade_direction, has_referral)?;
 
-    let swap_result =
-        pool.get_swap_result(actual_amount_in, fee_mode, trade_direction, current_point)?;
+    let swap_result = pool.get_swap_result(
+        actual_amount_in,
+        fee_mode,
+        trade_direction,
+        current_point,
+        output_rounding,
+    )?;
 
     Ok(swap_result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{account_data, recorded_swaps};
+    use proptest::prelude::*;
+
+    const POOL_ACCOUNT_LEN: usize = 1112;
+    // offsets are relative to the start of the account, discriminator included
+    const DYNAMIC_FEE_OFFSET: usize = 8 + 48;
+
+    /// Synthetic fee section, serialized with the pool account layout. Not a recorded account.
+    struct FeeFixture {
+        cliff_fee_numerator: u64,
+        fee_scheduler_mode: u8,
+        number_of_period: u16,
+        period_frequency: u64,
+        reduction_factor: u64,
+        activation_point: u64,
+        max_volatility_accumulator: u32,
+        variable_fee_control: u32,
+        bin_step: u16,
+        sqrt_price_reference: u128,
+        volatility_accumulator: u128,
+        volatility_reference: u128,
+    }
+
+    impl FeeFixture {
+        fn flat(cliff_fee_numerator: u64) -> Self {
+            FeeFixture {
+                cliff_fee_numerator,
+                fee_scheduler_mode: 0,
+                number_of_period: 0,
+                period_frequency: 0,
+                reduction_factor: 0,
+                activation_point: 0,
+                max_volatility_accumulator: 14_460_000,
+                variable_fee_control: 0,
+                bin_step: 1,
+                sqrt_price_reference: ONE_Q64,
+                volatility_accumulator: 0,
+                volatility_reference: 0,
+            }
+        }
+
+        /// Serialize into a full pool account so the fixture goes through `Pool::deserialize`.
+        fn to_account(&self) -> Vec<u8> {
+            let mut data = vec![0u8; POOL_ACCOUNT_LEN];
+            data[0..8].copy_from_slice(&Pool::DISCRIMINATOR);
+
+            let base = 8;
+            data[base..base + 8].copy_from_slice(&self.cliff_fee_numerator.to_le_bytes());
+            data[base + 8] = self.fee_scheduler_mode;
+            data[base + 14..base + 16].copy_from_slice(&self.number_of_period.to_le_bytes());
+            data[base + 16..base + 24].copy_from_slice(&self.period_frequency.to_le_bytes());
+            data[base + 24..base + 32].copy_from_slice(&self.reduction_factor.to_le_bytes());
+
+            // token_a_mint and token_b_mint follow the fee section, they must differ
+            let mints = 8 + 160;
+            data[mints..mints + 32].fill(1);
+            data[mints + 32..mints + 64].fill(2);
+
+            let dynamic = DYNAMIC_FEE_OFFSET;
+            let bin_step_u128 = u128::from(self.bin_step) * ONE_Q64 / u128::from(BASIS_POINT_MAX);
+            data[dynamic] = 1;
+            data[dynamic + 8..dynamic + 12]
+                .copy_from_slice(&self.max_volatility_accumulator.to_le_bytes());
+            data[dynamic + 12..dynamic + 16]
+                .copy_from_slice(&self.variable_fee_control.to_le_bytes());
+            data[dynamic + 16..dynamic + 18].copy_from_slice(&self.bin_step.to_le_bytes());
+            data[dynamic + 32..dynamic + 48].copy_from_slice(&bin_step_u128.to_le_bytes());
+            data[dynamic + 48..dynamic + 64]
+                .copy_from_slice(&self.sqrt_price_reference.to_le_bytes());
+            data[dynamic + 64..dynamic + 80]
+                .copy_from_slice(&self.volatility_accumulator.to_le_bytes());
+            data[dynamic + 80..dynamic + 96]
+                .copy_from_slice(&self.volatility_reference.to_le_bytes());
+
+            // activation_point sits after the fee section, six pubkeys and the liquidity/fee counters
+            let activation = 8 + 160 + 6 * 32 + 32 + 32 + 48;
+            data[activation..activation + 8].copy_from_slice(&self.activation_point.to_le_bytes());
+            data
+        }
+
+        fn pool(&self) -> Pool {
+            Pool::deserialize(&self.to_account()).unwrap()
+        }
+    }
+
+    // Expected numerators below were worked out by hand from the program's formula:
+    // variable = ceil((volatility_accumulator * bin_step)^2 * variable_fee_control / 1e11)
+    // total = base + variable, clamped to MAX_FEE_NUMERATOR when applied to an amount.
+    // None is recorded from chain, so they pin the implementation to that formula and catch
+    // regressions, not a formula that itself differs from the program.
+
+    #[test]
+    fn fixture_round_trips_through_deserialize() {
+        let fixture = FeeFixture {
+            volatility_accumulator: 1_000_000,
+            variable_fee_control: 240,
+            activation_point: 42,
+            ..FeeFixture::flat(2_500_000)
+        };
+        let pool = fixture.pool();
+        let dynamic_fee = &pool.pool_fees.dynamic_fee;
+
+        assert!(dynamic_fee.is_dynamic_fee_enable());
+        assert_eq!(pool.pool_fees.base_fee.cliff_fee_numerator, 2_500_000);
+        assert_eq!(dynamic_fee.max_volatility_accumulator, 14_460_000);
+        assert_eq!(dynamic_fee.variable_fee_control, 240);
+        assert_eq!(dynamic_fee.bin_step, 1);
+        assert_eq!(dynamic_fee.bin_step_u128, 1_844_674_407_370_955);
+        assert_eq!(dynamic_fee.volatility_accumulator, 1_000_000);
+        assert_eq!(pool.activation_point, 42);
+    }
+
+    #[test]
+    fn total_fee_with_low_volatility() {
+        let pool = FeeFixture {
+            volatility_accumulator: 1_000_000,
+            variable_fee_control: 240,
+            ..FeeFixture::flat(2_500_000)
+        }
+        .pool();
+
+        assert_eq!(pool.pool_fees.dynamic_fee.get_variable_fee().unwrap(), 2_400);
+        assert_eq!(pool.pool_fees.get_total_trading_fee(100, 0).unwrap(), 2_502_400);
+    }
+
+    #[test]
+    fn total_fee_at_max_volatility() {
+        let pool = FeeFixture {
+            volatility_accumulator: 14_460_000,
+            variable_fee_control: 240,
+            ..FeeFixture::flat(2_500_000)
+        }
+        .pool();
+
+        assert_eq!(pool.pool_fees.get_total_trading_fee(100, 0).unwrap(), 3_001_820);
+    }
+
+    #[test]
+    fn variable_fee_rounds_up() {
+        let pool = FeeFixture {
+            volatility_accumulator: 1_234_567,
+            variable_fee_control: 7,
+            ..FeeFixture::flat(1_000_000)
+        }
+        .pool();
+
+        assert_eq!(pool.pool_fees.dynamic_fee.get_variable_fee().unwrap(), 107);
+        assert_eq!(pool.pool_fees.get_total_trading_fee(0, 0).unwrap(), 1_000_107);
+    }
+
+    #[test]
+    fn total_fee_with_linear_scheduler() {
+        let pool = FeeFixture {
+            fee_scheduler_mode: 0,
+            number_of_period: 100,
+            period_frequency: 60,
+            reduction_factor: 100_000,
+            activation_point: 1_000,
+            bin_step: 10,
+            volatility_accumulator: 2_500_000,
+            variable_fee_control: 500,
+            ..FeeFixture::flat(50_000_000)
+        }
+        .pool();
+
+        // ten full periods have passed: 50_000_000 - 10 * 100_000
+        let current_point = 1_000 + 60 * 10 + 5;
+        assert_eq!(
+            pool.pool_fees
+                .get_total_trading_fee(current_point, pool.activation_point)
+                .unwrap(),
+            49_000_000 + 3_125_000
+        );
+        // before activation the scheduler is fully decayed
+        assert_eq!(
+            pool.pool_fees
+                .get_total_trading_fee(0, pool.activation_point)
+                .unwrap(),
+            40_000_000 + 3_125_000
+        );
+    }
+
+    #[test]
+    fn fee_on_amount_is_capped() {
+        let pool = FeeFixture {
+            bin_step: 80,
+            max_volatility_accumulator: 350_000,
+            volatility_accumulator: 350_000,
+            variable_fee_control: 120_000,
+            ..FeeFixture::flat(10_000_000)
+        }
+        .pool();
+
+        assert_eq!(
+            pool.pool_fees.get_total_trading_fee(0, 0).unwrap(),
+            10_000_000 + 940_800_000
+        );
+
+        let result = pool
+            .pool_fees
+            .get_fee_on_amount(1_000_000, false, 0, 0)
+            .unwrap();
+        assert_eq!(result.amount + result.lp_fee + result.protocol_fee, 1_000_000);
+        assert_eq!(result.lp_fee + result.protocol_fee, 500_000);
+    }
+
+    #[test]
+    fn volatility_accumulator_tracks_price_moves() {
+        let mut dynamic_fee = FeeFixture {
+            max_volatility_accumulator: 150_000,
+            volatility_reference: 20_000,
+            ..FeeFixture::flat(2_500_000)
+        }
+        .pool()
+        .pool_fees
+        .dynamic_fee;
+
+        // five bin steps away from the reference price is ten delta bins
+        let sqrt_price = ONE_Q64 + 5 * dynamic_fee.bin_step_u128;
+        dynamic_fee.update_volatility_accumulator(sqrt_price).unwrap();
+        assert_eq!(dynamic_fee.volatility_accumulator, 20_000 + 10 * 10_000);
+
+        // moving the same distance below the reference gives the same delta
+        let sqrt_price = ONE_Q64 - 5 * dynamic_fee.bin_step_u128;
+        dynamic_fee.update_volatility_accumulator(sqrt_price).unwrap();
+        assert_eq!(dynamic_fee.volatility_accumulator, 20_000 + 10 * 10_000);
+
+        let sqrt_price = ONE_Q64 + 50 * dynamic_fee.bin_step_u128;
+        dynamic_fee.update_volatility_accumulator(sqrt_price).unwrap();
+        assert_eq!(dynamic_fee.volatility_accumulator, 150_000);
+    }
+
+    #[test]
+    fn variable_fee_disabled_when_uninitialized() {
+        let mut pool = FeeFixture {
+            volatility_accumulator: 1_000_000,
+            variable_fee_control: 240,
+            ..FeeFixture::flat(2_500_000)
+        }
+        .pool();
+        pool.pool_fees.dynamic_fee.initialized = 0;
+
+        assert_eq!(pool.pool_fees.get_total_trading_fee(0, 0).unwrap(), 2_500_000);
+    }
+
+    #[test]
+    fn rejects_identical_mints() {
+        let mut data = FeeFixture::flat(2_500_000).to_account();
+        let mints = 8 + 160;
+        data.copy_within(mints..mints + 32, mints + 32);
+
+        assert!(Pool::deserialize(&data).is_err());
+    }
+
+    // Random but valid pools: price somewhere in [2^-16, 2^16], bounds a factor 4 away
+    // so large inputs can also hit PriceRangeViolent.
+    fn swap_pool(
+        cliff_fee_numerator: u64,
+        collect_fee_mode: u8,
+        liquidity: u128,
+        sqrt_price: u128,
+    ) -> Pool {
+        let mut pool = FeeFixture::flat(cliff_fee_numerator).pool();
+        pool.collect_fee_mode = collect_fee_mode;
+        pool.liquidity = liquidity;
+        pool.sqrt_price = sqrt_price;
+        pool.sqrt_min_price = sqrt_price / 4;
+        pool.sqrt_max_price = sqrt_price * 4;
+        pool
+    }
+
+    struct QuoteCase {
+        cliff_fee_numerator: u64,
+        collect_fee_mode: u8,
+        liquidity: u128,
+        sqrt_price: u128,
+        amount_in: u64,
+        a_to_b: bool,
+        output_amount: u64,
+        next_sqrt_price: u128,
+        lp_fee: u64,
+        protocol_fee: u64,
+    }
+
+    // Expected values were worked out from the program's swap math with a 20% protocol cut:
+    // a to b: next = ceil(L * sp / (L + in * sp)), out = (L * (sp - next)) >> 128
+    // b to a: next = sp + (in << 128) / L, out = floor(L * (next - sp) / (sp * next))
+    // fees are ceil(amount * numerator / 1e9), taken from the output unless the pool
+    // collects only in token b and the trade pays in b.
+    const QUOTE_CASES: [QuoteCase; 4] = [
+        QuoteCase {
+            cliff_fee_numerator: 2_500_000,
+            collect_fee_mode: 0,
+            liquidity: 1_000_000_000 << 64,
+            sqrt_price: ONE_Q64,
+            amount_in: 1_000_000_000,
+            a_to_b: true,
+            output_amount: 498_750_000,
+            next_sqrt_price: ONE_Q64 / 2,
+            lp_fee: 1_000_000,
+            protocol_fee: 250_000,
+        },
+        QuoteCase {
+            cliff_fee_numerator: 2_500_000,
+            collect_fee_mode: 0,
+            liquidity: 1_000_000_000 << 64,
+            sqrt_price: ONE_Q64,
+            amount_in: 1_000_000_000,
+            a_to_b: false,
+            output_amount: 498_750_000,
+            next_sqrt_price: ONE_Q64 * 2,
+            lp_fee: 1_000_000,
+            protocol_fee: 250_000,
+        },
+        QuoteCase {
+            cliff_fee_numerator: 10_000_000,
+            collect_fee_mode: 1,
+            liquidity: 123_456_789_012 << 64,
+            sqrt_price: ONE_Q64 * 3 / 2,
+            amount_in: 50_000_000_000,
+            a_to_b: true,
+            output_amount: 69_284_603_185,
+            next_sqrt_price: 17_213_135_935_565_926_162,
+            lp_fee: 559_875_582,
+            protocol_fee: 139_968_895,
+        },
+        // OnlyB charges the fee on the token b input
+        QuoteCase {
+            cliff_fee_numerator: 10_000_000,
+            collect_fee_mode: 1,
+            liquidity: 123_456_789_012 << 64,
+            sqrt_price: ONE_Q64 * 3 / 2,
+            amount_in: 50_000_000_000,
+            a_to_b: false,
+            output_amount: 17_359_741_149,
+            next_sqrt_price: 35_066_338_213_504_880_492,
+            lp_fee: 400_000_000,
+            protocol_fee: 100_000_000,
+        },
+    ];
+
+    #[derive(serde::Deserialize)]
+    struct RecordedSwap {
+        signature: String,
+        slot: u64,
+        unix_timestamp: u64,
+        pool: String,
+        amount_in: u64,
+        a_to_b: bool,
+        has_referral: bool,
+        amount_out: u64,
+    }
+
+    #[test]
+    #[ignore = "needs swaps recorded from chain in tests/fixtures/damm"]
+    fn recorded_swaps_match_quotes() {
+        let swaps = recorded_swaps::<RecordedSwap>("damm");
+        assert!(
+            !swaps.is_empty(),
+            "no recorded swaps in tests/fixtures/damm"
+        );
+
+        for (file, swap) in swaps {
+            let pool = Pool::deserialize(&account_data(&swap.pool)).unwrap();
+            let result = get_quote(
+                &pool,
+                swap.unix_timestamp,
+                swap.slot,
+                swap.amount_in,
+                swap.a_to_b,
+                swap.has_referral,
+            )
+            .unwrap();
+            assert_eq!(
+                result.output_amount, swap.amount_out,
+                "{} ({})",
+                file, swap.signature
+            );
+        }
+    }
+
+    #[test]
+    fn swap_matches_program_quotes() {
+        for (i, case) in QUOTE_CASES.iter().enumerate() {
+            let mut pool = swap_pool(
+                case.cliff_fee_numerator,
+                case.collect_fee_mode,
+                case.liquidity,
+                case.sqrt_price,
+            );
+            pool.pool_fees.protocol_fee_percent = 20;
+
+            let result = get_quote(&pool, 0, 0, case.amount_in, case.a_to_b, false).unwrap();
+            assert_eq!(result.output_amount, case.output_amount, "case {}", i);
+            assert_eq!(result.next_sqrt_price, case.next_sqrt_price, "case {}", i);
+            assert_eq!(result.lp_fee, case.lp_fee, "case {}", i);
+            assert_eq!(result.protocol_fee, case.protocol_fee, "case {}", i);
+        }
+    }
+
+    #[test]
+    fn fee_mode_follows_collect_fee_mode() {
+        let on_input = |mode, direction| {
+            FeeMode::get_fee_mode(mode, direction, false)
+                .unwrap()
+                .fees_on_input
+        };
+
+        // cp-amm: 0 collects in both tokens, 1 only in token b
+        assert!(!on_input(0, TradeDirection::AtoB));
+        assert!(!on_input(0, TradeDirection::BtoA));
+        assert!(!on_input(1, TradeDirection::AtoB));
+        assert!(on_input(1, TradeDirection::BtoA));
+        for mode in [2, 3] {
+            assert!(FeeMode::get_fee_mode(mode, TradeDirection::AtoB, false).is_err());
+        }
+    }
+
+    #[test]
+    fn output_fee_is_taken_from_the_output() {
+        // every output-fee path: both directions when collecting in both tokens, a to b with OnlyB
+        let paths = [(0, true), (0, false), (1, true)];
+        for (collect_fee_mode, a_to_b) in paths {
+            let mut pool = swap_pool(10_000_000, collect_fee_mode, 1_000_000_000 << 64, ONE_Q64);
+            pool.pool_fees.protocol_fee_percent = 20;
+            let amount_in = 1_000_000_000;
+            let case = format!("mode {} a_to_b {}", collect_fee_mode, a_to_b);
+
+            // the whole input goes through the curve
+            let gross = if a_to_b {
+                pool.get_swap_result_from_a_to_b(amount_in, Rounding::Down)
+            } else {
+                pool.get_swap_result_from_b_to_a(amount_in, Rounding::Down)
+            }
+            .unwrap();
+            let fee = pool
+                .pool_fees
+                .get_fee_on_amount(gross.output_amount, false, 0, pool.activation_point)
+                .unwrap();
+
+            let result = get_quote(&pool, 0, 0, amount_in, a_to_b, false).unwrap();
+            assert_eq!(result.next_sqrt_price, gross.next_sqrt_price, "{}", case);
+            assert_eq!(result.output_amount, fee.amount, "{}", case);
+            assert_eq!(
+                result.output_amount + result.lp_fee + result.protocol_fee,
+                gross.output_amount,
+                "{}",
+                case
+            );
+            assert_eq!(result.lp_fee, fee.lp_fee, "{}", case);
+            assert_eq!(result.protocol_fee, fee.protocol_fee, "{}", case);
+        }
+    }
+
+    #[test]
+    fn nearest_output_is_at_most_one_above_program_output() {
+        let pool = swap_pool(10_000_000, 1, 123_456_789_012 << 64, ONE_Q64 * 3 / 2);
+        for (amount_in, a_to_b) in [(50_000_000_000, true), (50_000_000_000, false), (7, true)] {
+            let quote = |rounding| {
+                get_quote_with_rounding(&pool, 0, 0, amount_in, a_to_b, false, rounding)
+                    .unwrap()
+                    .output_amount
+            };
+
+            let down = get_quote(&pool, 0, 0, amount_in, a_to_b, false).unwrap();
+            let down = down.output_amount;
+            assert_eq!(quote(Rounding::Down), down);
+            assert!((down..=down + 1).contains(&quote(Rounding::Nearest)));
+        }
+    }
+
+    fn arb_pool() -> impl Strategy<Value = Pool> {
+        (
+            100_000u64..=100_000_000,
+            prop::sample::select(vec![0u8, 1]),
+            (1u128 << 64)..=(1u128 << 96),
+            (1u128 << 56)..=(1u128 << 72),
+        )
+            .prop_map(|(fee, mode, liquidity, sqrt_price)| {
+                swap_pool(fee, mode, liquidity, sqrt_price)
+            })
+    }
+
+    /// Quote for the properties. An input that runs past the price range or amounts that
+    /// overflow the math reject the case, any other error fails it. Too many rejected cases
+    /// fail the test as well.
+    fn arb_quote(pool: &Pool, amount_in: u64, a_to_b: bool) -> Result<SwapResult, TestCaseError> {
+        match get_quote(pool, 0, 0, amount_in, a_to_b, false) {
+            Ok(result) => Ok(result),
+            Err(e) => match e.to_string().as_str() {
+                "PriceRangeViolent" | "Math overflow" | "TypeCast Failed" => {
+                    Err(TestCaseError::reject(e.to_string()))
+                }
+                _ => Err(TestCaseError::fail(format!("unexpected quote error {}", e))),
+            },
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn swap_output_within_reserves(
+            pool in arb_pool(),
+            amount_in in 1u64..=1_000_000_000_000,
+            a_to_b in any::<bool>(),
+        ) {
+            let result = arb_quote(&pool, amount_in, a_to_b)?;
+
+            // tokens the curve can pay out before the price reaches its bound
+            let reserve = if a_to_b {
+                get_delta_amount_b_unsigned(
+                    pool.sqrt_min_price,
+                    pool.sqrt_price,
+                    pool.liquidity,
+                    Rounding::Down,
+                )
+            } else {
+                get_delta_amount_a_unsigned(
+                    pool.sqrt_price,
+                    pool.sqrt_max_price,
+                    pool.liquidity,
+                    Rounding::Down,
+                )
+            };
+            // a reserve that doesn't fit in u64 can't be exceeded
+            if let Ok(reserve) = reserve {
+                prop_assert!(result.output_amount <= reserve);
+            }
+        }
+
+        #[test]
+        fn swap_moves_price_in_trade_direction(
+            pool in arb_pool(),
+            amount_in in 1u64..=1_000_000_000_000,
+            a_to_b in any::<bool>(),
+        ) {
+            let result = arb_quote(&pool, amount_in, a_to_b)?;
+
+            if a_to_b {
+                prop_assert!(result.next_sqrt_price <= pool.sqrt_price);
+                prop_assert!(result.next_sqrt_price >= pool.sqrt_min_price);
+            } else {
+                prop_assert!(result.next_sqrt_price >= pool.sqrt_price);
+                prop_assert!(result.next_sqrt_price <= pool.sqrt_max_price);
+            }
+        }
+
+        #[test]
+        fn swap_output_monotonic_in_input(
+            pool in arb_pool(),
+            amount_in in 1u64..=1_000_000_000_000,
+            extra in 0u64..=1_000_000_000,
+            a_to_b in any::<bool>(),
+        ) {
+            let small = arb_quote(&pool, amount_in, a_to_b)?;
+            let large = arb_quote(&pool, amount_in + extra, a_to_b)?;
+
+            prop_assert!(small.output_amount <= large.output_amount);
+            if a_to_b {
+                prop_assert!(large.next_sqrt_price <= small.next_sqrt_price);
+            } else {
+                prop_assert!(large.next_sqrt_price >= small.next_sqrt_price);
+            }
+        }
+    }
+}
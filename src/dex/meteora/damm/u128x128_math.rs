@@ -1,12 +1,14 @@
 use ruint::aliases::{U256, U512};
 
-/// Round up, down
+/// Round up, down, or to the nearest (half up)
 #[derive(PartialEq, Clone, Copy)]
 pub enum Rounding {
     /// Rounding up
     Up,
     /// Rounding down
     Down,
+    /// Rounding to the nearest, half up. Not used by the program, only for estimates
+    Nearest,
 }
 
 /// (x * y) / denominator
@@ -64,6 +66,11 @@ pub fn shl_div(x: u128, y: u128, offset: u8, rounding: Rounding) -> Option<u128>
             let (quotient, _) = prod.div_rem(denominator);
             quotient.try_into().ok()
         }
+        Rounding::Nearest => prod
+            .checked_add(denominator >> 1)?
+            .checked_div(denominator)?
+            .try_into()
+            .ok(),
     }
 }
 
@@ -97,6 +104,9 @@ pub fn mul_div_u256(x: U256, y: U256, denominator: U256, rounding: Rounding) ->
             let (quotient, _) = prod.div_rem(denominator);
             quotient
         }
+        Rounding::Nearest => prod
+            .checked_add(denominator >> 1)?
+            .checked_div(denominator)?,
     };
     if result > U512::from(U256::MAX) {
         None
@@ -36,6 +36,7 @@ pub fn safe_mul_div_cast_u64<T: FromPrimitive>(
             .safe_sub(1u128)?
             .safe_div(denominator)?,
         Rounding::Down => prod.safe_div(denominator)?,
+        Rounding::Nearest => prod.safe_add(denominator / 2)?.safe_div(denominator)?,
     };
 
     T::from_u128(result).ok_or_else(|| anyhow::anyhow!("TypeCast Failed"))
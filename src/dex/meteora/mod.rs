@@ -2,15 +2,19 @@ use anchor_client::solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
 
 pub const METEORA_DLMM_PROGRAM_ID: &str = "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo";
+use dashmap::DashMap;
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
 
 pub mod damm;
 
-static STEP_RATIO_CACHE: Lazy<HashMap<u16, f64>> = Lazy::new(|| {
-    let mut cache = HashMap::new();
+// Seeded with common bin steps, other steps are added on first use
+static STEP_RATIO_CACHE: Lazy<DashMap<u16, f64>> = Lazy::new(|| {
+    let cache = DashMap::new();
     // Common bin steps in Meteora
-    for bin_step in [1, 5, 10, 15, 20, 25, 50, 70, 75, 80, 100, 200, 500, 1000] {
+    for bin_step in [
+        1, 2, 4, 5, 8, 10, 15, 16, 20, 25, 30, 50, 60, 70, 75, 80, 100, 125, 150, 200, 250, 300,
+        400, 500, 1000,
+    ] {
         cache.insert(bin_step, 1.0 + (bin_step as f64) / 10_000.0);
     }
     cache
@@ -21,6 +25,7 @@ pub mod dlmm {
 
     const DLMM_EVENT_AUTHORITY: &str = "D1ZN9Wj1fRSUQfCjhvnu1hqDMT7hzjzBBpi12nVniYD6";
     pub const POOL_DISCRIMINATOR: [u8; 8] = [33, 11, 49, 98, 181, 101, 177, 13];
+    pub const POOL_ACCOUNT_LEN: usize = 904;
 
     #[inline]
     pub fn program_id() -> Pubkey {
@@ -69,13 +74,13 @@ pub mod utils {
             return 1.0;
         }
 
-        // Use lookup table for common bin steps
-        if let Some(&step_ratio) = STEP_RATIO_CACHE.get(&bin_step) {
-            return fast_powi(step_ratio, active_id);
+        // Use lookup table, caching uncommon bin steps on first miss
+        if let Some(step_ratio) = STEP_RATIO_CACHE.get(&bin_step) {
+            return fast_powi(*step_ratio, active_id);
         }
 
-        // Fallback for uncommon bin steps
         let step_ratio = 1.0 + (bin_step as f64) / 10_000.0;
+        STEP_RATIO_CACHE.insert(bin_step, step_ratio);
         fast_powi(step_ratio, active_id)
     }
 
@@ -0,0 +1,48 @@
+use super::{GlobalConfig, PumpAmmReader};
+use anchor_client::solana_client::nonblocking::rpc_client::RpcClient;
+use anyhow::Result;
+use std::sync::{Arc, RwLock};
+use tracing::info;
+
+/// Fee basis points charged by the pump AMM, read from its global config account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeBps {
+    pub lp: u128,
+    pub protocol: u128,
+    pub coin_creator: u128,
+}
+
+// protocol defaults, used until the global config has been loaded
+static FEE_BPS: RwLock<FeeBps> = RwLock::new(FeeBps {
+    lp: 20,
+    protocol: 5,
+    coin_creator: 80,
+});
+
+#[inline]
+pub fn fee_bps() -> FeeBps {
+    *FEE_BPS.read().unwrap()
+}
+
+pub fn set_fee_bps(config: &GlobalConfig) {
+    let fees = FeeBps {
+        lp: config.lp_fee_basis_points as u128,
+        protocol: config.protocol_fee_basis_points as u128,
+        coin_creator: config.coin_creator_fee_basis_points as u128,
+    };
+
+    let mut current = FEE_BPS.write().unwrap();
+    if *current != fees {
+        info!("Pump AMM fees {:?} -> {:?}", *current, fees);
+        *current = fees;
+    }
+}
+
+/// Read the global config account and cache its fee basis points for quoting. Later updates
+/// of the account go through `set_fee_bps` as it is refreshed with the pools.
+pub async fn load_fee_bps(rpc_client: Arc<RpcClient>) -> Result<GlobalConfig> {
+    let reader = PumpAmmReader::new_with_client(rpc_client)?;
+    let config = reader.read_global_config().await?;
+    set_fee_bps(&config);
+    Ok(config)
+}
@@ -50,3 +50,5 @@ pub mod quote;
 pub use quote::*;
 pub mod pda;
 pub use pda::*;
+pub mod fees;
+pub use fees::*;
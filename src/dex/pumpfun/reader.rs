@@ -18,7 +18,7 @@ pub struct PumpAmmReader {
 impl PumpAmmReader {
     pub fn new(rpc_url: &str) -> Result<Self> {
         let program_id = super::program_id();
-        let rpc_client = Arc::new(RpcClient::new(rpc_url.to_string()));
+        let rpc_client = crate::global::new_rpc_with_commitment(rpc_url, Default::default());
 
         Ok(Self {
             program_id,
@@ -112,6 +112,10 @@ impl PumpAmmReader {
             })?);
         offset += 32;
 
+        if base_mint == quote_mint {
+            return Err(anyhow::anyhow!("Pool has identical mints {}", base_mint));
+        }
+
         // lp_mint: Pubkey (32 bytes)
         let lp_mint =
             Pubkey::new_from_array(data[offset..offset + 32].try_into().map_err(|_| {
@@ -184,12 +188,12 @@ impl PumpAmmReader {
             return Err(anyhow::anyhow!("Invalid GlobalConfig discriminator"));
         }
 
-        let config = self.parse_global_config_data(&account.data[8..])?;
+        let config = Self::parse_global_config_data(&account.data[8..])?;
 
         Ok(config)
     }
 
-    fn parse_global_config_data(&self, data: &[u8]) -> Result<GlobalConfig> {
+    pub fn parse_global_config_data(data: &[u8]) -> Result<GlobalConfig> {
         // GlobalConfig struct:
         // admin: Pubkey (32 bytes)
         // lp_fee_basis_points: u64 (8 bytes)
@@ -347,8 +351,14 @@ pub async fn read_amm_reserves(
         .await?;
 
     let mut iter = accounts.into_iter();
-    let base_account = iter.next().unwrap().unwrap();
-    let quote_account = iter.next().unwrap().unwrap();
+    let base_account = iter
+        .next()
+        .flatten()
+        .ok_or_else(|| anyhow::anyhow!("Base vault {} not found", base_token_account))?;
+    let quote_account = iter
+        .next()
+        .flatten()
+        .ok_or_else(|| anyhow::anyhow!("Quote vault {} not found", quote_token_account))?;
 
     let base_token_amount = crate::util::parse_token_amount(&base_account.data.as_ref())?;
     let quote_token_amount = crate::util::parse_token_amount(&quote_account.data.as_ref())?;
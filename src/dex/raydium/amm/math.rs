@@ -468,6 +468,13 @@ pub fn swap_compute(
     swap_base_in: bool,
     slippage_bps: u64,
 ) -> Result<u64> {
+    if !amm_state.can_swap() {
+        return Err(anyhow!(
+            "Pool status {} does not allow swaps",
+            amm_state.status
+        ));
+    }
+
     let (amm_pool_pc_vault_amount, amm_pool_coin_vault_amount) =
         Calculator::calc_total_without_take_pnl_no_orderbook(
             vaults.pc_vault_amount,
@@ -24,6 +24,25 @@ pub const PROGRAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
 pub const OPENBOOK_ID: &str = "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX";
 
 pub const POOL_DISCRIMINATOR: [u8; 8] = [6, 0, 0, 0, 0, 0, 0, 0];
+pub const POOL_ACCOUNT_LEN: usize = 752;
+
+// `AmmInfo.status` values, the program stores one state rather than independent flags
+/// Account created, pool not initialized yet
+pub const STATUS_UNINITIALIZED: u64 = 0;
+/// Fully operational, swaps, deposits and withdrawals allowed
+pub const STATUS_INITIALIZED: u64 = 1;
+/// Every instruction disabled by the admin
+pub const STATUS_DISABLED: u64 = 2;
+/// Only withdrawals allowed
+pub const STATUS_WITHDRAW_ONLY: u64 = 3;
+/// Deposits and withdrawals allowed, swaps disabled
+pub const STATUS_LIQUIDITY_ONLY: u64 = 4;
+/// Only the orderbook side is running, swaps disabled
+pub const STATUS_ORDERBOOK_ONLY: u64 = 5;
+/// Swaps allowed, deposits and withdrawals disabled
+pub const STATUS_SWAP_ONLY: u64 = 6;
+/// Swaps allowed once `pool_open_time` has passed
+pub const STATUS_WAITING_TRADE: u64 = 7;
 
 pub fn program_id() -> Pubkey {
     Pubkey::from_str(PROGRAM_ID).unwrap()
@@ -140,6 +159,9 @@ impl AmmInfo {
         let token_pc = reader.read_pubkey()?;
         let coin_mint = reader.read_pubkey()?;
         let pc_mint = reader.read_pubkey()?;
+        if coin_mint == pc_mint {
+            return Err(anyhow!("Pool has identical mints {}", coin_mint));
+        }
         let lp_mint = reader.read_pubkey()?;
         let open_orders = reader.read_pubkey()?;
         let market = reader.read_pubkey()?;
@@ -226,6 +248,24 @@ impl AmmInfo {
         })
     }
 
+    /// Whether the pool status accepts swaps. A `STATUS_WAITING_TRADE` pool also rejects them
+    /// before its open time, which only the program's clock can tell.
+    pub fn can_swap(&self) -> bool {
+        matches!(
+            self.status,
+            STATUS_INITIALIZED | STATUS_SWAP_ONLY | STATUS_WAITING_TRADE
+        )
+    }
+
+    /// Lot size the amm rounds `mint_in` amounts to.
+    pub fn lot_size(&self, mint_in: &Pubkey) -> u64 {
+        if *mint_in == self.coin_mint {
+            self.coin_lot_size
+        } else {
+            self.pc_lot_size
+        }
+    }
+
     pub fn derive_vault_signer(&self, vault_signer_nonce: u64) -> Result<Pubkey> {
         match Pubkey::create_program_address(
             &[self.market.as_ref(), &vault_signer_nonce.to_le_bytes()],
@@ -59,8 +59,16 @@ pub async fn fetch_vaults(rpc_client: Arc<RpcClient>, amm: &AmmInfo) -> Result<P
         .into_iter()
         .collect::<Vec<_>>();
 
-    let vault_a_data = &accounts[0].as_ref().unwrap().data;
-    let vault_b_data = &accounts[1].as_ref().unwrap().data;
+    let vault_a_data = &accounts
+        .get(0)
+        .and_then(|account| account.as_ref())
+        .ok_or_else(|| anyhow!("Coin vault {} not found", amm.token_coin))?
+        .data;
+    let vault_b_data = &accounts
+        .get(1)
+        .and_then(|account| account.as_ref())
+        .ok_or_else(|| anyhow!("Pc vault {} not found", amm.token_pc))?
+        .data;
 
     Ok(PoolVaults {
         coin_vault_amount: crate::util::parse_token_amount(&vault_a_data)?,
@@ -30,6 +30,7 @@ pub const RAYDIUM_CLMM_PROGRAM_ID: &str = "devi51mZmdwUJGU9hjN27vEz64Gps7uUefqxg
 pub const RAYDIUM_CLMM_PROGRAM_ID: &str = "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK";
 
 pub const POOL_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+pub const POOL_ACCOUNT_LEN: usize = 1544;
 
 pub fn program_id() -> Pubkey {
     Pubkey::from_str(RAYDIUM_CLMM_PROGRAM_ID).unwrap()
@@ -138,6 +139,9 @@ impl PoolState {
         let owner = reader.read_pubkey()?;
         let token_mint_0 = reader.read_pubkey()?;
         let token_mint_1 = reader.read_pubkey()?;
+        if token_mint_0 == token_mint_1 {
+            return Err(anyhow!("Pool has identical mints {}", token_mint_0));
+        }
         let token_vault_0 = reader.read_pubkey()?;
         let token_vault_1 = reader.read_pubkey()?;
         let observation_key = reader.read_pubkey()?;
@@ -152,6 +156,11 @@ impl PoolState {
 
         // Read sqrt_price_x64 (16 bytes) - this is u128 according to IDL
         let sqrt_price_x64 = reader.read_u128()?;
+        if !(tick_array::MIN_SQRT_PRICE_X64..=tick_array::MAX_SQRT_PRICE_X64)
+            .contains(&sqrt_price_x64)
+        {
+            return Err(anyhow!("CLMM sqrt price {} out of bounds", sqrt_price_x64));
+        }
 
         // Read tick_current and padding (8 bytes total)
         let tick_current_bytes = reader.read_u32()?;
@@ -286,10 +295,10 @@ impl PoolState {
     }
 
     /// Get price as rational number for exact calculations
-    pub fn get_price_exact(&self) -> (u128, f64) {
-        // For u128 sqrt_price, we can compute the square directly
-        let sqrt_price_squared = (self.sqrt_price_x64 as u128).pow(2);
-        (sqrt_price_squared, q_math::Q128_128_SCALE)
+    pub fn get_price_exact(&self) -> (U256, f64) {
+        // squared in 256 bits, sqrt prices from 2^64 up don't square into a u128
+        let sqrt_price = U256::from(self.sqrt_price_x64);
+        (sqrt_price * sqrt_price, q_math::Q128_128_SCALE)
     }
 
     /// High precision price calculation
@@ -297,10 +306,49 @@ impl PoolState {
         if self.sqrt_price_x64 == 0 {
             return 0.0;
         }
-        // More precise calculation using u128 arithmetic
-        let sqrt_price_squared = (self.sqrt_price_x64 as u128).pow(2);
-        // Use precise conversion for better accuracy
-        q_math::u128_to_f64_precise(sqrt_price_squared) / q_math::Q128_128_SCALE
+        let (sqrt_price_squared, scale) = self.get_price_exact();
+        // the Q128.128 square split into its integer and fractional parts, each fits a u128
+        let integer = (sqrt_price_squared >> 128).low_u128();
+        let fraction = sqrt_price_squared.low_u128();
+        integer as f64 + q_math::u128_to_f64_precise(fraction) / scale
+    }
+
+    /// APPROXIMATE output of swapping `amount_in` before fees, and its price impact as a fraction
+    /// of the spot output. Treats the current `liquidity` as if it spanned every price, the
+    /// single range of a constant product pool, so it is only good for ranking pools whose tick
+    /// arrays aren't loaded. Never size or build a trade on it. `None` without liquidity.
+    pub fn approximate_price_impact(&self, amount_in: u64, a_to_b: bool) -> Option<(u64, f64)> {
+        if self.liquidity == 0 || self.sqrt_price_x64 == 0 {
+            return None;
+        }
+
+        let liquidity = self.liquidity as f64;
+        let sqrt_price = self.sqrt_price_x64 as f64 / q_math::Q64_64_SCALE;
+        let amount_in = amount_in as f64;
+        let (amount_out, spot_out) = if a_to_b {
+            // token 0 in pushes the price down, token 1 comes out
+            let next_sqrt_price = liquidity * sqrt_price / (liquidity + amount_in * sqrt_price);
+            (
+                liquidity * (sqrt_price - next_sqrt_price),
+                amount_in * sqrt_price * sqrt_price,
+            )
+        } else {
+            let next_sqrt_price = sqrt_price + amount_in / liquidity;
+            (
+                liquidity * (1.0 / sqrt_price - 1.0 / next_sqrt_price),
+                amount_in / (sqrt_price * sqrt_price),
+            )
+        };
+        if !amount_out.is_finite() || !spot_out.is_finite() {
+            return None;
+        }
+
+        let price_impact = if spot_out > 0.0 {
+            1.0 - amount_out / spot_out
+        } else {
+            0.0
+        };
+        Some((amount_out as u64, price_impact))
     }
 
     /// Get current tick price using precomputed constants
@@ -316,8 +364,10 @@ impl PoolState {
     /// Get total fees accumulated
     pub fn get_total_fees(&self) -> (u64, u64) {
         (
-            self.protocol_fees_token_0 + self.fund_fees_token_0,
-            self.protocol_fees_token_1 + self.fund_fees_token_1,
+            self.protocol_fees_token_0
+                .saturating_add(self.fund_fees_token_0),
+            self.protocol_fees_token_1
+                .saturating_add(self.fund_fees_token_1),
         )
     }
 
@@ -448,3 +498,72 @@ impl PoolState {
         (min_tick_boundary, max_tick_boundary)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_state() -> PoolState {
+        let mut data = vec![0u8; POOL_ACCOUNT_LEN];
+        // token_mint_0 and token_mint_1 follow the bump, amm_config and owner
+        data[73..105].fill(1);
+        data[105..137].fill(2);
+        // sqrt_price_x64 of 1.0
+        data[253..269].copy_from_slice(&(1u128 << 64).to_le_bytes());
+        PoolState::deserialize(&data).unwrap()
+    }
+
+    #[test]
+    fn total_fees_saturate_near_u64_max() {
+        let mut pool = pool_state();
+        pool.protocol_fees_token_0 = u64::MAX - 1;
+        pool.fund_fees_token_0 = 2;
+        pool.protocol_fees_token_1 = 10;
+        pool.fund_fees_token_1 = u64::MAX;
+
+        assert_eq!(pool.get_total_fees(), (u64::MAX, u64::MAX));
+    }
+
+    #[test]
+    fn total_fees_add_below_u64_max() {
+        let mut pool = pool_state();
+        pool.protocol_fees_token_0 = u64::MAX - 5;
+        pool.fund_fees_token_0 = 5;
+        pool.protocol_fees_token_1 = 7;
+        pool.fund_fees_token_1 = 3;
+
+        assert_eq!(pool.get_total_fees(), (u64::MAX, 10));
+    }
+
+    #[test]
+    fn exact_price_squares_past_u128() {
+        let mut pool = pool_state();
+        // a price of 1.0 already squares to 2^128
+        assert_eq!(pool.get_price_exact().0, U256::one() << 128);
+        assert_eq!(pool.get_price_precise(), 1.0);
+
+        pool.sqrt_price_x64 = 3 << 63;
+        assert_eq!(pool.get_price_precise(), 2.25);
+
+        pool.sqrt_price_x64 = u128::MAX;
+        let (squared, _) = pool.get_price_exact();
+        assert_eq!(squared, U256::from(u128::MAX) * U256::from(u128::MAX));
+        assert!(pool.get_price_precise().is_finite());
+    }
+
+    #[test]
+    fn approximate_quote_follows_constant_product() {
+        let mut pool = pool_state();
+        assert_eq!(pool.approximate_price_impact(1_000, true), None);
+
+        // at a price of 1.0 the range holds 1_000_000 of each token, x * y = k
+        pool.liquidity = 1_000_000;
+        for a_to_b in [true, false] {
+            let (amount_out, price_impact) = pool.approximate_price_impact(1_000, a_to_b).unwrap();
+            // 1_000 * 1_000_000 / 1_001_000, rounded down
+            assert_eq!(amount_out, 999);
+            assert!((price_impact - 1_000.0 / 1_001_000.0).abs() < 1e-9);
+        }
+        assert_eq!(pool.approximate_price_impact(0, true), Some((0, 0.0)));
+    }
+}
@@ -5,11 +5,14 @@ use anchor_client::solana_client::nonblocking::rpc_client::RpcClient;
 use std::ops::{DerefMut, Neg};
 use std::{collections::VecDeque, sync::Arc};
 
-pub fn get_cur_and_next_five_tick_array(
+/// Keys of the current initialized tick array and the next ones in the swap direction,
+/// `tick_arrays` in total at most.
+pub fn get_cur_and_next_tick_arrays(
     pool_id: Pubkey,
     pool_state: &PoolState,
     tickarray_bitmap_extension: &TickArrayBitmapExtension,
     zero_for_one: bool,
+    tick_arrays: usize,
 ) -> Vec<Pubkey> {
     let (_, mut current_vaild_tick_array_start_index) = pool_state
         .get_first_initialized_tick_array(&Some(tickarray_bitmap_extension.clone()), zero_for_one)
@@ -26,7 +29,7 @@ pub fn get_cur_and_next_five_tick_array(
         )
         .0,
     );
-    let mut max_array_size = 5;
+    let mut max_array_size = tick_arrays.saturating_sub(1);
     while max_array_size != 0 {
         let next_tick_array_index = pool_state
             .next_initialized_tick_array_start_index(
@@ -56,16 +59,16 @@ pub fn get_cur_and_next_five_tick_array(
     tick_array_keys
 }
 
-pub async fn load_cur_and_next_five_tick_array(
+pub async fn load_cur_and_next_tick_arrays(
     rpc_client: Arc<RpcClient>,
     pool_id: Pubkey,
     pool_state: &PoolState,
     tickarray_bitmap_extension: &TickArrayBitmapExtension,
     zero_for_one: bool,
-) -> VecDeque<TickArrayState> {
+    tick_arrays: usize,
+) -> Result<VecDeque<TickArrayState>> {
     let (_, mut current_vaild_tick_array_start_index) = pool_state
-        .get_first_initialized_tick_array(&Some(tickarray_bitmap_extension.clone()), zero_for_one)
-        .unwrap();
+        .get_first_initialized_tick_array(&Some(tickarray_bitmap_extension.clone()), zero_for_one)?;
     let mut tick_array_keys = Vec::new();
     tick_array_keys.push(
         Pubkey::find_program_address(
@@ -78,15 +81,14 @@ pub async fn load_cur_and_next_five_tick_array(
         )
         .0,
     );
-    let mut max_array_size = 5;
+    let mut max_array_size = tick_arrays.saturating_sub(1);
     while max_array_size != 0 {
         let next_tick_array_index = pool_state
             .next_initialized_tick_array_start_index(
                 &Some(tickarray_bitmap_extension.clone()),
                 current_vaild_tick_array_start_index,
                 zero_for_one,
-            )
-            .unwrap();
+            )?;
         if next_tick_array_index.is_none() {
             break;
         }
@@ -104,16 +106,14 @@ pub async fn load_cur_and_next_five_tick_array(
         );
         max_array_size -= 1;
     }
-    let tick_array_rsps = rpc_client
-        .get_multiple_accounts(&tick_array_keys)
-        .await
-        .unwrap();
+    let tick_array_rsps = rpc_client.get_multiple_accounts(&tick_array_keys).await?;
     let mut tick_arrays = VecDeque::new();
-    for tick_array in tick_array_rsps {
-        let tick_array_state = TickArrayState::deserialize(&tick_array.unwrap().data).unwrap();
-        tick_arrays.push_back(tick_array_state);
+    for (key, tick_array) in tick_array_keys.iter().zip(tick_array_rsps) {
+        let tick_array =
+            tick_array.ok_or_else(|| anyhow::anyhow!("Tick array {} not found", key))?;
+        tick_arrays.push_back(TickArrayState::deserialize(&tick_array.data)?);
     }
-    tick_arrays
+    Ok(tick_arrays)
 }
 
 pub fn get_out_put_amount_and_remaining_accounts(
@@ -125,12 +125,12 @@ pub fn get_out_put_amount_and_remaining_accounts(
     pool_state: &PoolState,
     tickarray_bitmap_extension: &TickArrayBitmapExtension,
     tick_arrays: &mut VecDeque<TickArrayState>,
-) -> Result<(u64, VecDeque<i32>), &'static str> {
+) -> Result<(u64, VecDeque<i32>, SwapState), &'static str> {
     let (is_pool_current_tick_array, current_vaild_tick_array_start_index) = pool_state
         .get_first_initialized_tick_array(&Some(*tickarray_bitmap_extension), zero_for_one)
         .unwrap();
 
-    let (amount_calculated, tick_array_start_index_vec) = swap_compute(
+    let (state, tick_array_start_index_vec) = swap_compute(
         zero_for_one,
         is_base_input,
         is_pool_current_tick_array,
@@ -143,7 +143,62 @@ pub fn get_out_put_amount_and_remaining_accounts(
         tick_arrays,
     )?;
 
-    Ok((amount_calculated, tick_array_start_index_vec))
+    Ok((state.amount_calculated, tick_array_start_index_vec, state))
+}
+
+/// Exact-out counterpart of `get_out_put_amount_and_remaining_accounts`: the input (fees
+/// included) needed to receive `output_amount`. Ticks are walked in the swap direction with the
+/// output as the specified amount, and it fails when the loaded tick arrays or the price limit
+/// run out before the whole output is filled instead of returning a partial amount.
+pub fn get_input_amount_for_output(
+    output_amount: u64,
+    sqrt_price_limit_x64: Option<u128>,
+    zero_for_one: bool,
+    trade_fee_rate: u32,
+    pool_state: &PoolState,
+    tickarray_bitmap_extension: &TickArrayBitmapExtension,
+    tick_arrays: &mut VecDeque<TickArrayState>,
+) -> Result<(u64, VecDeque<i32>, SwapState), &'static str> {
+    let (is_pool_current_tick_array, current_vaild_tick_array_start_index) = pool_state
+        .get_first_initialized_tick_array(&Some(*tickarray_bitmap_extension), zero_for_one)
+        .map_err(|_| "Get first initialized tick array failed")?;
+
+    let (state, tick_array_start_index_vec) = swap_compute(
+        zero_for_one,
+        false,
+        is_pool_current_tick_array,
+        trade_fee_rate,
+        output_amount,
+        current_vaild_tick_array_start_index,
+        sqrt_price_limit_x64.unwrap_or(0),
+        pool_state,
+        tickarray_bitmap_extension,
+        tick_arrays,
+    )?;
+
+    if state.amount_specified_remaining != 0 {
+        return Result::Err("not enough liquidity to fill the output amount");
+    }
+
+    Ok((state.amount_calculated, tick_array_start_index_vec, state))
+}
+
+/// Check the tick a swap ended on still holds its final sqrt price. A swap stopping on a tick
+/// boundary while moving down sits one tick below it, so the upper bound is inclusive. A
+/// mismatch means ticks were crossed that the loaded tick arrays don't describe.
+pub fn check_final_state(state: &SwapState) -> Result<(), String> {
+    let lower = tick_array::get_sqrt_price_at_tick(state.tick).map_err(|e| e.to_string())?;
+    let upper = tick_array::get_sqrt_price_at_tick((state.tick + 1).min(tick_array::MAX_TICK))
+        .map_err(|e| e.to_string())?;
+
+    if state.sqrt_price_x64 < lower || state.sqrt_price_x64 > upper {
+        return Err(format!(
+            "tick {} spans sqrt prices [{}, {}], final sqrt price is {}",
+            state.tick, lower, upper, state.sqrt_price_x64
+        ));
+    }
+
+    Ok(())
 }
 
 fn swap_compute(
@@ -157,7 +212,7 @@ fn swap_compute(
     pool_state: &PoolState,
     tickarray_bitmap_extension: &TickArrayBitmapExtension,
     tick_arrays: &mut VecDeque<TickArrayState>,
-) -> Result<(u64, VecDeque<i32>), &'static str> {
+) -> Result<(SwapState, VecDeque<i32>), &'static str> {
     if amount_specified == 0 {
         return Result::Err("amountSpecified must not be 0");
     }
@@ -329,7 +384,7 @@ fn swap_compute(
         loop_count += 1;
     }
 
-    Ok((state.amount_calculated, tick_array_start_index_vec))
+    Ok((state, tick_array_start_index_vec))
 }
 
 #[derive(Debug)]
@@ -363,3 +418,31 @@ pub struct StepComputations {
     // how much fee is being paid in
     pub fee_amount: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_at(tick: i32, sqrt_price_x64: u128) -> SwapState {
+        SwapState {
+            amount_specified_remaining: 0,
+            amount_calculated: 0,
+            sqrt_price_x64,
+            tick,
+            liquidity: 0,
+        }
+    }
+
+    #[test]
+    fn final_tick_must_hold_the_sqrt_price() {
+        let at = |tick| tick_array::get_sqrt_price_at_tick(tick).unwrap();
+
+        assert!(check_final_state(&state_at(100, at(100))).is_ok());
+        assert!(check_final_state(&state_at(100, (at(100) + at(101)) / 2)).is_ok());
+        // a downward swap stopping on tick 101 moves to the tick below it
+        assert!(check_final_state(&state_at(100, at(101))).is_ok());
+
+        assert!(check_final_state(&state_at(100, at(102))).is_err());
+        assert!(check_final_state(&state_at(100, at(100) - 1)).is_err());
+    }
+}
@@ -21,6 +21,16 @@ pub struct TickArrayBitmapExtension {
 }
 
 impl TickArrayBitmapExtension {
+    /// Stand-in for a pool whose extension isn't loaded, no tick array outside the default
+    /// bitmap is initialized.
+    pub fn empty(pool_id: Pubkey) -> Self {
+        TickArrayBitmapExtension {
+            pool_id,
+            positive_tick_array_bitmap: [[0u64; 8]; 14],
+            negative_tick_array_bitmap: [[0u64; 8]; 14],
+        }
+    }
+
     pub fn deserialize(data: &[u8]) -> Result<Self> {
         let mut reader = ByteReader::new(data);
 
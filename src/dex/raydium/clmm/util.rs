@@ -12,7 +12,7 @@ pub async fn fetch_pool_state(
         .await
         .map_err(|e| anyhow!("Failed to fetch account data: {}", e))?;
 
-    PoolState::deserialize(&account_data)
+    PoolState::deserialize(&account_data).map_err(|e| anyhow!("Pool {}: {}", pool_pubkey, e))
 }
 
 pub async fn fetch_bitmap_extension_state(
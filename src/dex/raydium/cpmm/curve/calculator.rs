@@ -91,15 +91,13 @@ impl CurveCalculator {
         protocol_fee_rate: u64,
         fund_fee_rate: u64,
     ) -> Option<SwapResult> {
-        // debit the fee to calculate the amount swapped
+        // debit the fee to calculate the amount swapped, protocol and fund fees are
+        // carved out of the trade fee rather than charged on top of it
         let trade_fee = Fees::trading_fee(source_amount, trade_fee_rate)?;
         let protocol_fee = Fees::protocol_fee(trade_fee, protocol_fee_rate)?;
         let fund_fee = Fees::fund_fee(trade_fee, fund_fee_rate)?;
 
-        let source_amount_less_fees = source_amount
-            .checked_sub(trade_fee)?
-            .checked_sub(protocol_fee)?
-            .checked_sub(fund_fee)?;
+        let source_amount_less_fees = source_amount.checked_sub(trade_fee)?;
 
         let destination_amount_swapped = ConstantProductCurve::swap_base_input_without_fees(
             source_amount_less_fees,
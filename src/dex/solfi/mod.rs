@@ -2,13 +2,16 @@ use crate::{byte_reader::ByteReader, onchain::get_associated_token_address};
 use anchor_client::{
     solana_client::nonblocking::rpc_client::RpcClient, solana_sdk::pubkey::Pubkey,
 };
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use std::{str::FromStr, sync::Arc};
 
 pub mod instruction;
 
 const PROGRAM_ID: &str = "SoLFiHG9TfgtdUXUjWAxi3LtvYuFyDLVhBWxdMZxyCe";
 pub const POOL_DISCRIMINATOR: [u8; 8] = [240, 0, 0, 0, 0, 0, 0, 0];
+// 0.3% swap fee, not stored in the pool account
+pub const FEE_BPS: u64 = 30;
+const BPS_DENOMINATOR: u128 = 10_000;
 
 pub fn program_id() -> Pubkey {
     Pubkey::from_str(PROGRAM_ID).unwrap()
@@ -41,6 +44,9 @@ impl Pool {
 
         let mint_a = reader.read_pubkey()?;
         let mint_b = reader.read_pubkey()?;
+        if mint_a == mint_b {
+            return Err(anyhow!("Pool has identical mints {}", mint_a));
+        }
         let vault_a = get_associated_token_address(market, &mint_a);
         let vault_b = get_associated_token_address(market, &mint_b);
 
@@ -60,8 +66,16 @@ impl Pool {
             .into_iter()
             .collect::<Vec<_>>();
 
-        let vault_a_data = &accounts[0].as_ref().unwrap().data;
-        let vault_b_data = &accounts[1].as_ref().unwrap().data;
+        let vault_a_data = &accounts
+            .get(0)
+            .and_then(|account| account.as_ref())
+            .ok_or_else(|| anyhow!("Vault {} not found", self.vault_a))?
+            .data;
+        let vault_b_data = &accounts
+            .get(1)
+            .and_then(|account| account.as_ref())
+            .ok_or_else(|| anyhow!("Vault {} not found", self.vault_b))?
+            .data;
 
         Ok(PoolReserves {
             vault_a_amount: crate::util::parse_token_amount(&vault_a_data)?,
@@ -81,21 +95,26 @@ pub struct PoolReserves {
 }
 
 impl PoolReserves {
-    pub fn swap_quote(&self, amount_in: u64, a_to_b: bool) -> u64 {
+    /// Either side holds less than `min_reserve`, quotes and prices on it are meaningless.
+    pub fn is_below_min_reserve(&self, min_reserve: u64) -> bool {
+        self.vault_a_amount < min_reserve || self.vault_b_amount < min_reserve
+    }
+
+    pub fn swap_quote(&self, amount_in: u64, a_to_b: bool, fee_bps: u64) -> u64 {
         if a_to_b {
-            self.calculate_swap_a_to_b(amount_in)
+            self.calculate_swap_a_to_b(amount_in, fee_bps)
         } else {
-            self.calculate_swap_b_to_a(amount_in)
+            self.calculate_swap_b_to_a(amount_in, fee_bps)
         }
     }
 
-    pub fn calculate_swap_a_to_b(&self, amount_a_in: u64) -> u64 {
+    pub fn calculate_swap_a_to_b(&self, amount_a_in: u64, fee_bps: u64) -> u64 {
         if amount_a_in == 0 || self.vault_a_amount == 0 || self.vault_b_amount == 0 {
             return 0;
         }
 
-        // Fee 0.3% (997/1000)
-        let amount_a_in_with_fee = (amount_a_in as u128 * 997) / 1000;
+        // Fee 0.3% by default (9970/10000)
+        let amount_a_in_with_fee = (amount_a_in as u128 * after_fee(fee_bps)) / BPS_DENOMINATOR;
 
         // Constant product: x * y = k
         // amount_out = (amount_in * reserve_out) / (reserve_in + amount_in)
@@ -109,13 +128,13 @@ impl PoolReserves {
         (numerator / denominator) as u64
     }
 
-    pub fn calculate_swap_b_to_a(&self, amount_b_in: u64) -> u64 {
+    pub fn calculate_swap_b_to_a(&self, amount_b_in: u64, fee_bps: u64) -> u64 {
         if amount_b_in == 0 || self.vault_a_amount == 0 || self.vault_b_amount == 0 {
             return 0;
         }
 
-        // Fee 0.3% (997/1000)
-        let amount_b_in_with_fee = (amount_b_in as u128 * 997) / 1000;
+        // Fee 0.3% by default (9970/10000)
+        let amount_b_in_with_fee = (amount_b_in as u128 * after_fee(fee_bps)) / BPS_DENOMINATOR;
 
         let numerator = amount_b_in_with_fee * self.vault_a_amount as u128;
         let denominator = self.vault_b_amount as u128 + amount_b_in_with_fee;
@@ -141,14 +160,14 @@ impl PoolReserves {
         self.vault_a_amount as f64 / self.vault_b_amount as f64
     }
 
-    pub fn calculate_amount_a_in_for_b_out(&self, amount_b_out: u64) -> u64 {
+    pub fn calculate_amount_a_in_for_b_out(&self, amount_b_out: u64, fee_bps: u64) -> u64 {
         if amount_b_out == 0 || amount_b_out >= self.vault_b_amount {
             return 0;
         }
 
-        // amount_in = (reserve_in * amount_out) / ((reserve_out - amount_out) * 997/1000)
-        let numerator = self.vault_a_amount as u128 * amount_b_out as u128 * 1000;
-        let denominator = (self.vault_b_amount - amount_b_out) as u128 * 997;
+        // amount_in = (reserve_in * amount_out) / ((reserve_out - amount_out) * 9970/10000)
+        let numerator = self.vault_a_amount as u128 * amount_b_out as u128 * BPS_DENOMINATOR;
+        let denominator = (self.vault_b_amount - amount_b_out) as u128 * after_fee(fee_bps);
 
         if denominator == 0 {
             return 0;
@@ -157,13 +176,13 @@ impl PoolReserves {
         ((numerator / denominator) + 1) as u64 // +1 để round up
     }
 
-    pub fn calculate_amount_b_in_for_a_out(&self, amount_a_out: u64) -> u64 {
+    pub fn calculate_amount_b_in_for_a_out(&self, amount_a_out: u64, fee_bps: u64) -> u64 {
         if amount_a_out == 0 || amount_a_out >= self.vault_a_amount {
             return 0;
         }
 
-        let numerator = self.vault_b_amount as u128 * amount_a_out as u128 * 1000;
-        let denominator = (self.vault_a_amount - amount_a_out) as u128 * 997;
+        let numerator = self.vault_b_amount as u128 * amount_a_out as u128 * BPS_DENOMINATOR;
+        let denominator = (self.vault_a_amount - amount_a_out) as u128 * after_fee(fee_bps);
 
         if denominator == 0 {
             return 0;
@@ -173,10 +192,7 @@ impl PoolReserves {
     }
 }
 
-pub async fn fetch_and_deserialize_pool(
-    rpc_client: Arc<RpcClient>,
-    pool_address: &Pubkey,
-) -> Result<Pool> {
-    let account = rpc_client.get_account(pool_address).await?;
-    Pool::deserialize(&pool_address, &account.data)
+// share of the input left after a fee of `fee_bps`, out of BPS_DENOMINATOR
+fn after_fee(fee_bps: u64) -> u128 {
+    BPS_DENOMINATOR.saturating_sub(fee_bps as u128)
 }
@@ -48,6 +48,9 @@ impl Pool {
         let owner = reader.read_pubkey()?;
         let mint_a = reader.read_pubkey()?;
         let mint_b = reader.read_pubkey()?;
+        if mint_a == mint_b {
+            return Err(anyhow!("Pool has identical mints {}", mint_a));
+        }
         let token_a_reserves = reader.read_u128()?;
         let token_b_reserves = reader.read_u128()?;
         let shift = reader.read_u128()?;
@@ -32,4 +32,6 @@ pub const TICK_INDEX_NOT_IN_ARRAY: CoreError = "Tick index not in array";
 
 pub const INVALID_TICK_ARRAY_SEQUENCE: CoreError = "Invalid tick array sequence";
 
+pub const MISSING_TICK_ARRAYS: CoreError = "Missing tick arrays";
+
 pub const INVALID_ADAPTIVE_FEE_INFO: CoreError = "Invalid adaptive fee info";
@@ -14,6 +14,7 @@ pub use state_math::*;
 
 pub const PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
 pub const POOL_DISCRIMINATOR: [u8; 8] = [63, 149, 209, 12, 225, 128, 99, 9];
+pub const POOL_ACCOUNT_LEN: usize = 653;
 
 pub fn program_id() -> Pubkey {
     Pubkey::from_str(PROGRAM_ID).unwrap()
@@ -7,7 +7,7 @@ use super::{
         Whirlpool,
         oracle::{AdaptiveFeeInfo, Oracle},
         tick::Tick,
-        tick_array::TickArrays,
+        tick_array::{TickArrays, validate_tick_arrays},
     },
     tick_index_to_sqrt_price, try_apply_swap_fee, try_apply_transfer_fee, try_get_amount_delta_a,
     try_get_amount_delta_b, try_get_max_amount_with_slippage_tolerance,
@@ -55,6 +55,12 @@ pub fn swap_quote_by_input_token(
     let token_in_after_fee =
         try_apply_transfer_fee(token_in.into(), transfer_fee_in.unwrap_or_default())?;
 
+    validate_tick_arrays(
+        tick_arrays.iter().flatten(),
+        whirlpool.tick_current_index,
+        whirlpool.tick_spacing,
+    )?;
+
     // let tick_array_options = tick_arrays.into_array();
     let tick_sequence = TickArraySequence::new(tick_arrays, whirlpool.tick_spacing)?;
 
@@ -62,7 +62,7 @@ impl Whirlpool {
 
         let mut reader = ByteReader::new(&data[8..]);
 
-        Ok(Self {
+        let whirlpool = Self {
             whirlpools_config: reader.read_pubkey()?,
             whirlpool_bump: reader.read_bytes_array::<1>()?,
             tick_spacing: reader.read_u16()?,
@@ -86,7 +86,23 @@ impl Whirlpool {
                 WhirlpoolRewardInfo::deserialize(&mut reader)?,
                 WhirlpoolRewardInfo::deserialize(&mut reader)?,
             ],
-        })
+        };
+
+        if !(MIN_SQRT_PRICE..=MAX_SQRT_PRICE).contains(&whirlpool.sqrt_price) {
+            return Err(anyhow!(
+                "Whirlpool sqrt price {} out of bounds",
+                whirlpool.sqrt_price
+            ));
+        }
+
+        if whirlpool.token_mint_a == whirlpool.token_mint_b {
+            return Err(anyhow!(
+                "Pool has identical mints {}",
+                whirlpool.token_mint_a
+            ));
+        }
+
+        Ok(whirlpool)
     }
 
     pub fn fee_tier_index(&self) -> u16 {
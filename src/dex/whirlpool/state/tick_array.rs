@@ -71,6 +71,23 @@ pub enum TickArrays {
 }
 
 impl TickArrays {
+    pub fn as_vec(&self) -> Vec<&TickArray> {
+        match self {
+            TickArrays::One(a) => vec![a],
+            TickArrays::Two(a, b) => vec![a, b],
+            TickArrays::Three(a, b, c) => vec![a, b, c],
+            TickArrays::Four(a, b, c, d) => vec![a, b, c, d],
+            TickArrays::Five(a, b, c, d, e) => vec![a, b, c, d, e],
+            TickArrays::Six(a, b, c, d, e, f) => vec![a, b, c, d, e, f],
+        }
+    }
+
+    /// Checks that the arrays are contiguous and cover the current tick, so a swap
+    /// starting at `current_tick` can traverse them in either direction without gaps.
+    pub fn validate(&self, current_tick: i32, tick_spacing: u16) -> Result<(), CoreError> {
+        validate_tick_arrays(self.as_vec(), current_tick, tick_spacing)
+    }
+
     pub fn into_array(self) -> [Option<TickArray>; 6] {
         match self {
             TickArrays::One(a) => [Some(a), None, None, None, None, None],
@@ -83,6 +100,38 @@ impl TickArrays {
     }
 }
 
+pub fn validate_tick_arrays<'a>(
+    tick_arrays: impl IntoIterator<Item = &'a TickArray>,
+    current_tick: i32,
+    tick_spacing: u16,
+) -> Result<(), CoreError> {
+    let mut start_indexes: Vec<i32> = tick_arrays
+        .into_iter()
+        .map(|tick_array| tick_array.start_tick_index)
+        .collect();
+    if start_indexes.is_empty() {
+        return Err(MISSING_TICK_ARRAYS);
+    }
+
+    start_indexes.sort_unstable();
+    start_indexes.dedup();
+
+    let array_span = TICK_ARRAY_SIZE as i32 * tick_spacing as i32;
+    for pair in start_indexes.windows(2) {
+        if pair[1] - pair[0] != array_span {
+            return Err(MISSING_TICK_ARRAYS);
+        }
+    }
+
+    let lower = start_indexes[0];
+    let upper = start_indexes[start_indexes.len() - 1] + array_span;
+    if current_tick < lower || current_tick >= upper {
+        return Err(MISSING_TICK_ARRAYS);
+    }
+
+    Ok(())
+}
+
 impl From<TickArrays> for [Option<TickArray>; 1] {
     fn from(tick_arrays: TickArrays) -> Self {
         match tick_arrays {
@@ -18,6 +18,7 @@ pub async fn fetch_and_deserialize_whirlpool(
 ) -> Result<Whirlpool> {
     let account = rpc_client.get_account(whirlpool_address).await?;
     Whirlpool::deserialize(&account.data)
+        .map_err(|e| anyhow::anyhow!("Pool {}: {}", whirlpool_address, e))
 }
 
 pub async fn fetch_and_deserialize_tick_array(
@@ -87,76 +88,93 @@ pub fn uninitialized_tick_array(start_tick_index: i32) -> TickArray {
     }
 }
 
-pub async fn fetch_tick_arrays_or_default(
-    rpc: Arc<RpcClient>,
-    whirlpool_address: Pubkey,
-    whirlpool: &Whirlpool,
-) -> Result<[(Pubkey, TickArray); 5]> {
+// distance of each quote slot from the current tick array: current, two above, two below
+const TICK_ARRAY_SLOT_DISTANCES: [usize; 5] = [0, 1, 2, 1, 2];
+
+/// Start tick indexes of the five tick arrays the quote takes, in slot order.
+pub fn tick_array_start_indexes(whirlpool: &Whirlpool) -> [i32; 5] {
     let tick_array_start_index =
         get_tick_array_start_tick_index(whirlpool.tick_current_index, whirlpool.tick_spacing);
     let offset = whirlpool.tick_spacing as i32 * TICK_ARRAY_SIZE as i32;
 
-    let tick_array_indexes = [
+    [
         tick_array_start_index,
         tick_array_start_index + offset,
         tick_array_start_index + offset * 2,
         tick_array_start_index - offset,
         tick_array_start_index - offset * 2,
-    ];
+    ]
+}
 
-    let tick_array_addresses: Vec<Pubkey> = tick_array_indexes
-        .iter()
-        .map(|&x| get_tick_array_address(&whirlpool_address, x).map(|y| y.0))
-        .collect::<Result<Vec<Pubkey>, _>>()?;
+/// Whether quote slot `slot` is within `tick_arrays` arrays of the current one in its direction.
+pub fn is_loaded_slot(slot: usize, tick_arrays: usize) -> bool {
+    TICK_ARRAY_SLOT_DISTANCES[slot] < tick_arrays
+}
 
-    let tick_array_infos = rpc.get_multiple_accounts(&tick_array_addresses).await?;
-    let maybe_tick_arrays: Vec<Option<TickArray>> = tick_array_infos
-        .iter()
-        .map(|account_option| {
-            account_option
-                .as_ref()
-                .and_then(|account| TickArray::deserialize(&account.data).ok())
-        })
-        .collect();
+/// Addresses of all five quote slots, loaded or not.
+pub fn tick_array_addresses(
+    whirlpool_address: Pubkey,
+    whirlpool: &Whirlpool,
+) -> Result<[Pubkey; 5]> {
+    let tick_array_indexes = tick_array_start_indexes(whirlpool);
+    let mut tick_array_addresses = [Pubkey::default(); 5];
+    for (address, start_index) in tick_array_addresses.iter_mut().zip(tick_array_indexes) {
+        *address = get_tick_array_address(&whirlpool_address, start_index)?.0;
+    }
+
+    Ok(tick_array_addresses)
+}
+
+/// Fetch the `tick_arrays` nearest arrays in each direction. Slots beyond that, or not
+/// initialized on chain, quote as uninitialized arrays.
+pub async fn fetch_tick_arrays_or_default(
+    rpc: Arc<RpcClient>,
+    whirlpool_address: Pubkey,
+    whirlpool: &Whirlpool,
+    tick_arrays: usize,
+) -> Result<[(Pubkey, TickArray); 5]> {
+    let tick_array_indexes = tick_array_start_indexes(whirlpool);
+    let tick_array_addresses = tick_array_addresses(whirlpool_address, whirlpool)?;
 
-    let tick_arrays: Vec<TickArray> = maybe_tick_arrays
+    let loaded_slots: Vec<usize> = (0..tick_array_addresses.len())
+        .filter(|&slot| is_loaded_slot(slot, tick_arrays))
+        .collect();
+    let loaded_addresses: Vec<Pubkey> = loaded_slots
         .iter()
-        .enumerate()
-        .map(|(i, x)| {
-            x.clone()
-                .unwrap_or(uninitialized_tick_array(tick_array_indexes[i]))
-        })
-        .collect::<Vec<TickArray>>();
-
-    let result: [(Pubkey, TickArray); 5] = zip(tick_array_addresses, tick_arrays)
-        .collect::<Vec<(Pubkey, TickArray)>>()
-        .try_into()
-        .map_err(|_| "Failed to convert tick arrays to array".to_string())
-        .unwrap();
+        .map(|&slot| tick_array_addresses[slot])
+        .collect();
+    let tick_array_infos = rpc.get_multiple_accounts(&loaded_addresses).await?;
+
+    let mut result: [(Pubkey, TickArray); 5] = std::array::from_fn(|i| {
+        (
+            tick_array_addresses[i],
+            uninitialized_tick_array(tick_array_indexes[i]),
+        )
+    });
+    for (slot, account_option) in zip(loaded_slots, tick_array_infos) {
+        if let Some(tick_array) = account_option
+            .as_ref()
+            .and_then(|account| TickArray::deserialize(&account.data).ok())
+        {
+            result[slot].1 = tick_array;
+        }
+    }
 
     Ok(result)
 }
 
+/// Addresses of the `tick_arrays` nearest arrays in each direction, the ones worth subscribing to.
 pub fn get_tick_arrays_or_default(
     whirlpool_address: Pubkey,
     whirlpool: &Whirlpool,
+    tick_arrays: usize,
 ) -> Result<Vec<Pubkey>> {
-    let tick_array_start_index =
-        get_tick_array_start_tick_index(whirlpool.tick_current_index, whirlpool.tick_spacing);
-    let offset = whirlpool.tick_spacing as i32 * TICK_ARRAY_SIZE as i32;
+    let tick_array_addresses = tick_array_addresses(whirlpool_address, whirlpool)?;
 
-    let tick_array_indexes = [
-        tick_array_start_index,
-        tick_array_start_index + offset,
-        tick_array_start_index + offset * 2,
-        tick_array_start_index - offset,
-        tick_array_start_index - offset * 2,
-    ];
-
-    let tick_array_addresses: Vec<Pubkey> = tick_array_indexes
-        .iter()
-        .map(|&x| get_tick_array_address(&whirlpool_address, x).map(|y| y.0))
-        .collect::<Result<Vec<Pubkey>, _>>()?;
-
-    Ok(tick_array_addresses)
+    Ok(tick_array_addresses
+        .into_iter()
+        .enumerate()
+        .filter(|(slot, _)| is_loaded_slot(*slot, tick_arrays))
+        .map(|(_, address)| address)
+        .collect())
 }
@@ -1,43 +1,40 @@
 use crate::{
-    config::{Config, Watcher, read_config},
+    config::{Config, ReloadReport, Watcher, read_config},
     io,
 };
 use anchor_client::{
-    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_client::{
+        http_sender::HttpSender, nonblocking::rpc_client::RpcClient, rpc_client::RpcClientConfig,
+    },
     solana_sdk::{
         commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Keypair, signer::Signer,
     },
 };
 use anyhow::Result;
+use arc_swap::{ArcSwap, Guard};
 use std::{
+    collections::{HashMap, HashSet},
     path::Path,
     str::FromStr,
     sync::{
         Arc, OnceLock,
         atomic::{AtomicU64, Ordering},
     },
+    time::Duration,
 };
 
-#[cfg(feature = "devnet")]
-lazy_static::lazy_static! {
-    static ref CONFIG:Config = read_config("config_dev.toml").unwrap();
-    static ref RPC: Arc<RpcClient> = Arc::new(
-        RpcClient::new_with_commitment(
-            CONFIG.rpc.url.to_string(),
-            CommitmentConfig::processed()
-        )
-    );
-}
+#[cfg(all(feature = "devnet", not(test)))]
+const CONFIG_PATH: &str = "config_dev.toml";
+#[cfg(all(not(feature = "devnet"), not(test)))]
+const CONFIG_PATH: &str = "config.toml";
+// unit tests reaching the config through the getters read the documented defaults
+#[cfg(test)]
+const CONFIG_PATH: &str = "config.toml.example";
 
-#[cfg(not(feature = "devnet"))]
 lazy_static::lazy_static! {
-    static ref CONFIG: Config = read_config("config.toml").unwrap();
-    static ref RPC: Arc<RpcClient> = Arc::new(
-        RpcClient::new_with_commitment(
-            CONFIG.rpc.url.to_string(),
-            CommitmentConfig::processed()
-        )
-    );
+    // swapped whole by `reload_config`, readers keep the snapshot they loaded
+    static ref CONFIG: ArcSwap<Config> = ArcSwap::from_pointee(read_config(CONFIG_PATH).unwrap());
+    static ref RPC: Arc<RpcClient> = new_rpc(&get_config().rpc.url);
 }
 
 pub const WSOL: Pubkey = Pubkey::new_from_array([
@@ -49,8 +46,28 @@ pub fn get_rpc_client() -> Arc<RpcClient> {
     RPC.clone()
 }
 
-pub fn get_config() -> &'static Config {
-    &CONFIG
+/// The current config, cheap enough to load on hot paths. Keep the guard short-lived, holding
+/// many at once falls back to slower reference counting.
+pub fn get_config() -> Guard<Arc<Config>> {
+    CONFIG.load()
+}
+
+/// Re-read the config file and swap in the settings that are safe to change while running,
+/// see `Config::with_runtime_settings`. The profit floor is picked up too.
+pub fn reload_config() -> Result<ReloadReport> {
+    let new = read_config(CONFIG_PATH)?;
+    let (next, report) = get_config().with_runtime_settings(&new);
+    MINIMUM_PROFIT.store(next.bot.minimum_profit, Ordering::Relaxed);
+    CONFIG.store(Arc::new(next));
+    Ok(report)
+}
+
+/// Swap in a copy of the current config changed by `edit`, for tests of code behind the getters.
+#[cfg(test)]
+pub fn update_config(edit: impl FnOnce(&mut Config)) {
+    let mut next = Config::clone(&get_config());
+    edit(&mut next);
+    CONFIG.store(Arc::new(next));
 }
 
 pub fn only_watch_succeed_tx() -> bool {
@@ -65,12 +82,38 @@ pub fn only_watch_failed_tx() -> bool {
     watcher.only_failed
 }
 
+pub fn get_max_pool_queue() -> usize {
+    get_config().watcher.max_pool_queue
+}
+
+pub fn get_alt_fetch_concurrency() -> usize {
+    get_config().watcher.alt_fetch_concurrency.max(1)
+}
+
+pub fn get_watcher_ping_interval_secs() -> u64 {
+    get_config().watcher.ping_interval_secs
+}
+
+pub fn get_watcher_message_timeout_secs() -> u64 {
+    get_config().watcher.message_timeout_secs
+}
+
 pub fn get_watcher_config() -> Watcher {
     let config = get_config();
     let watcher = config.watcher.clone();
     watcher
 }
 
+/// `watcher.ignore_signers`, checked by `Watcher::validate`.
+pub fn get_ignored_signers() -> HashSet<Pubkey> {
+    get_config()
+        .watcher
+        .ignore_signers
+        .iter()
+        .filter_map(|signer| Pubkey::from_str(signer).ok())
+        .collect()
+}
+
 pub fn enabled_slippage() -> bool {
     let config = get_config();
     let bot = config.bot.clone();
@@ -83,10 +126,281 @@ pub fn get_slippage_bps() -> u64 {
     bot.slippage_bps
 }
 
+pub fn get_max_inflight_arbs() -> usize {
+    get_config().bot.max_inflight_arbs.max(1)
+}
+
+/// How long a queued profitable route stays sendable, `None` when it never expires.
+pub fn get_opportunity_ttl() -> Option<Duration> {
+    match get_config().bot.opportunity_ttl_ms {
+        0 => None,
+        ttl_ms => Some(Duration::from_millis(ttl_ms)),
+    }
+}
+
+pub fn dedup_first_hop() -> bool {
+    get_config().bot.dedup_first_hop
+}
+
+pub fn auto_wrap_sol() -> bool {
+    get_config().bot.auto_wrap_sol
+}
+
+pub fn get_sol_fee_reserve_lamports() -> u64 {
+    get_config().bot.sol_fee_reserve_lamports
+}
+
+pub fn get_balance_refresh_secs() -> u64 {
+    get_config().bot.balance_refresh_secs
+}
+
+pub fn dry_run() -> bool {
+    get_config().bot.dry_run
+}
+
+pub fn get_route_cooldown_ms() -> u64 {
+    get_config().bot.route_cooldown_ms
+}
+
+pub fn get_max_tx_accounts() -> usize {
+    get_config().bot.max_tx_accounts
+}
+
+pub fn track_confirmations() -> bool {
+    get_config().bot.track_confirmations
+}
+
+pub fn get_solfi_min_reserve() -> u64 {
+    get_config().bot.solfi_min_reserve
+}
+
+pub fn skip_pre_activation() -> bool {
+    get_config().bot.skip_pre_activation
+}
+
+/// `fees.<dex>` in bps, quoted instead of the fee derived from that DEX's pool state.
+pub fn get_fee_override_bps(dex: &str) -> Option<u64> {
+    get_config().fees.get(dex).copied()
+}
+
+pub fn damm_has_referral() -> bool {
+    get_config().bot.damm_has_referral
+}
+
+pub fn cleanup_temp_atas() -> bool {
+    get_config().bot.cleanup_temp_atas
+}
+
+pub fn get_temp_ata_cleanup_secs() -> u64 {
+    get_config().bot.temp_ata_cleanup_secs
+}
+
+pub fn get_revert_blacklist_threshold() -> u32 {
+    get_config().bot.revert_blacklist_threshold
+}
+
+pub fn get_revert_blacklist_window_secs() -> u64 {
+    get_config().bot.revert_blacklist_window_secs
+}
+
+pub fn get_revert_blacklist_cooldown_secs() -> u64 {
+    get_config().bot.revert_blacklist_cooldown_secs
+}
+
+pub fn get_pair_failure_threshold() -> u32 {
+    get_config().bot.pair_failure_threshold
+}
+
+pub fn get_pair_cooldown_secs() -> u64 {
+    get_config().bot.pair_cooldown_secs
+}
+
+pub fn get_max_optimizer_iters() -> usize {
+    get_config().bot.max_optimizer_iters
+}
+
+pub fn get_route_eval_threads() -> usize {
+    get_config().bot.route_eval_threads
+}
+
+pub fn get_finding_interval_ms() -> u64 {
+    get_config().bot.finding_interval_ms
+}
+
+pub fn get_pool_loader_concurrency() -> usize {
+    get_config().bot.pool_loader_concurrency.max(1)
+}
+
+pub fn get_typical_fee_bps() -> u64 {
+    get_config().bot.typical_fee_bps
+}
+
+pub fn get_price_threshold() -> f64 {
+    get_config().bot.price_threshold
+}
+
+/// `bot.disabled_dexes`, named as `TokenPoolType::dex_name` returns them.
+pub fn get_disabled_dexes() -> HashSet<String> {
+    get_config().bot.disabled_dexes.iter().cloned().collect()
+}
+
+pub fn get_max_cu_price_micro_lamports() -> u64 {
+    get_config().bot.max_cu_price_micro_lamports
+}
+
+pub fn include_cu_limit() -> bool {
+    get_config().bot.include_cu_limit
+}
+
+pub fn simulate_before_send() -> bool {
+    get_config().bot.simulate_before_send
+}
+
+pub fn get_simulate_min_profit() -> u64 {
+    get_config().bot.simulate_min_profit
+}
+
+pub fn get_simulate_tolerance_bps() -> u64 {
+    get_config().bot.simulate_tolerance_bps
+}
+
+pub fn get_max_clock_lag_slots() -> u64 {
+    get_config().bot.max_clock_lag_slots
+}
+
+pub fn get_alt_ttl_secs() -> u64 {
+    get_config().bot.alt_ttl_secs
+}
+
+pub fn get_static_alts() -> Result<Vec<Pubkey>> {
+    get_config()
+        .bot
+        .static_alts
+        .iter()
+        .map(|alt| Pubkey::from_str(alt).map_err(|_| anyhow::anyhow!("Invalid static ALT {}", alt)))
+        .collect()
+}
+
+/// `bot.pinned_pools` as (pool address, DEX name) pairs.
+pub fn get_pinned_pools() -> Result<Vec<(Pubkey, String)>> {
+    get_config()
+        .bot
+        .pinned_pools
+        .iter()
+        .map(|pool| {
+            Pubkey::from_str(&pool.address)
+                .map(|address| (address, pool.dex.clone()))
+                .map_err(|_| anyhow::anyhow!("Invalid pinned pool {}", pool.address))
+        })
+        .collect()
+}
+
+/// `mints.decimals` by mint, the keys are checked when the config is parsed.
+pub fn get_configured_mint_decimals() -> HashMap<Pubkey, u8> {
+    get_config()
+        .mints
+        .decimals
+        .iter()
+        .filter_map(|(mint, decimals)| Some((Pubkey::from_str(mint).ok()?, *decimals)))
+        .collect()
+}
+
+/// `bot.mint` followed by any extra `bot.base_mints`, without duplicates.
+pub fn get_base_mints() -> Result<Vec<Pubkey>> {
+    let config = get_config();
+    let bot = &config.bot;
+    let mut mints: Vec<Pubkey> = Vec::with_capacity(bot.base_mints.len() + 1);
+    for mint in std::iter::once(&bot.mint).chain(bot.base_mints.iter()) {
+        let mint =
+            Pubkey::from_str(mint).map_err(|_| anyhow::anyhow!("Invalid base mint {}", mint))?;
+        if !mints.contains(&mint) {
+            mints.push(mint);
+        }
+    }
+    Ok(mints)
+}
+
+/// `bot.accounting_mint`, the base mint when unset.
+pub fn get_accounting_mint() -> Result<Pubkey> {
+    match &get_config().bot.accounting_mint {
+        Some(mint) => {
+            Pubkey::from_str(mint).map_err(|_| anyhow::anyhow!("Invalid accounting mint {}", mint))
+        }
+        None => Ok(*get_base_mint()),
+    }
+}
+
+pub fn auto_create_base_ata() -> bool {
+    get_config().bot.auto_create_base_ata
+}
+
+pub fn get_price_socket_path() -> Option<String> {
+    get_config().price.socket_path.clone()
+}
+
+pub fn get_alert_webhook_url() -> Option<String> {
+    get_config().alerts.webhook_url.clone()
+}
+
+pub fn get_alert_cooldown_secs() -> u64 {
+    get_config().alerts.cooldown_secs
+}
+
+pub fn get_whirlpool_tick_arrays() -> usize {
+    get_config().whirlpool.tick_arrays
+}
+
+pub fn get_clmm_tick_arrays() -> usize {
+    get_config().clmm.tick_arrays
+}
+
+pub fn clmm_check_swap_state() -> bool {
+    get_config().clmm.check_swap_state
+}
+
+/// `(nonce account, authority)` from `[nonce]`, `None` when submissions use a recent blockhash.
+pub fn get_nonce_account() -> Result<Option<(Pubkey, Pubkey)>> {
+    let config = get_config();
+    let nonce_config = &config.nonce;
+    let Some(account) = &nonce_config.account else {
+        return Ok(None);
+    };
+
+    let account = Pubkey::from_str(account)
+        .map_err(|_| anyhow::anyhow!("Invalid nonce account {}", account))?;
+    let authority = match &nonce_config.authority {
+        Some(authority) => Pubkey::from_str(authority)
+            .map_err(|_| anyhow::anyhow!("Invalid nonce authority {}", authority))?,
+        None => get_pubkey(),
+    };
+    if authority != get_pubkey() {
+        return Err(anyhow::anyhow!(
+            "Nonce authority {} is not the wallet, it can't sign the advance",
+            authority
+        ));
+    }
+
+    Ok(Some((account, authority)))
+}
+
 pub fn new_rpc(rpc_endpoint: &str) -> Arc<RpcClient> {
-    Arc::new(RpcClient::new_with_commitment(
-        rpc_endpoint.to_string(),
-        CommitmentConfig::processed(),
+    new_rpc_with_commitment(rpc_endpoint, CommitmentConfig::processed())
+}
+
+/// Every RPC client goes through here so requests are bounded by `rpc.request_timeout_ms`
+/// and `rpc.connect_timeout_ms`.
+pub fn new_rpc_with_commitment(rpc_endpoint: &str, commitment: CommitmentConfig) -> Arc<RpcClient> {
+    let config = get_config();
+    let rpc_config = &config.rpc;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(rpc_config.request_timeout_ms))
+        .connect_timeout(Duration::from_millis(rpc_config.connect_timeout_ms))
+        .build()
+        .expect("Failed to build RPC http client");
+
+    Arc::new(RpcClient::new_sender(
+        HttpSender::new_with_client(rpc_endpoint, client),
+        RpcClientConfig::with_commitment(commitment),
     ))
 }
 
@@ -94,6 +408,8 @@ static GLOBAL_KEYPAIR: OnceLock<Arc<Keypair>> = OnceLock::new();
 static GLOBAL_PAYER: OnceLock<Arc<Keypair>> = OnceLock::new();
 static BASE_MINT: OnceLock<Arc<Pubkey>> = OnceLock::new();
 static MINT_ATA_AMOUNT: AtomicU64 = AtomicU64::new(0);
+// native lamports of the wallet, only kept fresh when wSOL routes wrap them
+static NATIVE_LAMPORTS: AtomicU64 = AtomicU64::new(0);
 static MINIMUM_PROFIT: AtomicU64 = AtomicU64::new(1000);
 
 #[inline]
@@ -101,6 +417,26 @@ pub fn get_base_mint_amount() -> u64 {
     MINT_ATA_AMOUNT.load(Ordering::Relaxed)
 }
 
+#[inline]
+pub fn get_native_lamports() -> u64 {
+    NATIVE_LAMPORTS.load(Ordering::Relaxed)
+}
+
+#[inline]
+pub fn set_native_lamports(lamports: u64) {
+    NATIVE_LAMPORTS.store(lamports, Ordering::Relaxed);
+}
+
+/// Whether wSOL routes wrap native SOL, which then counts as capital too.
+pub fn wraps_native_sol() -> bool {
+    auto_wrap_sol() && *get_base_mint() == WSOL
+}
+
+#[inline]
+pub fn set_base_mint_amount(amount: u64) {
+    MINT_ATA_AMOUNT.store(amount, Ordering::Relaxed);
+}
+
 #[inline]
 pub fn get_base_mint() -> Arc<Pubkey> {
     BASE_MINT.get().expect("BASE_MINT not initialized").clone()
@@ -147,28 +483,52 @@ fn load_keypair_with_fallback(wallet_path: Option<&str>) -> Arc<Keypair> {
     }
 }
 
+/// Wallet keypair from `wallet.keypair_env` if that variable is set, else from a keypair file.
+fn load_wallet(wallet_path: Option<&str>) -> Result<Keypair> {
+    let config = get_config();
+    let wallet = &config.wallet;
+    if let Some(env_name) = &wallet.keypair_env {
+        if let std::result::Result::Ok(secret) = std::env::var(env_name) {
+            println!("Load wallet from env {}", env_name);
+            return io::keypair_from_bs58(&secret)
+                .map_err(|_| anyhow::anyhow!("Invalid secret key in env {}", env_name));
+        }
+    }
+
+    let real_path = wallet_path
+        .or(wallet.keypair_path.as_deref())
+        .unwrap_or("./wallet.json");
+    println!("Load wallet from {}", real_path);
+    io::load_keypair(real_path)
+        .map_err(|_| anyhow::anyhow!("Failed to load wallet keypair from {}", real_path))
+}
+
 pub async fn prepare_data(wallet_path: Option<&str>, mint_str: &str) -> Result<()> {
     let mint = Pubkey::from_str(mint_str)?;
     BASE_MINT
         .set(Arc::new(mint))
         .map_err(|_| anyhow::anyhow!("Base mint already initialized"))?;
-    let real_path = match wallet_path {
-        Some(val) => val,
-        None => "./wallet.json",
-    };
-    println!("Load wallet from {}", real_path);
-    let payer = Arc::new(io::load_keypair(real_path).unwrap());
+    get_accounting_mint()?;
+    let payer = Arc::new(load_wallet(wallet_path)?);
     GLOBAL_KEYPAIR
         .set(payer)
         .map_err(|_| anyhow::anyhow!("Global keypair already initialized"))?;
-    let amount = crate::onchain::get_ata_token_amount(&get_pubkey(), &mint).await?;
-    MINT_ATA_AMOUNT.store(amount, Ordering::Relaxed);
-    MINIMUM_PROFIT.store(CONFIG.bot.minimum_profit, Ordering::Relaxed);
-
+    MINIMUM_PROFIT.store(get_config().bot.minimum_profit, Ordering::Relaxed);
     let payer = load_keypair_with_fallback(Some("./payer"));
     GLOBAL_PAYER
         .set(payer)
         .map_err(|_| anyhow::anyhow!("Global GLOBAL_PAYER already initialized"))?;
 
+    // everything below goes over RPC
+    if auto_create_base_ata() {
+        crate::onchain::create_missing_atas(&get_base_mints()?).await?;
+    }
+    let amount = crate::onchain::get_ata_token_amount(&get_pubkey(), &mint).await?;
+    MINT_ATA_AMOUNT.store(amount, Ordering::Relaxed);
+    if wraps_native_sol() {
+        let lamports = get_rpc_client().get_balance(&get_pubkey()).await?;
+        NATIVE_LAMPORTS.store(lamports, Ordering::Relaxed);
+    }
+
     Ok(())
 }
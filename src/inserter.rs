@@ -1,5 +1,6 @@
 use crate::{
     global,
+    instructions::transfer_hook,
     pool_index::{TokenPool, add_pool},
     dex::raydium,
     streaming::{self, AccountDataType, AccountTypeInfo, global_data},
@@ -107,6 +108,15 @@ pub async fn add(token_pool: TokenPool, pool_data: AccountDataType) -> Result<Ve
     Ok(vec![])
 }
 
+/// Index a pool without the liquidity checks discovered pools go through.
+pub async fn add_pinned(token_pool: TokenPool, pool_data: AccountDataType) -> Result<Vec<Pubkey>> {
+    if add_pool(token_pool.clone()) {
+        return insert_pool_info(&token_pool, pool_data).await;
+    }
+
+    Ok(vec![])
+}
+
 async fn insert_pool_info(
     token_pool: &TokenPool,
     pool_data: AccountDataType,
@@ -198,31 +208,48 @@ async fn insert_pool_info(
                     .unwrap()
                     .0;
 
-            let bitmap_state =
-                raydium::clmm::util::fetch_bitmap_extension_state(rpc_client.clone(), &bitmap_ext)
-                    .await?;
-            let left_ticks = raydium::clmm::swap_util::get_cur_and_next_five_tick_array(
+            // the extension is only needed near the default bitmap boundary, it's loaded on
+            // demand if the pool moves there later
+            let bitmap_state = match raydium::clmm::util::fetch_bitmap_extension_state(
+                rpc_client.clone(),
+                &bitmap_ext,
+            )
+            .await
+            {
+                std::result::Result::Ok(bitmap_state) => {
+                    vec_keys.push(bitmap_ext);
+                    global_data::add_accounts(
+                        bitmap_ext,
+                        AccountDataType::RaydiumTickArrayBitmapExt(bitmap_state),
+                        AccountTypeInfo::RaydiumTickArrayBitmapExt,
+                    );
+                    bitmap_state
+                }
+                Err(_) if !streaming::near_bitmap_boundary(&pool_state) => {
+                    raydium::clmm::tick_array_bitmap_extension::TickArrayBitmapExtension::empty(
+                        token_pool.pool,
+                    )
+                }
+                Err(e) => return Err(e),
+            };
+            let left_ticks = raydium::clmm::swap_util::get_cur_and_next_tick_arrays(
                 token_pool.pool,
                 &pool_state,
                 &bitmap_state,
                 false,
+                global::get_clmm_tick_arrays(),
             );
-            let right_ticks = raydium::clmm::swap_util::get_cur_and_next_five_tick_array(
+            let right_ticks = raydium::clmm::swap_util::get_cur_and_next_tick_arrays(
                 token_pool.pool,
                 &pool_state,
                 &bitmap_state,
                 true,
+                global::get_clmm_tick_arrays(),
             );
             let ticks = streaming::util::merge(&[&left_ticks, &right_ticks]);
 
-            vec_keys.push(bitmap_ext);
             vec_keys.extend(&ticks);
             global_data::add_accounts_type(&ticks, AccountTypeInfo::RaydiumTickArrayState);
-            global_data::add_accounts(
-                bitmap_ext,
-                AccountDataType::RaydiumTickArrayBitmapExt(bitmap_state),
-                AccountTypeInfo::RaydiumTickArrayBitmapExt,
-            );
             global_data::add_accounts(
                 token_pool.pool,
                 account_data,
@@ -233,12 +260,17 @@ async fn insert_pool_info(
             let oracle_address = whirlpool::state::pda::derive_oracle_address(&token_pool.pool)
                 .unwrap()
                 .0;
-            let tick_data =
-                whirlpool::util::get_tick_arrays_or_default(token_pool.pool, &pool_state).unwrap();
+            let tick_data = whirlpool::util::get_tick_arrays_or_default(
+                token_pool.pool,
+                &pool_state,
+                global::get_whirlpool_tick_arrays(),
+            )
+            .unwrap();
             vec_keys.push(oracle_address);
             global_data::add_accounts_type(&tick_data, AccountTypeInfo::WhirlpoolTickArray);
             vec_keys.extend(&tick_data);
             global_data::add_account_type(oracle_address, AccountTypeInfo::WhirlpoolOracle);
+            transfer_hook::load(rpc_client.clone(), &[token_pool.mint_a, token_pool.mint_b]).await;
             global_data::add_accounts(token_pool.pool, account_data, AccountTypeInfo::Whirlpool);
         }
         AccountDataType::VertigoPool(_) => {
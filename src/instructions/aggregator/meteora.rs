@@ -3,7 +3,8 @@ use crate::{
     arb::{MeteoraDammv2Data, MeteoraDlmmData},
     instructions::util::bins_to_remaining_accounts,
     dex::meteora,
-    onchain::get_associated_token_address,
+    global,
+    onchain::get_user_token_address,
     token_program,
 };
 use anchor_client::solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
@@ -14,8 +15,8 @@ pub fn build_dlmm_accounts(
     data: &MeteoraDlmmData,
     current_account_in: &Pubkey,
 ) -> (u8, Vec<AccountMeta>, Pubkey) {
-    let token_x_account = get_associated_token_address(payer, &data.lb_pair.token_x_mint);
-    let token_y_account = get_associated_token_address(payer, &data.lb_pair.token_y_mint);
+    let token_x_account = get_user_token_address(payer, &data.lb_pair.token_x_mint);
+    let token_y_account = get_user_token_address(payer, &data.lb_pair.token_y_mint);
 
     let (token_in_account, token_out_account) = if current_account_in == &token_x_account {
         (token_x_account, token_y_account)
@@ -54,8 +55,8 @@ pub fn build_damm_accounts(
 ) -> (u8, Vec<AccountMeta>, Pubkey) {
     let (pool_authority, _) = meteora::damm::DammV2PDA::get_pool_authority().unwrap();
     let (event_authority, _) = meteora::damm::DammV2PDA::get_event_authority().unwrap();
-    let token_x_account = get_associated_token_address(payer, &data.pool_state.token_a_mint);
-    let token_y_account = get_associated_token_address(payer, &data.pool_state.token_b_mint);
+    let token_x_account = get_user_token_address(payer, &data.pool_state.token_a_mint);
+    let token_y_account = get_user_token_address(payer, &data.pool_state.token_b_mint);
 
     let (token_in_account, token_out_account) = if current_account_in == &token_x_account {
         (token_x_account, token_y_account)
@@ -63,12 +64,19 @@ pub fn build_damm_accounts(
         (token_y_account, token_x_account)
     };
 
+    // the program id stands in for "no referral"
+    let referral_account = if global::damm_has_referral() {
+        damm_referral_account(data, token_in_account == token_x_account, payer)
+    } else {
+        meteora::damm::program_id()
+    };
+
     let accounts = vec![
         AccountMeta::new_readonly(meteora::damm::program_id(), false),
         AccountMeta::new_readonly(pool_authority, false),
         AccountMeta::new(pool_address, false),
         AccountMeta::new_readonly(event_authority, false),
-        AccountMeta::new(meteora::damm::program_id(), false),
+        AccountMeta::new(referral_account, false),
         AccountMeta::new(token_in_account, false),
         AccountMeta::new(token_out_account, false),
         AccountMeta::new(data.pool_state.token_a_vault, false),
@@ -81,3 +89,69 @@ pub fn build_damm_accounts(
 
     (METEORA_DAMM_ID, accounts, token_out_account)
 }
+
+/// The referral fee is paid in the token the pool collects fees in, so the referral account is
+/// the wallet's own ATA of that mint, which the route already uses as input or output.
+fn damm_referral_account(data: &MeteoraDammv2Data, a_to_b: bool, owner: &Pubkey) -> Pubkey {
+    let trade_direction = if a_to_b {
+        meteora::damm::TradeDirection::AtoB
+    } else {
+        meteora::damm::TradeDirection::BtoA
+    };
+    match meteora::damm::FeeMode::get_fee_mode(
+        data.pool_state.collect_fee_mode,
+        trade_direction,
+        true,
+    ) {
+        Ok(fee_mode) if fee_mode.fees_on_token_a => {
+            get_user_token_address(owner, &data.pool_state.token_a_mint)
+        }
+        Ok(_) => get_user_token_address(owner, &data.pool_state.token_b_mint),
+        Err(_) => meteora::damm::program_id(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn damm_pool(collect_fee_mode: u8) -> MeteoraDammv2Data {
+        let mut data = vec![0u8; meteora::damm::POOL_ACCOUNT_LEN];
+        data[0..8].copy_from_slice(&meteora::damm::POOL_DISCRIMINATOR);
+        // token_a_mint and token_b_mint follow the 160 byte fee section
+        data[8 + 160..8 + 192].copy_from_slice(Pubkey::new_unique().as_ref());
+        data[8 + 192..8 + 224].copy_from_slice(Pubkey::new_unique().as_ref());
+        let mut pool_state = meteora::damm::Pool::deserialize(&data).unwrap();
+        pool_state.collect_fee_mode = collect_fee_mode;
+
+        MeteoraDammv2Data {
+            pool_address: Pubkey::new_unique(),
+            pool_state,
+        }
+    }
+
+    #[test]
+    fn referral_account_holds_the_fee_token() {
+        let owner = Pubkey::new_unique();
+        let ata = |data: &MeteoraDammv2Data, a_to_b| {
+            let mints = (data.pool_state.token_a_mint, data.pool_state.token_b_mint);
+            let referral = damm_referral_account(data, a_to_b, &owner);
+            if referral == get_user_token_address(&owner, &mints.0) {
+                "a"
+            } else if referral == get_user_token_address(&owner, &mints.1) {
+                "b"
+            } else {
+                "none"
+            }
+        };
+
+        // both tokens: the fee is taken from the output
+        let both = damm_pool(0);
+        assert_eq!(ata(&both, true), "b");
+        assert_eq!(ata(&both, false), "a");
+        // only b: the fee is always in token b, input or output
+        let only_b = damm_pool(1);
+        assert_eq!(ata(&only_b, true), "b");
+        assert_eq!(ata(&only_b, false), "b");
+    }
+}
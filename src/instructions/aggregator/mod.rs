@@ -1,14 +1,16 @@
 use crate::{
     arb::{PoolType, SwapRoutes},
-    associated_token_program, global,
-    onchain::get_associated_token_address,
+    associated_token_program,
+    instructions::{token, transfer_hook},
+    onchain::{self, get_user_token_address},
     system_program,
 };
 use anchor_client::solana_sdk::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
 };
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use std::collections::HashSet;
 use std::str::FromStr;
 
 mod constants;
@@ -31,9 +33,22 @@ pub fn program_id() -> Pubkey {
     Pubkey::from_str(PROGRAM_ID).unwrap()
 }
 
-pub fn route(swap: SwapRoutes, fee: u64) -> Result<Instruction> {
-    let payer = global::get_pubkey();
-    let user_base_account = get_associated_token_address(&payer, &swap.mint);
+pub struct RouteInstructions {
+    // idempotent ATA creations for intermediate accounts that may not exist yet
+    pub setup: Vec<Instruction>,
+    pub swap: Instruction,
+    // dex of each leg and how many accounts it passes to the swap
+    pub leg_accounts: Vec<(String, usize)>,
+}
+
+pub fn route(
+    payer: &Pubkey,
+    swap: SwapRoutes,
+    fee: u64,
+    known_atas: &HashSet<Pubkey>,
+) -> Result<RouteInstructions> {
+    let payer = *payer;
+    let user_base_account = get_user_token_address(&payer, &swap.mint);
     let mut accounts: Vec<AccountMeta> = vec![
         AccountMeta::new(payer, true),
         AccountMeta::new(user_base_account, false),
@@ -44,9 +59,37 @@ pub fn route(swap: SwapRoutes, fee: u64) -> Result<Instruction> {
     let mut routes: Vec<u8> = Vec::with_capacity(swap.routes.len() * 2);
     let mut remaining_accounts: Vec<AccountMeta> = Vec::new();
     let mut current_account_in = user_base_account;
+    let mut current_mint = swap.mint;
+    let mut resolved_atas: HashSet<Pubkey> = HashSet::with_capacity(swap.routes.len());
+    resolved_atas.insert(user_base_account);
+    let mut setup: Vec<Instruction> = Vec::new();
+    let mut leg_accounts: Vec<(String, usize)> = Vec::with_capacity(swap.routes.len());
 
     for route in swap.routes {
-        let (dex_id, route_accounts, token_out_account) = match route {
+        let (mint_a, mint_b) = route.get_mints();
+        if current_mint != mint_a && current_mint != mint_b {
+            return Err(anyhow!(
+                "Pool {} doesn't trade {}, the route is malformed",
+                route.get_address(),
+                current_mint
+            ));
+        }
+        let mint_out = route.get_other_mint(&current_mint);
+        let dex = format!("{:?}", route.to_pool_type());
+
+        // Token-2022 transfer hooks need their extra accounts on the leg, only Whirlpool
+        // forwards them to the transfer, the other DEXes would fail on-chain
+        let mut hook_accounts = transfer_hook::hook_accounts(&mint_a)?;
+        hook_accounts.extend(transfer_hook::hook_accounts(&mint_b)?);
+        if !hook_accounts.is_empty() && !matches!(route, PoolType::Whirlpool(..)) {
+            return Err(anyhow!(
+                "Pool {} ({}) can't pass transfer hook accounts",
+                route.get_address(),
+                dex
+            ));
+        }
+
+        let (dex_id, mut route_accounts, token_out_account) = match route {
             PoolType::Pump(address, data) => {
                 build_pump_accounts(&payer, address, &data, &current_account_in)
             }
@@ -75,20 +118,35 @@ pub fn route(swap: SwapRoutes, fee: u64) -> Result<Instruction> {
                 build_solfi_accounts(&payer, address, &data, &current_account_in)
             }
         };
+        route_accounts.extend(hook_accounts);
 
         // Add route metadata
         routes.push(dex_id);
         routes.push(route_accounts.len() as u8);
+        leg_accounts.push((dex, route_accounts.len()));
         remaining_accounts.extend(route_accounts);
 
+        // Intermediate mints repeated across hops reuse the same ATA, only create it once
+        if !known_atas.contains(&token_out_account) && resolved_atas.insert(token_out_account) {
+            setup.push(token::create_ata_idempotent_instruction_with_program(
+                &payer,
+                &payer,
+                &mint_out,
+                &onchain::mint_token_program(&mint_out),
+            ));
+            onchain::temp_ata::record_created(token_out_account, mint_out);
+        }
+
         // Update input account for next route
         current_account_in = token_out_account;
+        current_mint = mint_out;
     }
 
     accounts.extend(remaining_accounts);
 
     let amount_in: u64 = swap.amount_in as u64;
-    let threshold: u64 = swap.threshold + 1_000_000;
+    // the optimizer's minimum, so the program enforces exactly what was simulated
+    let threshold: u64 = swap.min_amount_out;
 
     // Build instruction data
     let mut data = ROUTE_DISCRIMINATOR.to_vec();
@@ -104,5 +162,111 @@ pub fn route(swap: SwapRoutes, fee: u64) -> Result<Instruction> {
         data,
     };
 
-    Ok(instruction)
+    Ok(RouteInstructions {
+        setup,
+        swap: instruction,
+        leg_accounts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arb::{SolfiData, optimization},
+        dex::solfi as solfi_dex,
+    };
+
+    fn solfi_pool(mint_a: Pubkey, mint_b: Pubkey) -> PoolType {
+        let market = Pubkey::new_unique();
+        let pool_state = solfi_dex::Pool::new(&market, &mint_a, &mint_b);
+        let reserves = solfi_dex::PoolReserves {
+            vault_a_amount: 1_000_000,
+            vault_b_amount: 1_000_000,
+            vault_a: pool_state.vault_a,
+            vault_b: pool_state.vault_b,
+        };
+        PoolType::Solfi(
+            market,
+            SolfiData {
+                pool_address: market,
+                pool_state,
+                reserves,
+            },
+        )
+    }
+
+    #[test]
+    fn route_data_layout() {
+        let (base, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let swap = SwapRoutes {
+            routes: vec![solfi_pool(base, other), solfi_pool(other, base)],
+            profit: 0,
+            amount_in: 0x0102_0304_0506_0708,
+            threshold: 0,
+            min_amount_out: 0x1112_1314_1516_1718,
+            mint: base,
+        };
+        let payer = Pubkey::new_unique();
+        let instructions = route(&payer, swap, 0x2122_2324, &HashSet::new()).unwrap();
+
+        #[rustfmt::skip]
+        let expected: Vec<u8> = vec![
+            // anchor discriminator of `route`
+            229, 23, 203, 151, 122, 227, 173, 42,
+            // length of the route bytes, two per leg
+            4, 0, 0, 0,
+            // dex id and account count of each leg, in route order
+            SOLFI_ID, 8, SOLFI_ID, 8,
+            // amount in
+            0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01,
+            // threshold
+            0x18, 0x17, 0x16, 0x15, 0x14, 0x13, 0x12, 0x11,
+            // fee
+            0x24, 0x23, 0x22, 0x21, 0, 0, 0, 0,
+        ];
+        assert_eq!(instructions.swap.data, expected);
+        assert_eq!(instructions.swap.program_id, program_id());
+
+        // payer, base account and the two programs, then the legs' accounts in order
+        let accounts = &instructions.swap.accounts;
+        assert_eq!(accounts.len(), 4 + 2 * 8);
+        assert_eq!(accounts[0], AccountMeta::new(payer, true));
+        assert_eq!(
+            accounts[1],
+            AccountMeta::new(get_user_token_address(&payer, &base), false)
+        );
+        assert_eq!(accounts[4].pubkey, solfi_dex::program_id());
+        assert_eq!(accounts[12].pubkey, solfi_dex::program_id());
+        assert_eq!(instructions.leg_accounts, vec![("Solfi".to_string(), 8); 2]);
+        // only the intermediate mint's account may need creating
+        assert_eq!(instructions.setup.len(), 1);
+    }
+
+    #[test]
+    fn threshold_is_the_simulated_minimum() {
+        let (amount_in, profit, slippage_bps) = (1_000_000_000, 25_000_000, 50);
+        let min_amount_out = optimization::min_amount_out(amount_in, profit, slippage_bps).unwrap();
+        // 1.025 SOL quoted, 0.5% of it may slip
+        assert_eq!(min_amount_out, 1_019_875_000);
+
+        let swap = SwapRoutes {
+            routes: vec![],
+            profit,
+            amount_in,
+            threshold: 0,
+            min_amount_out,
+            mint: Pubkey::new_unique(),
+        };
+        let data = route(&Pubkey::new_unique(), swap, 7, &HashSet::new())
+            .unwrap()
+            .swap
+            .data;
+
+        // amount in, threshold and fee close the instruction data
+        let tail = &data[data.len() - 24..];
+        assert_eq!(tail[..8], amount_in.to_le_bytes());
+        assert_eq!(tail[8..16], min_amount_out.to_le_bytes());
+        assert_eq!(tail[16..], 7u64.to_le_bytes());
+    }
 }
@@ -1,5 +1,5 @@
 use super::{PUMP_BUY_ID, PUMP_SELL_ID};
-use crate::{arb::PumpAmmData, fee_program, onchain::get_associated_token_address, dex::pumpfun, token_program};
+use crate::{arb::PumpAmmData, fee_program, onchain::get_user_token_address, dex::pumpfun, token_program};
 use anchor_client::solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
 
 pub fn build_pump_accounts(
@@ -9,8 +9,8 @@ pub fn build_pump_accounts(
     current_account_in: &Pubkey,
 ) -> (u8, Vec<AccountMeta>, Pubkey) {
     let pdas = pumpfun::derive_pdas(&data.pool, payer).unwrap();
-    let user_base_account = get_associated_token_address(payer, &data.pool.base_mint);
-    let user_quote_account = get_associated_token_address(payer, &data.pool.quote_mint);
+    let user_base_account = get_user_token_address(payer, &data.pool.base_mint);
+    let user_quote_account = get_user_token_address(payer, &data.pool.quote_mint);
     let (fee_account, _) = pumpfun::protocol_fee_account(&token_program(), &data.pool.quote_mint);
 
     let mut accounts = vec![
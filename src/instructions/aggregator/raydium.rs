@@ -2,7 +2,7 @@ use super::{RAYDIUM_AMM_ID, RAYDIUM_CLMM_ID, RAYDIUM_CPMM_ID};
 use crate::{
     arb::{RaydiumAmmData, RaydiumClmmData, RaydiumCpmmData},
     memo_program,
-    onchain::get_associated_token_address,
+    onchain::get_user_token_address,
     dex::raydium, token_2022_program, token_program,
 };
 use anchor_client::solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
@@ -13,8 +13,8 @@ pub fn build_amm_accounts(
     data: &RaydiumAmmData,
     current_account_in: &Pubkey,
 ) -> (u8, Vec<AccountMeta>, Pubkey) {
-    let token_x_account = get_associated_token_address(payer, &data.pool_state.pc_mint);
-    let token_y_account = get_associated_token_address(payer, &data.pool_state.coin_mint);
+    let token_x_account = get_user_token_address(payer, &data.pool_state.pc_mint);
+    let token_y_account = get_user_token_address(payer, &data.pool_state.coin_mint);
     let (amm_authority, _) = raydium::amm::derive_amm_authority().unwrap();
     let vault_signer = data
         .pool_state
@@ -59,8 +59,8 @@ pub fn build_cpmm_accounts(
     let (authority, _) = raydium::cpmm::pda::derive_authority().unwrap();
     let (observation_state, _) =
         raydium::cpmm::pda::derive_observation_state(&pool_address).unwrap();
-    let token_x_account = get_associated_token_address(payer, &data.pool_state.token_0_mint);
-    let token_y_account = get_associated_token_address(payer, &data.pool_state.token_1_mint);
+    let token_x_account = get_user_token_address(payer, &data.pool_state.token_0_mint);
+    let token_y_account = get_user_token_address(payer, &data.pool_state.token_1_mint);
 
     let (token_in_account, token_out_account, vault_in, vault_out, token_in, token_out) =
         if current_account_in == &token_x_account {
@@ -112,8 +112,8 @@ pub fn build_clmm_accounts(
     //     raydium::clmm::pda::derive_observation_state(&pool_address).unwrap();
     let (bitmap_ext, _) =
         raydium::clmm::pda::derive_tick_array_bitmap_extension(&pool_address).unwrap();
-    let token_x_account = get_associated_token_address(payer, &data.pool_state.token_mint_0);
-    let token_y_account = get_associated_token_address(payer, &data.pool_state.token_mint_1);
+    let token_x_account = get_user_token_address(payer, &data.pool_state.token_mint_0);
+    let token_y_account = get_user_token_address(payer, &data.pool_state.token_mint_1);
     let observation_state = data.pool_state.observation_key;
 
     let (a_to_b, token_in_account, token_out_account, vault_in, vault_out, token_in, token_out) =
@@ -1,5 +1,5 @@
 use super::SOLFI_ID;
-use crate::{arb::SolfiData, onchain::get_associated_token_address, dex::solfi, token_program};
+use crate::{arb::SolfiData, onchain::get_user_token_address, dex::solfi, token_program};
 use anchor_client::solana_sdk::{instruction::AccountMeta, pubkey::Pubkey, sysvar};
 
 pub fn build_solfi_accounts(
@@ -8,8 +8,8 @@ pub fn build_solfi_accounts(
     data: &SolfiData,
     current_account_in: &Pubkey,
 ) -> (u8, Vec<AccountMeta>, Pubkey) {
-    let token_x_account = get_associated_token_address(payer, &data.pool_state.mint_a);
-    let token_y_account = get_associated_token_address(payer, &data.pool_state.mint_b);
+    let token_x_account = get_user_token_address(payer, &data.pool_state.mint_a);
+    let token_y_account = get_user_token_address(payer, &data.pool_state.mint_b);
 
     let accounts = vec![
         AccountMeta::new_readonly(solfi::program_id(), false),
@@ -1,6 +1,6 @@
 use super::{VERTIGO_BUY_ID, VERTIGO_SELL_ID};
 use crate::{
-    arb::VertigoData, memo_program, onchain::get_associated_token_address, token_program, dex::vertigo,
+    arb::VertigoData, memo_program, onchain::get_user_token_address, token_program, dex::vertigo,
 };
 use anchor_client::solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
 
@@ -10,8 +10,8 @@ pub fn build_vertigo_accounts(
     data: &VertigoData,
     current_account_in: &Pubkey,
 ) -> (u8, Vec<AccountMeta>, Pubkey) {
-    let token_x_account = get_associated_token_address(payer, &data.pool_state.mint_a);
-    let token_y_account = get_associated_token_address(payer, &data.pool_state.mint_b);
+    let token_x_account = get_user_token_address(payer, &data.pool_state.mint_a);
+    let token_y_account = get_user_token_address(payer, &data.pool_state.mint_b);
     let (vault_x, _) =
         vertigo::pda::derive_token_vault(&pool_address, &data.pool_state.mint_a).unwrap();
     let (vault_y, _) =
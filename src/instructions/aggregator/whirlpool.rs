@@ -1,5 +1,5 @@
 use super::WHIRLPOOL_ID;
-use crate::{arb::WhirlpoolData, onchain::get_associated_token_address, token_program, dex::whirlpool};
+use crate::{arb::WhirlpoolData, onchain::get_user_token_address, token_program, dex::whirlpool};
 use anchor_client::solana_sdk::{instruction::AccountMeta, pubkey::Pubkey};
 
 pub fn build_whirlpool_accounts(
@@ -8,9 +8,13 @@ pub fn build_whirlpool_accounts(
     data: &WhirlpoolData,
     current_account_in: &Pubkey,
 ) -> (u8, Vec<AccountMeta>, Pubkey) {
-    let token_x_account = get_associated_token_address(payer, &data.pool_state.token_mint_a);
-    let token_y_account = get_associated_token_address(payer, &data.pool_state.token_mint_b);
+    let token_x_account = get_user_token_address(payer, &data.pool_state.token_mint_a);
+    let token_y_account = get_user_token_address(payer, &data.pool_state.token_mint_b);
     let (oracle, _) = whirlpool::state::pda::derive_oracle_address(&pool_address).unwrap();
+    // the swap walks the current tick array and the next two in its direction,
+    // tick_data holds them as [current, above, above, below, below]
+    let a_to_b = current_account_in == &token_x_account;
+    let tick_slots = if a_to_b { [0, 3, 4] } else { [0, 1, 2] };
 
     let accounts = vec![
         AccountMeta::new_readonly(whirlpool::program_id(), false),
@@ -20,13 +24,13 @@ pub fn build_whirlpool_accounts(
         AccountMeta::new(data.pool_state.token_vault_a, false),
         AccountMeta::new(token_y_account, false),
         AccountMeta::new(data.pool_state.token_vault_b, false),
-        AccountMeta::new(data.tick_data[0].0, false),
-        AccountMeta::new(data.tick_data[1].0, false),
-        AccountMeta::new(data.tick_data[2].0, false),
+        AccountMeta::new(data.tick_data[tick_slots[0]].0, false),
+        AccountMeta::new(data.tick_data[tick_slots[1]].0, false),
+        AccountMeta::new(data.tick_data[tick_slots[2]].0, false),
         AccountMeta::new(oracle, false),
     ];
 
-    let token_out_account = if current_account_in == &token_x_account {
+    let token_out_account = if a_to_b {
         token_y_account
     } else {
         token_x_account
@@ -1,7 +1,12 @@
 use anchor_client::solana_sdk::{
-    compute_budget::ComputeBudgetInstruction, instruction::Instruction,
+    compute_budget::{self, ComputeBudgetInstruction},
+    instruction::Instruction,
 };
 
+// without a limit instruction the runtime grants this much per instruction, up to MAX_UNITS
+const DEFAULT_INSTRUCTION_UNITS: u32 = 200_000;
+const MAX_UNITS: u32 = 1_400_000;
+
 pub fn limit_instruction(units: u32) -> Instruction {
     ComputeBudgetInstruction::set_compute_unit_limit(units)
 }
@@ -10,6 +15,32 @@ pub fn price_instruction(micro_lamports: u64) -> Instruction {
     ComputeBudgetInstruction::set_compute_unit_price(micro_lamports)
 }
 
+/// `micro_lamports` clamped to `ceiling`, a ceiling of 0 leaves it as is.
+pub fn cap_price(micro_lamports: u64, ceiling: u64) -> u64 {
+    if ceiling > 0 {
+        micro_lamports.min(ceiling)
+    } else {
+        micro_lamports
+    }
+}
+
+/// Compute units a transaction without a limit instruction gets for `ixs`, compute budget
+/// instructions don't count.
+pub fn default_limit<'a>(ixs: impl IntoIterator<Item = &'a Instruction>) -> u32 {
+    let counted = ixs
+        .into_iter()
+        .filter(|ix| ix.program_id != compute_budget::id())
+        .count() as u32;
+    counted
+        .saturating_mul(DEFAULT_INSTRUCTION_UNITS)
+        .min(MAX_UNITS)
+}
+
+/// Lamports paid in priority fee for `units` compute units at `micro_lamports` each.
+pub fn priority_fee(micro_lamports: u64, units: u32) -> u64 {
+    (u128::from(micro_lamports) * u128::from(units)).div_ceil(1_000_000) as u64
+}
+
 pub fn loaded_accounts_data_size_limit_instruction(bytes: u32) -> Instruction {
     ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(bytes)
 }
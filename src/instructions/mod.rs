@@ -1,3 +1,4 @@
+use crate::global;
 use anchor_client::{
     solana_client::nonblocking::rpc_client::RpcClient,
     solana_client::rpc_config::RpcSendTransactionConfig,
@@ -27,6 +28,7 @@ pub mod flashloan;
 pub use flashloan::*;
 pub mod aggregator;
 pub mod token;
+pub mod transfer_hook;
 
 pub mod util {
     use super::*;
@@ -54,7 +56,7 @@ pub mod util {
     }
 
     pub async fn get_latest_blockhash(rpc_url: String) -> Result<Hash> {
-        let rpc_client = RpcClient::new(rpc_url.to_string());
+        let rpc_client = global::new_rpc_with_commitment(&rpc_url, CommitmentConfig::default());
         Ok(rpc_client.get_latest_blockhash().await?)
     }
 
@@ -62,7 +64,7 @@ pub mod util {
         rpc_url: String,
         alt_pubkeys: &[Pubkey],
     ) -> Result<Vec<AddressLookupTableAccount>> {
-        let rpc_client = Arc::new(RpcClient::new(rpc_url.to_string()));
+        let rpc_client = global::new_rpc_with_commitment(&rpc_url, CommitmentConfig::default());
 
         let alt_future: Vec<_> = alt_pubkeys
             .iter()
@@ -97,7 +99,7 @@ pub mod util {
         payer: Rc<Keypair>,
         instructions: &[Instruction],
     ) -> Result<Signature> {
-        let rpc_client = Arc::new(RpcClient::new(rpc_url.to_string()));
+        let rpc_client = global::new_rpc_with_commitment(&rpc_url, CommitmentConfig::default());
         let (recent, _) = rpc_client
             .get_latest_blockhash_with_commitment(CommitmentConfig::processed())
             .await?;
@@ -116,7 +118,7 @@ pub mod util {
         payer: Arc<Keypair>,
         instructions: &[Instruction],
     ) -> Result<Signature> {
-        let rpc_client = Arc::new(RpcClient::new(rpc_url.to_string()));
+        let rpc_client = global::new_rpc_with_commitment(&rpc_url, CommitmentConfig::default());
         let (recent, _) = rpc_client
             .get_latest_blockhash_with_commitment(CommitmentConfig::processed())
             .await?;
@@ -146,10 +148,7 @@ pub mod util {
         instructions: &[Instruction],
         alt_accounts: &[AddressLookupTableAccount],
     ) -> Result<Signature> {
-        let rpc_client = Arc::new(RpcClient::new_with_commitment(
-            rpc_url.to_string(),
-            CommitmentConfig::processed(),
-        ));
+        let rpc_client = global::new_rpc(&rpc_url);
         let (recent_blockhash, _) = rpc_client
             .get_latest_blockhash_with_commitment(CommitmentConfig::processed())
             .await?;
@@ -187,7 +186,7 @@ pub mod util {
         mint: &Pubkey,
     ) -> Result<Pubkey> {
         let ata = get_associated_token_address(owner, mint);
-        let rpc = RpcClient::new(rpc_url.to_string());
+        let rpc = global::new_rpc_with_commitment(&rpc_url, CommitmentConfig::default());
 
         match rpc.get_account(&ata).await {
             std::result::Result::Ok(_) => {}
@@ -207,7 +206,7 @@ pub mod util {
         payer: Arc<Keypair>,
         instructions: &[Instruction],
     ) -> Result<String> {
-        let rpc_client = Arc::new(RpcClient::new(rpc_url.to_string()));
+        let rpc_client = global::new_rpc_with_commitment(&rpc_url, CommitmentConfig::default());
         let (recent, _) = rpc_client
             .get_latest_blockhash_with_commitment(CommitmentConfig::processed())
             .await?;
@@ -228,7 +227,7 @@ pub mod util {
         instructions: &[Instruction],
         alt_accounts: &[AddressLookupTableAccount],
     ) -> Result<String> {
-        let rpc_client = Arc::new(RpcClient::new(rpc_url.to_string()));
+        let rpc_client = global::new_rpc_with_commitment(&rpc_url, CommitmentConfig::default());
         let (recent_blockhash, _) = rpc_client
             .get_latest_blockhash_with_commitment(CommitmentConfig::processed())
             .await?;
@@ -245,4 +244,22 @@ pub mod util {
 
         Ok(bs58::encode(bytes).into_string())
     }
+
+    /// Same as `create_versioned_transaction_bs58` but leaves signing to the caller,
+    /// the result is the serialized message the signature covers.
+    pub async fn create_unsigned_versioned_message_bs58(
+        rpc_url: String,
+        payer: &Pubkey,
+        instructions: &[Instruction],
+        alt_accounts: &[AddressLookupTableAccount],
+    ) -> Result<String> {
+        let rpc_client = global::new_rpc_with_commitment(&rpc_url, CommitmentConfig::default());
+        let (recent_blockhash, _) = rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::processed())
+            .await?;
+        let message =
+            v0::Message::try_compile(payer, instructions, &alt_accounts, recent_blockhash)?;
+
+        Ok(bs58::encode(VersionedMessage::V0(message).serialize()).into_string())
+    }
 }
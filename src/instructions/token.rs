@@ -1,4 +1,5 @@
-use anchor_client::solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use crate::wsol_mint;
+use anchor_client::solana_sdk::{instruction::Instruction, pubkey::Pubkey, system_instruction};
 use anyhow::Result;
 use spl_associated_token_account::get_associated_token_address;
 use spl_token::instruction as token_instruction;
@@ -28,6 +29,33 @@ pub fn token_transfer_instruction(
     Ok(instruction)
 }
 
+pub fn create_ata_idempotent_instruction(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+        payer,
+        owner,
+        mint,
+        &spl_token::id(),
+    )
+}
+
+pub fn create_ata_idempotent_instruction_with_program(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Instruction {
+    spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+        payer,
+        owner,
+        mint,
+        token_program_id,
+    )
+}
+
 pub fn create_ata_token_instruction(
     payer: &Pubkey,
     owner: &Pubkey,
@@ -43,3 +71,30 @@ pub fn create_ata_token_instruction(
 
     Ok(instruction)
 }
+
+pub fn wrap_sol(owner: &Pubkey, amount: u64, create_account: bool) -> Result<Vec<Instruction>> {
+    let token_program_id = spl_token::id();
+    let wsol_ata = get_associated_token_address(owner, &wsol_mint());
+    let mut instructions = Vec::with_capacity(3);
+
+    if create_account {
+        instructions.push(create_ata_idempotent_instruction(owner, owner, &wsol_mint()));
+    }
+
+    instructions.push(system_instruction::transfer(owner, &wsol_ata, amount));
+    instructions.push(token_instruction::sync_native(
+        &token_program_id,
+        &wsol_ata,
+    )?);
+
+    Ok(instructions)
+}
+
+pub fn unwrap_sol(owner: &Pubkey) -> Result<Instruction> {
+    let token_program_id = spl_token::id();
+    let wsol_ata = get_associated_token_address(owner, &wsol_mint());
+    let instruction =
+        token_instruction::close_account(&token_program_id, &wsol_ata, owner, owner, &[])?;
+
+    Ok(instruction)
+}
@@ -0,0 +1,211 @@
+use crate::{
+    arb::transfer_fee::read_extension,
+    streaming::{AccountDataType, global_data},
+    token_2022_program,
+};
+use anchor_client::{
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{instruction::AccountMeta, pubkey::Pubkey},
+};
+use anyhow::{Result, anyhow};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+use tracing::warn;
+
+const EXTENSION_TRANSFER_HOOK: u16 = 14;
+// authority, then the hook program (zeroed when unset)
+const HOOK_PROGRAM_OFFSET: usize = 32;
+const EXTRA_ACCOUNT_METAS_SEED: &[u8] = b"extra-account-metas";
+// Execute discriminator, TLV length, then the u32 entry count
+const META_LIST_HEADER_LEN: usize = 8 + 4 + 4;
+// discriminator, address config, is_signer, is_writable
+const EXTRA_ACCOUNT_META_LEN: usize = 1 + 32 + 1 + 1;
+// entries with any other discriminator are derived from seeds
+const META_FIXED_ADDRESS: u8 = 0;
+
+// mint -> accounts its transfer hook needs, or why they couldn't be resolved
+static HOOK_ACCOUNTS: Lazy<DashMap<Pubkey, std::result::Result<Vec<AccountMeta>, String>>> =
+    Lazy::new(DashMap::new);
+
+/// Transfer hook program of a Token-2022 mint, `None` without the extension or with it unset.
+pub fn hook_program(mint_data: &[u8]) -> Option<Pubkey> {
+    let value = read_extension(mint_data, EXTENSION_TRANSFER_HOOK)?;
+    let program =
+        Pubkey::try_from(value.get(HOOK_PROGRAM_OFFSET..HOOK_PROGRAM_OFFSET + 32)?).ok()?;
+    (program != Pubkey::default()).then_some(program)
+}
+
+/// Account holding the `ExtraAccountMetaList` of `mint` under its hook program.
+pub fn validation_address(mint: &Pubkey, hook_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[EXTRA_ACCOUNT_METAS_SEED, mint.as_ref()], hook_program).0
+}
+
+/// Extra accounts listed in an `ExtraAccountMetaList`. Only fixed addresses can be resolved
+/// ahead of time, seed-derived entries depend on the transfer and are rejected.
+pub fn parse_extra_account_metas(data: &[u8]) -> std::result::Result<Vec<AccountMeta>, String> {
+    let count = data
+        .get(META_LIST_HEADER_LEN - 4..META_LIST_HEADER_LEN)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+        .ok_or("validation account too short")?;
+
+    (0..count)
+        .map(|i| {
+            let offset = META_LIST_HEADER_LEN + i * EXTRA_ACCOUNT_META_LEN;
+            let entry = data
+                .get(offset..offset + EXTRA_ACCOUNT_META_LEN)
+                .ok_or_else(|| format!("extra account {} out of bounds", i))?;
+            if entry[0] != META_FIXED_ADDRESS {
+                return Err(format!("extra account {} is seed-derived", i));
+            }
+
+            Ok(AccountMeta {
+                pubkey: Pubkey::try_from(&entry[1..33]).unwrap(),
+                is_signer: entry[33] != 0,
+                is_writable: entry[34] != 0,
+            })
+        })
+        .collect()
+}
+
+async fn resolve(
+    rpc_client: &RpcClient,
+    mint: &Pubkey,
+    hook_program: Pubkey,
+) -> std::result::Result<Vec<AccountMeta>, String> {
+    let validation = validation_address(mint, &hook_program);
+    let data = rpc_client
+        .get_account_data(&validation)
+        .await
+        .map_err(|e| format!("validation account {} unavailable: {}", validation, e))?;
+
+    // the hook program and its validation account follow the extra accounts
+    let mut metas = parse_extra_account_metas(&data)?;
+    metas.push(AccountMeta::new_readonly(hook_program, false));
+    metas.push(AccountMeta::new_readonly(validation, false));
+    Ok(metas)
+}
+
+/// Resolve the transfer hook accounts of `mints`, called when a pool trading them is indexed.
+/// Failures are remembered so routes through the mint are skipped instead of submitted.
+pub async fn load(rpc_client: Arc<RpcClient>, mints: &[Pubkey]) {
+    let accounts = match rpc_client.get_multiple_accounts(mints).await {
+        std::result::Result::Ok(accounts) => accounts,
+        Err(e) => {
+            warn!(
+                "Failed to fetch mints {:?} for transfer hooks: {}",
+                mints, e
+            );
+            return;
+        }
+    };
+
+    for (mint, account) in mints.iter().zip(accounts) {
+        let Some(hook_program) = account
+            .filter(|account| account.owner == token_2022_program())
+            .and_then(|account| hook_program(&account.data))
+        else {
+            continue;
+        };
+
+        let resolved = resolve(&rpc_client, mint, hook_program).await;
+        if let Err(reason) = &resolved {
+            warn!("Transfer hook of mint {} unresolved: {}", mint, reason);
+        }
+        HOOK_ACCOUNTS.insert(*mint, resolved);
+    }
+}
+
+/// Accounts a transfer of `mint` needs for its transfer hook, empty without one. Errors when
+/// the cached mint has a hook whose accounts were never resolved or couldn't be.
+pub fn hook_accounts(mint: &Pubkey) -> Result<Vec<AccountMeta>> {
+    if let Some(resolved) = HOOK_ACCOUNTS.get(mint) {
+        return resolved
+            .clone()
+            .map_err(|reason| anyhow!("Transfer hook of mint {} unresolved: {}", mint, reason));
+    }
+
+    let account = match global_data::get_account(mint) {
+        Some(AccountDataType::Account(account)) => Some(account),
+        _ => global_data::get_mint_account(mint),
+    };
+    match account
+        .filter(|account| account.owner == token_2022_program())
+        .and_then(|account| hook_program(&account.data))
+    {
+        Some(hook_program) => Err(anyhow!(
+            "Transfer hook {} of mint {} was never resolved",
+            hook_program,
+            mint
+        )),
+        None => Ok(vec![]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extra_account_metas(entries: &[(u8, Pubkey, bool, bool)]) -> Vec<u8> {
+        let mut data = vec![0u8; META_LIST_HEADER_LEN - 4];
+        data.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        for (discriminator, address, is_signer, is_writable) in entries {
+            data.push(*discriminator);
+            data.extend_from_slice(address.as_ref());
+            data.push(*is_signer as u8);
+            data.push(*is_writable as u8);
+        }
+        data
+    }
+
+    #[test]
+    fn reads_the_hook_program() {
+        let program = Pubkey::new_unique();
+        let mut data = vec![0u8; 165];
+        data.push(1); // mint account type
+        let mut value = vec![0u8; HOOK_PROGRAM_OFFSET];
+        value.extend_from_slice(program.as_ref());
+        data.extend_from_slice(&EXTENSION_TRANSFER_HOOK.to_le_bytes());
+        data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        data.extend_from_slice(&value);
+
+        assert_eq!(hook_program(&data), Some(program));
+
+        // the extension stays on the mint once the hook is removed, with a zeroed program
+        let unset = data.len() - 32;
+        data[unset..].fill(0);
+        assert_eq!(hook_program(&data), None);
+        assert_eq!(hook_program(&[0u8; 82]), None);
+    }
+
+    #[test]
+    fn parses_fixed_extra_accounts() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let data = extra_account_metas(&[
+            (META_FIXED_ADDRESS, first, false, true),
+            (META_FIXED_ADDRESS, second, false, false),
+        ]);
+
+        assert_eq!(
+            parse_extra_account_metas(&data).unwrap(),
+            vec![
+                AccountMeta::new(first, false),
+                AccountMeta::new_readonly(second, false)
+            ]
+        );
+        assert!(parse_extra_account_metas(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn rejects_seed_derived_extra_accounts() {
+        let data = extra_account_metas(&[
+            (META_FIXED_ADDRESS, Pubkey::new_unique(), false, false),
+            (1, Pubkey::default(), false, true),
+        ]);
+
+        assert_eq!(
+            parse_extra_account_metas(&data),
+            Err("extra account 1 is seed-derived".to_string())
+        );
+    }
+}
@@ -4,14 +4,22 @@ use bs58;
 use std::fs;
 
 pub fn load_key_pair_from_bs58(path: &str) -> Result<Keypair> {
-    let b58_str = fs::read_to_string(path)?.trim().to_string();
+    let b58_str = fs::read_to_string(path)?;
+    keypair_from_bs58(&b58_str)
+}
 
-    let bytes = bs58::decode(b58_str).into_vec()?;
+/// Parse a base58 secret key, errors never echo any part of the secret.
+pub fn keypair_from_bs58(secret: &str) -> Result<Keypair> {
+    let Ok(bytes) = bs58::decode(secret.trim()).into_vec() else {
+        bail!("Invalid secret key");
+    };
     if bytes.len() != 64 {
         bail!("Invalid secret key");
     }
 
-    let payer = Keypair::from_bytes(&bytes)?;
+    let Ok(payer) = Keypair::from_bytes(&bytes) else {
+        bail!("Invalid secret key");
+    };
     Ok(payer)
 }
 
@@ -0,0 +1,121 @@
+use crate::config::LogConfig;
+use anyhow::{Result, anyhow};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::{
+    str::FromStr,
+    sync::{
+        OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+use tracing::{Level, Metadata, callsite::Identifier, level_filters::LevelFilter};
+use tracing_subscriber::{
+    EnvFilter, Layer, Registry, filter::filter_fn, layer::SubscriberExt, reload,
+    util::SubscriberInitExt,
+};
+
+// modules whose debug events fire per message or per update, relative to the crate root
+const SAMPLED_MODULES: [&str; 2] = ["watcher", "streaming::grpc"];
+
+// level filter, reloadable so `configure` can change it after startup
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+static SAMPLE_RATE: AtomicU64 = AtomicU64::new(1);
+// events seen per sampled call site
+static CALLSITE_COUNTS: Lazy<DashMap<Identifier, u64>> = Lazy::new(DashMap::new);
+
+fn is_sampled_module(target: &str) -> bool {
+    let Some(module) = target
+        .strip_prefix(env!("CARGO_CRATE_NAME"))
+        .and_then(|module| module.strip_prefix("::"))
+    else {
+        return false;
+    };
+
+    SAMPLED_MODULES.iter().any(|sampled| {
+        module == *sampled
+            || module
+                .strip_prefix(sampled)
+                .is_some_and(|rest| rest.starts_with("::"))
+    })
+}
+
+/// `level` for every target, with the RUST_LOG directives added, e.g.
+/// `RUST_LOG=solarb_client::arb=trace`.
+fn level_filter(level: LevelFilter) -> EnvFilter {
+    EnvFilter::builder()
+        .with_default_directive(level.into())
+        .from_env_lossy()
+}
+
+/// Whether the `count`-th event (from 0) of a call site is emitted at 1-in-`rate`.
+fn keep(count: u64, rate: u64) -> bool {
+    rate <= 1 || count % rate == 0
+}
+
+// the level filter already ran, this only thins out the sampled modules
+fn sampled(metadata: &Metadata<'_>) -> bool {
+    let rate = SAMPLE_RATE.load(Ordering::Relaxed);
+    if rate <= 1
+        || !metadata.is_event()
+        || *metadata.level() < Level::DEBUG
+        || !is_sampled_module(metadata.target())
+    {
+        return true;
+    }
+
+    let mut count = CALLSITE_COUNTS.entry(metadata.callsite()).or_insert(0);
+    let emit = keep(*count, rate);
+    *count += 1;
+    emit
+}
+
+/// Install the fmt subscriber at info level, `configure` applies `[log]` once it is read.
+pub fn init() {
+    let (filter, handle) = reload::Layer::new(level_filter(LevelFilter::INFO));
+    let _ = FILTER.set(handle);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().with_filter(filter_fn(sampled)))
+        .init();
+}
+
+/// Apply `log.level` and `log.sample_rate`. Debug events in the watcher and gRPC loops are
+/// printed 1-in-`sample_rate` per call site, info and above always are.
+pub fn configure(config: &LogConfig) -> Result<()> {
+    let level = LevelFilter::from_str(&config.level)
+        .map_err(|_| anyhow!("Invalid log.level {}", config.level))?;
+    if let Some(filter) = FILTER.get() {
+        filter
+            .reload(level_filter(level))
+            .map_err(|e| anyhow!("Failed to apply log.level {}: {}", config.level, e))?;
+    }
+    SAMPLE_RATE.store(config.sample_rate.max(1), Ordering::Relaxed);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_hot_modules_are_sampled() {
+        let target = |module: &str| format!("{}::{}", env!("CARGO_CRATE_NAME"), module);
+        assert!(is_sampled_module(&target("watcher")));
+        assert!(is_sampled_module(&target("watcher::processor")));
+        assert!(is_sampled_module(&target("streaming::grpc")));
+        assert!(!is_sampled_module(&target("streaming::grpc_x")));
+        assert!(!is_sampled_module(&target("arb::processor")));
+        assert!(!is_sampled_module("tonic::transport"));
+    }
+
+    #[test]
+    fn keeps_one_in_rate() {
+        let kept = (0..100).filter(|count| keep(*count, 10)).count();
+        assert_eq!(kept, 10);
+        assert!(keep(0, 10));
+        assert!(!keep(1, 10));
+        assert!((0..5).all(|count| keep(count, 1)));
+    }
+}
@@ -1,10 +1,11 @@
 use anyhow::{Ok, Result};
-use tracing::info;
-use tracing_subscriber;
+use tokio::signal::unix::{SignalKind, signal};
+use tracing::{error, info, warn};
 
 pub mod arb;
 pub mod byte_reader;
 pub mod cache;
+pub mod cli;
 pub mod config;
 pub mod constants;
 pub mod dex;
@@ -12,6 +13,7 @@ pub mod global;
 pub mod inserter;
 pub mod instructions;
 pub mod io;
+pub mod logging;
 pub mod math;
 pub mod metric;
 pub mod onchain;
@@ -19,28 +21,98 @@ pub mod polling;
 pub mod pool_index;
 pub mod safe_math;
 pub mod streaming;
+#[cfg(test)]
+mod test_fixtures;
 pub mod transaction;
 pub mod util;
 pub mod watcher;
 
 pub use constants::*;
 
+const CLOCK_WAIT_SECS: u64 = 30;
+
+/// Re-read the config on every SIGHUP, applying what can change without a restart.
+fn reload_config_on_sighup() -> Result<()> {
+    let mut hangups = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            let report = match global::reload_config() {
+                std::result::Result::Ok(report) => report,
+                Err(e) => {
+                    error!("Config reload failed, keeping the current one: {}", e);
+                    continue;
+                }
+            };
+
+            if let Err(e) = logging::configure(&global::get_config().log) {
+                error!("Config reload: {}", e);
+            }
+            if report.changed.is_empty() {
+                info!("Config reloaded, no runtime setting changed");
+            } else {
+                info!("Config reloaded: {}", report.changed.join(", "));
+            }
+            if !report.ignored.is_empty() {
+                warn!(
+                    "Config reload ignored changes under [{}], they need a restart",
+                    report.ignored.join("], [")
+                );
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Check `[nonce]` against the chain, otherwise a bad account only shows as every arb falling
+/// back to the recent blockhash.
+async fn check_nonce_account() -> Result<()> {
+    let Some((nonce_account, authority)) = global::get_nonce_account()? else {
+        return Ok(());
+    };
+
+    let nonce = onchain::get_nonce_data(&nonce_account).await?;
+    if nonce.authority != authority {
+        return Err(anyhow::anyhow!(
+            "Nonce account {} is authorized to {}, not the wallet {}",
+            nonce_account,
+            nonce.authority,
+            authority
+        ));
+    }
+    info!("Submitting against durable nonce {}", nonce_account);
+
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
+    logging::init();
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("quote") {
+        return cli::quote(&args[2..]).await;
+    }
+    if args.get(1).map(String::as_str) == Some("selftest") {
+        return cli::selftest().await;
+    }
+
     info!("Solarb client runing...");
     let conf = config::read_config("config.toml").unwrap();
-    let _ = global::prepare_data(None, &conf.bot.mint).await;
+    logging::configure(&conf.log)?;
+    global::prepare_data(None, &conf.bot.mint).await?;
     println!("Mainnet wallet {}", global::get_pubkey());
     let base_mint = global::get_base_mint().as_ref().clone();
     let base_mint_ata_amount = global::get_base_mint_amount();
     println!("Base mint {} - amount {}", base_mint, base_mint_ata_amount);
+    check_nonce_account().await?;
 
     {
         let command_tx = streaming::start(conf.clone()).await?;
+        watcher::pin_pools(&command_tx).await?;
         let command_tx_2 = command_tx.clone();
         watcher::monitoring(conf, Some(command_tx), 3).await?;
-        let event_receiver = streaming::polling::start(10_000).await?;
+        let event_receiver =
+            streaming::polling::start(streaming::polling::POLLING_INTERVAL_MS).await?;
 
         tokio::spawn(streaming::updater::signal_receiver(
             event_receiver,
@@ -48,15 +120,37 @@ async fn main() -> Result<()> {
         ));
 
         polling::blockhash::start_blockhash_refresher(1);
+        polling::balance::start_balance_refresher(global::get_balance_refresh_secs());
+        streaming::start_alt_refresher(global::get_alt_ttl_secs());
+        if global::cleanup_temp_atas() {
+            onchain::temp_ata::start_cleanup(global::get_temp_ata_cleanup_secs());
+        }
         metric::start(60);
+        reload_config_on_sighup()?;
         tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-        arb::processor::finding(100)?;
+        // every quote needs the clock, passes before it arrives would find nothing
+        let clock_timeout = tokio::time::Duration::from_secs(CLOCK_WAIT_SECS);
+        match streaming::global_data::wait_for_clock(clock_timeout).await {
+            Some(clock) => info!("Clock at slot {}, starting route finding", clock.slot),
+            None => warn!(
+                "No clock update after {}s, starting route finding anyway, passes are skipped until it arrives",
+                CLOCK_WAIT_SECS
+            ),
+        }
+        arb::processor::finding(global::get_finding_interval_ms())?;
 
         tokio::signal::ctrl_c()
             .await
             .expect("Failed to listen for ctrl-c");
 
         info!("Shutting down...");
+        arb::processor::stop_finding();
+        if global::cleanup_temp_atas() {
+            match onchain::temp_ata::close_empty(false).await {
+                std::result::Result::Ok(closed) => info!("Closed {} empty temporary ATAs", closed),
+                Err(e) => warn!("Temporary ATA cleanup failed: {}", e),
+            }
+        }
     }
 
     Ok(())
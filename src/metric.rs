@@ -1,11 +1,487 @@
-use crate::{arb, pool_index, streaming, wsol_mint};
+use crate::{
+    arb::{
+        self, QuoteError,
+        confirmation::{RouteOutcome, SubmittedArb},
+    },
+    global, pool_index,
+    streaming::{self, price},
+    watcher, wsol_mint,
+};
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
 use tokio::time;
-use tracing::info;
+use tracing::{debug, info, warn};
+
+// target slot time, the clock only carries a timestamp to measure its age by
+const SLOT_MS: u64 = 400;
+
+// upper bounds of the latency buckets in microseconds, slower samples land in the last bucket
+const LATENCY_BUCKETS_US: [u64; 7] = [1, 10, 100, 1_000, 10_000, 100_000, 1_000_000];
+
+/// Bucketed latency counts, reset every time the summary is logged.
+pub struct LatencyHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS_US.len() + 1],
+    count: AtomicU64,
+    total_us: AtomicU64,
+}
+
+impl LatencyHistogram {
+    const fn new() -> Self {
+        Self {
+            buckets: [const { AtomicU64::new(0) }; LATENCY_BUCKETS_US.len() + 1],
+            count: AtomicU64::new(0),
+            total_us: AtomicU64::new(0),
+        }
+    }
+
+    #[inline]
+    pub fn record(&self, elapsed: std::time::Duration) {
+        let us = elapsed.as_micros() as u64;
+        let bucket = LATENCY_BUCKETS_US
+            .iter()
+            .position(|bound| us <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_US.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_us.fetch_add(us, Ordering::Relaxed);
+    }
+
+    /// `count avg [<=1us a, <=10us b, ..., >1000000us z]`, then clear the counts.
+    fn take_summary(&self) -> String {
+        let count = self.count.swap(0, Ordering::Relaxed);
+        let total_us = self.total_us.swap(0, Ordering::Relaxed);
+        let buckets: Vec<String> = self
+            .buckets
+            .iter()
+            .enumerate()
+            .map(|(i, bucket)| {
+                let n = bucket.swap(0, Ordering::Relaxed);
+                match LATENCY_BUCKETS_US.get(i) {
+                    Some(bound) => format!("<={}us {}", bound, n),
+                    None => format!(">{}us {}", LATENCY_BUCKETS_US[i - 1], n),
+                }
+            })
+            .collect();
+        let avg_us = if count == 0 { 0 } else { total_us / count };
+
+        format!("{} avg {}us [{}]", count, avg_us, buckets.join(", "))
+    }
+}
+
+/// Iterations an optimizer needed per run, reset every time the summary is logged.
+pub struct IterationStats {
+    runs: AtomicU64,
+    iterations: AtomicU64,
+    capped: AtomicU64,
+}
+
+impl IterationStats {
+    const fn new() -> Self {
+        Self {
+            runs: AtomicU64::new(0),
+            iterations: AtomicU64::new(0),
+            capped: AtomicU64::new(0),
+        }
+    }
+
+    /// `capped` is set when the run stopped on `bot.max_optimizer_iters` rather than converging.
+    #[inline]
+    pub fn record(&self, iterations: usize, capped: bool) {
+        self.runs.fetch_add(1, Ordering::Relaxed);
+        self.iterations
+            .fetch_add(iterations as u64, Ordering::Relaxed);
+        if capped {
+            self.capped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// `runs avg iterations (capped)`, then clear the counts.
+    fn take_summary(&self) -> String {
+        let runs = self.runs.swap(0, Ordering::Relaxed);
+        let iterations = self.iterations.swap(0, Ordering::Relaxed);
+        let capped = self.capped.swap(0, Ordering::Relaxed);
+        let avg = if runs == 0 {
+            0.0
+        } else {
+            iterations as f64 / runs as f64
+        };
+
+        format!("{} runs avg {:.1} iters ({} capped)", runs, avg, capped)
+    }
+}
+
+/// Hop quotes that errored, per `QuoteError` variant, reset every time the summary is logged.
+pub struct QuoteErrorCounts {
+    no_liquidity: AtomicU64,
+    unsupported: AtomicU64,
+    disabled: AtomicU64,
+    dex: AtomicU64,
+}
+
+impl QuoteErrorCounts {
+    const fn new() -> Self {
+        Self {
+            no_liquidity: AtomicU64::new(0),
+            unsupported: AtomicU64::new(0),
+            disabled: AtomicU64::new(0),
+            dex: AtomicU64::new(0),
+        }
+    }
+
+    #[inline]
+    pub fn record(&self, error: &QuoteError) {
+        let counter = match error {
+            QuoteError::NoLiquidity => &self.no_liquidity,
+            QuoteError::Unsupported => &self.unsupported,
+            QuoteError::Disabled => &self.disabled,
+            QuoteError::Dex(_) => &self.dex,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// `no liquidity, unsupported, disabled, dex` counts, then clear them.
+    fn take_summary(&self) -> String {
+        format!(
+            "{} no liquidity, {} unsupported, {} disabled, {} dex",
+            self.no_liquidity.swap(0, Ordering::Relaxed),
+            self.unsupported.swap(0, Ordering::Relaxed),
+            self.disabled.swap(0, Ordering::Relaxed),
+            self.dex.swap(0, Ordering::Relaxed)
+        )
+    }
+}
+
+/// Why a detected opportunity didn't go out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    // net profit under bot.minimum_profit
+    Floor,
+    // the same route or first hop went out too recently
+    Cooldown,
+    // bot.max_inflight_arbs submissions already pending
+    InflightCap,
+    // a pool on the route is blacklisted or one of its pairs paused
+    Blacklist,
+    // waited in the send queue past bot.opportunity_ttl_ms
+    Expired,
+}
+
+impl SkipReason {
+    const ALL: [SkipReason; 5] = [
+        SkipReason::Floor,
+        SkipReason::Cooldown,
+        SkipReason::InflightCap,
+        SkipReason::Blacklist,
+        SkipReason::Expired,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SkipReason::Floor => "floor",
+            SkipReason::Cooldown => "cooldown",
+            SkipReason::InflightCap => "inflight_cap",
+            SkipReason::Blacklist => "blacklist",
+            SkipReason::Expired => "expired",
+        }
+    }
+}
+
+/// Opportunities of this session, detected against submitted, landed and skipped per reason.
+/// An opportunity is a route quoted at some net profit, counted once under its first outcome
+/// however many passes find it again. Never reset, a snapshot is appended to the ledger
+/// whenever the counts changed.
+pub struct OpportunityCounts {
+    detected: AtomicU64,
+    submitted: AtomicU64,
+    // only counted with bot.track_confirmations
+    landed: AtomicU64,
+    skipped: [AtomicU64; SkipReason::ALL.len()],
+    // net profit of the skipped opportunities in lamports, see `net_profit_lamports`
+    skipped_profit: [AtomicI64; SkipReason::ALL.len()],
+    // route hash -> net profit of the last opportunity counted on it, one entry per route
+    counted: Lazy<DashMap<u64, i64>>,
+}
+
+impl OpportunityCounts {
+    const fn new() -> Self {
+        Self {
+            detected: AtomicU64::new(0),
+            submitted: AtomicU64::new(0),
+            landed: AtomicU64::new(0),
+            skipped: [const { AtomicU64::new(0) }; SkipReason::ALL.len()],
+            skipped_profit: [const { AtomicI64::new(0) }; SkipReason::ALL.len()],
+            counted: Lazy::new(DashMap::new),
+        }
+    }
+
+    /// Count `route` quoted at `net_profit` as detected, false when that quote was counted
+    /// already, e.g. found again by the next pass over unchanged pools.
+    fn detect(&self, route: u64, net_profit: i64) -> bool {
+        let new = self.counted.insert(route, net_profit) != Some(net_profit);
+        if new {
+            self.detected.fetch_add(1, Ordering::Relaxed);
+        }
+        new
+    }
+
+    /// Every submission counts, also one of a quote skipped before, e.g. at the in-flight cap.
+    #[inline]
+    pub fn record_submitted(&self, route: u64, net_profit: i64) {
+        self.detect(route, net_profit);
+        self.submitted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn record_landed(&self) {
+        self.landed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// `route` quoted at `net_profit` skipped for `reason`, unless the quote was counted already.
+    #[inline]
+    pub fn record_skipped(&self, route: u64, reason: SkipReason, net_profit: i64) {
+        if self.detect(route, net_profit) {
+            self.skipped[reason as usize].fetch_add(1, Ordering::Relaxed);
+            self.skipped_profit[reason as usize].fetch_add(net_profit, Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self, session: i64, timestamp: i64) -> arb::ledger::OpportunityRecord {
+        let skipped = SkipReason::ALL
+            .iter()
+            .map(|reason| {
+                let skipped = arb::ledger::SkippedOpportunities {
+                    count: self.skipped[*reason as usize].load(Ordering::Relaxed),
+                    profit: self.skipped_profit[*reason as usize].load(Ordering::Relaxed),
+                };
+                (reason.name().to_string(), skipped)
+            })
+            .collect();
+
+        arb::ledger::OpportunityRecord {
+            session,
+            timestamp,
+            detected: self.detected.load(Ordering::Relaxed),
+            submitted: self.submitted.load(Ordering::Relaxed),
+            landed: self.landed.load(Ordering::Relaxed),
+            skipped,
+        }
+    }
+}
+
+/// `floor n (profit p), cooldown ...` over every skip reason.
+fn skipped_summary(record: &arb::ledger::OpportunityRecord) -> String {
+    let skipped: Vec<String> = SkipReason::ALL
+        .iter()
+        .filter_map(|reason| {
+            let skipped = record.skipped.get(reason.name())?;
+            Some(format!(
+                "{} {} (profit {})",
+                reason.name(),
+                skipped.count,
+                skipped.profit
+            ))
+        })
+        .collect();
+
+    skipped.join(", ")
+}
+
+pub static OPPORTUNITIES: OpportunityCounts = OpportunityCounts::new();
+
+pub static BRENT_ITERATIONS: IterationStats = IterationStats::new();
+pub static GOLDEN_SECTION_ITERATIONS: IterationStats = IterationStats::new();
+pub static TERNARY_SEARCH_ITERATIONS: IterationStats = IterationStats::new();
+
+pub static ROUTE_ENUMERATION_LATENCY: LatencyHistogram = LatencyHistogram::new();
+pub static HOP_SWAP_LATENCY: LatencyHistogram = LatencyHistogram::new();
+pub static OPTIMIZER_LATENCY: LatencyHistogram = LatencyHistogram::new();
+pub static QUOTE_ERRORS: QuoteErrorCounts = QuoteErrorCounts::new();
+pub static ROUTES_EVALUATED: AtomicU64 = AtomicU64::new(0);
+pub static ROUTES_PROFITABLE: AtomicU64 = AtomicU64::new(0);
+// routes dropped before quoting because their pair's pools were priced too close together
+pub static ROUTES_SPREAD_SKIPPED: AtomicU64 = AtomicU64::new(0);
+// profitable routes dropped from the send queue after bot.opportunity_ttl_ms
+pub static ROUTES_QUEUE_EXPIRED: AtomicU64 = AtomicU64::new(0);
+pub static FULL_PASSES: AtomicU64 = AtomicU64::new(0);
+// full passes that took longer than bot.finding_interval_ms, the next one started right away
+pub static FULL_PASSES_OVERRUN: AtomicU64 = AtomicU64::new(0);
+// pool updates folded into a rerun because the pass for that pool was still running
+pub static POOL_PASSES_SKIPPED: AtomicU64 = AtomicU64::new(0);
+
+static ROUTES_LANDED: AtomicU64 = AtomicU64::new(0);
+static ROUTES_FAILED: AtomicU64 = AtomicU64::new(0);
+static ROUTES_EXPIRED: AtomicU64 = AtomicU64::new(0);
+// quoted profit summed over landed routes, in the accounting mint
+static LANDED_PROFIT: AtomicI64 = AtomicI64::new(0);
+// landed routes whose profit had no price in the accounting mint, left out of LANDED_PROFIT
+static LANDED_UNPRICED: AtomicU64 = AtomicU64::new(0);
+
+/// The `(depth, rate)` quote from the deepest pool, ignoring unusable rates.
+fn deepest_rate(quotes: impl IntoIterator<Item = (f64, f64)>) -> Option<f64> {
+    quotes
+        .into_iter()
+        .filter_map(|(depth, rate)| Some((depth, price::sanitize(rate)?)))
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, rate)| rate)
+}
+
+/// `amount` of `mint` in raw units of `accounting_mint`, at the cached price of the deepest
+/// pool between the two. `None` when no pool of the pair has a usable price.
+pub fn to_accounting_mint(amount: i64, mint: &Pubkey, accounting_mint: &Pubkey) -> Option<i64> {
+    if mint == accounting_mint {
+        return Some(amount);
+    }
+
+    let quotes = pool_index::find_by_pair(mint, accounting_mint)
+        .into_iter()
+        .filter_map(|pool| {
+            let (mint_a, atob) = streaming::global_data::get_price(&pool)?;
+            let rate = if &mint_a == mint { atob } else { 1.0 / atob };
+            let depth = pool_index::get(&pool)?.to_pool_type()?.estimated_tvl(mint);
+            Some((depth, rate))
+        });
+    let converted = amount as f64 * deepest_rate(quotes)?;
+
+    converted.is_finite().then_some(converted as i64)
+}
+
+/// Record how a submitted route ended up on chain.
+pub fn record_route(arb: &SubmittedArb, outcome: RouteOutcome) {
+    match outcome {
+        RouteOutcome::Landed => {
+            ROUTES_LANDED.fetch_add(1, Ordering::Relaxed);
+            OPPORTUNITIES.record_landed();
+            let profit = global::get_accounting_mint()
+                .ok()
+                .and_then(|accounting_mint| {
+                    to_accounting_mint(arb.profit, &arb.mint, &accounting_mint)
+                });
+            match profit {
+                Some(profit) => {
+                    LANDED_PROFIT.fetch_add(profit, Ordering::Relaxed);
+                }
+                None => {
+                    LANDED_UNPRICED.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+        RouteOutcome::Failed => {
+            ROUTES_FAILED.fetch_add(1, Ordering::Relaxed);
+        }
+        RouteOutcome::Expired => {
+            ROUTES_EXPIRED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    info!(
+        "Route {:?} - {} - {} pools - amount in {} -> profit {} after {:?}",
+        outcome,
+        arb.signature,
+        arb.pools.len(),
+        arb.amount_in,
+        arb.profit,
+        arb.submitted.elapsed()
+    );
+}
+
+/// Roughly how many slots old a clock stamped `clock_timestamp` is at `now`, both unix seconds.
+fn clock_lag_slots(clock_timestamp: i64, now: i64) -> u64 {
+    (now.saturating_sub(clock_timestamp).max(0) as u64).saturating_mul(1_000) / SLOT_MS
+}
+
+/// Without a recent clock every quote comes out empty, warn once it falls behind.
+fn check_clock() {
+    let max_lag = global::get_max_clock_lag_slots();
+    if max_lag == 0 {
+        return;
+    }
+
+    let Some(clock) = streaming::global_data::get_clock() else {
+        warn!("Unhealthy: no clock update received, routes can't be quoted");
+        return;
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64);
+    let lag = clock_lag_slots(clock.unix_timestamp, now);
+    if lag > max_lag {
+        warn!(
+            "Unhealthy: clock at slot {} is ~{} slots old (max {}), routes are quoted on stale state",
+            clock.slot, lag, max_lag
+        );
+    }
+}
+
+/// Ledger pnl summed in the accounting mint, mints without a price show up separately.
+fn ledger_pnl(per_mint: &std::collections::HashMap<String, i64>) -> String {
+    let Ok(accounting_mint) = global::get_accounting_mint() else {
+        return "unavailable".to_string();
+    };
+
+    let mut total = 0i64;
+    let mut unpriced = Vec::new();
+    for (mint, pnl) in per_mint {
+        let converted = Pubkey::from_str(mint)
+            .ok()
+            .and_then(|mint| to_accounting_mint(*pnl, &mint, &accounting_mint));
+        match converted {
+            Some(converted) => total = total.saturating_add(converted),
+            None => unpriced.push(format!("{} {}", pnl, mint)),
+        }
+    }
+
+    if unpriced.is_empty() {
+        total.to_string()
+    } else {
+        format!("{} (+ unpriced {})", total, unpriced.join(", "))
+    }
+}
+
+/// Opportunity totals of the sessions already in the ledger, to compare this one against.
+fn log_previous_sessions() {
+    let summary = match arb::ledger::summary() {
+        Ok(summary) => summary,
+        Err(e) => {
+            warn!("Failed to read ledger {}", e);
+            return;
+        }
+    };
+    if summary.sessions.is_empty() {
+        return;
+    }
+
+    let mut totals = arb::ledger::OpportunityRecord::default();
+    for record in summary.sessions.values() {
+        totals.detected += record.detected;
+        totals.submitted += record.submitted;
+        totals.landed += record.landed;
+        for (reason, skipped) in &record.skipped {
+            let total = totals.skipped.entry(reason.clone()).or_default();
+            total.count += skipped.count;
+            total.profit += skipped.profit;
+        }
+    }
+    info!(
+        "Opportunities over {} previous sessions: {} detected, {} submitted, {} landed, skipped {}",
+        summary.sessions.len(),
+        totals.detected,
+        totals.submitted,
+        totals.landed,
+        skipped_summary(&totals)
+    );
+}
 
 pub fn start(delay_seconds: u64) {
     let mut interval = time::interval(time::Duration::from_secs(delay_seconds));
     tokio::spawn(async move {
         info!("Log starting...");
+        let session = chrono::Utc::now().timestamp();
+        log_previous_sessions();
+        // nothing worth a ledger line before the first opportunity
+        let mut recorded = OPPORTUNITIES.snapshot(session, session);
         loop {
             interval.tick().await;
             let total_accounts = streaming::count_accounts();
@@ -16,16 +492,222 @@ pub fn start(delay_seconds: u64) {
             let pool_count = pool_index::pool_count();
             let wsol_p_count = pool_index::find_by_mint(&wsol_mint()).len();
             let route_count = pool_index::routes_count();
+            let (ready_pools, pending_pools) = pool_index::readiness();
 
             info!(
                 "{} watched accounts, {} pools, {} wsol pools, {} invalid pools, {} token pools, {} route counts",
                 total_accounts,
                 pool_count,
                 wsol_p_count,
-                pool_index::count_invalid_pools(),
+                pool_count.saturating_sub(ready_pools),
                 native_pool_count,
                 route_count
             );
+            info!(
+                "Warmup: {} pools ready, {} pending",
+                ready_pools, pending_pools
+            );
+
+            info!(
+                "Route evaluation: {} evaluated, {} above profit floor, {} skipped on pair spread, {} expired in the send queue",
+                ROUTES_EVALUATED.swap(0, Ordering::Relaxed),
+                ROUTES_PROFITABLE.swap(0, Ordering::Relaxed),
+                ROUTES_SPREAD_SKIPPED.swap(0, Ordering::Relaxed),
+                ROUTES_QUEUE_EXPIRED.swap(0, Ordering::Relaxed)
+            );
+            info!(
+                "Route passes: {} full, {} over the interval, {} pool updates folded into a rerun",
+                FULL_PASSES.swap(0, Ordering::Relaxed),
+                FULL_PASSES_OVERRUN.swap(0, Ordering::Relaxed),
+                POOL_PASSES_SKIPPED.swap(0, Ordering::Relaxed)
+            );
+            info!(
+                "Route enumeration latency {}",
+                ROUTE_ENUMERATION_LATENCY.take_summary()
+            );
+            info!("Hop swap latency {}", HOP_SWAP_LATENCY.take_summary());
+            info!("Optimizer latency {}", OPTIMIZER_LATENCY.take_summary());
+            info!("Hop quote errors: {}", QUOTE_ERRORS.take_summary());
+            check_clock();
+            let pool_queue = watcher::take_pool_queue_stats();
+            info!(
+                "Pool queue: depth {}, {} pushed, {} popped, {} dropped",
+                pool_queue.depth, pool_queue.pushed, pool_queue.popped, pool_queue.dropped
+            );
+            debug!(
+                "Optimizer iterations: brent {}, golden section {}, ternary search {}",
+                BRENT_ITERATIONS.take_summary(),
+                GOLDEN_SECTION_ITERATIONS.take_summary(),
+                TERNARY_SEARCH_ITERATIONS.take_summary()
+            );
+
+            let blacklisted = arb::blacklist::entries();
+            if !blacklisted.is_empty() {
+                let pools: Vec<String> = blacklisted
+                    .iter()
+                    .map(|(pool, remaining)| format!("{} ({}s)", pool, remaining.as_secs()))
+                    .collect();
+                info!(
+                    "{} blacklisted pools: {}",
+                    blacklisted.len(),
+                    pools.join(", ")
+                );
+            }
+
+            let paused = arb::pair_breaker::entries();
+            if !paused.is_empty() {
+                let pairs: Vec<String> = paused
+                    .iter()
+                    .map(|((mint_a, mint_b), remaining)| {
+                        format!("{}/{} ({}s)", mint_a, mint_b, remaining.as_secs())
+                    })
+                    .collect();
+                info!("{} paused pairs: {}", paused.len(), pairs.join(", "));
+            }
+
+            let accounting_mint = global::get_accounting_mint()
+                .map(|mint| mint.to_string())
+                .unwrap_or_default();
+
+            if global::track_confirmations() {
+                info!(
+                    "Submissions: {} landed, {} failed, {} expired, landed profit {} {} ({} unpriced)",
+                    ROUTES_LANDED.load(Ordering::Relaxed),
+                    ROUTES_FAILED.load(Ordering::Relaxed),
+                    ROUTES_EXPIRED.load(Ordering::Relaxed),
+                    LANDED_PROFIT.load(Ordering::Relaxed),
+                    accounting_mint,
+                    LANDED_UNPRICED.load(Ordering::Relaxed)
+                );
+            }
+
+            let opportunities = OPPORTUNITIES.snapshot(session, chrono::Utc::now().timestamp());
+            info!(
+                "Opportunities this session: {} detected, {} submitted, {} landed, skipped {}",
+                opportunities.detected,
+                opportunities.submitted,
+                opportunities.landed,
+                skipped_summary(&opportunities)
+            );
+
+            if global::dry_run() {
+                match arb::ledger::summary() {
+                    Ok(summary) => info!(
+                        "Paper trading: {} fills, pnl {} {}, win rate {:.2}%, {} unreadable ledger lines",
+                        summary.fills,
+                        ledger_pnl(&summary.per_mint),
+                        accounting_mint,
+                        summary.win_rate() * 100.0,
+                        summary.bad_lines
+                    ),
+                    Err(e) => warn!("Failed to read ledger {}", e),
+                }
+            }
+
+            // a line per change, the last one of the session holds its totals
+            if opportunities.same_counts(&recorded) {
+                continue;
+            }
+            match arb::ledger::record_opportunities(&opportunities) {
+                Ok(()) => recorded = opportunities,
+                Err(e) => warn!("Failed to record opportunities {}", e),
+            }
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_mint_needs_no_price() {
+        let mint = Pubkey::new_unique();
+        assert_eq!(to_accounting_mint(-1_234, &mint, &mint), Some(-1_234));
+    }
+
+    #[test]
+    fn quote_errors_are_counted_per_variant() {
+        let counts = QuoteErrorCounts::new();
+        counts.record(&QuoteError::NoLiquidity);
+        counts.record(&QuoteError::NoLiquidity);
+        counts.record(&QuoteError::Disabled);
+        counts.record(&QuoteError::Dex(anyhow::anyhow!("Math overflow")));
+
+        assert_eq!(
+            counts.take_summary(),
+            "2 no liquidity, 0 unsupported, 1 disabled, 1 dex"
+        );
+        assert_eq!(
+            counts.take_summary(),
+            "0 no liquidity, 0 unsupported, 0 disabled, 0 dex"
+        );
+    }
+
+    #[test]
+    fn skipped_opportunities_are_kept_per_reason() {
+        let counts = OpportunityCounts::new();
+        counts.record_submitted(1, 5_000);
+        counts.record_skipped(2, SkipReason::Floor, 400);
+        counts.record_skipped(3, SkipReason::InflightCap, 90_000);
+
+        let record = counts.snapshot(100, 160);
+        assert_eq!(
+            (record.detected, record.submitted, record.landed),
+            (3, 1, 0)
+        );
+        assert_eq!(
+            skipped_summary(&record),
+            "floor 1 (profit 400), cooldown 0 (profit 0), inflight_cap 1 (profit 90000), \
+             blacklist 0 (profit 0), expired 0 (profit 0)"
+        );
+        // snapshots don't reset the session counts
+        assert_eq!(counts.snapshot(100, 220).detected, 3);
+        assert!(counts.snapshot(100, 220).same_counts(&record));
+    }
+
+    #[test]
+    fn a_quote_found_again_counts_once() {
+        let counts = OpportunityCounts::new();
+        // the next passes find the same route at the same profit
+        for _ in 0..3 {
+            counts.record_skipped(1, SkipReason::Floor, 400);
+        }
+        // the pools moved, a new quote
+        counts.record_skipped(1, SkipReason::Floor, 450);
+        // a submission counts even when its quote was skipped before
+        counts.record_skipped(2, SkipReason::InflightCap, 90_000);
+        counts.record_submitted(2, 90_000);
+
+        let record = counts.snapshot(100, 160);
+        assert_eq!(
+            (record.detected, record.submitted, record.landed),
+            (3, 1, 0)
+        );
+        let floor = &record.skipped["floor"];
+        assert_eq!((floor.count, floor.profit), (2, 850));
+        assert_eq!(record.skipped["inflight_cap"].count, 1);
+    }
+
+    #[test]
+    fn clock_lag_counts_slots_since_the_timestamp() {
+        assert_eq!(clock_lag_slots(1_000, 1_000), 0);
+        assert_eq!(clock_lag_slots(1_000, 1_060), 150);
+        // a clock stamped slightly ahead of the local time isn't behind
+        assert_eq!(clock_lag_slots(1_002, 1_000), 0);
+    }
+
+    #[test]
+    fn deepest_usable_pool_sets_the_rate() {
+        assert_eq!(
+            deepest_rate([(10.0, 2.0), (500.0, 3.0), (50.0, 4.0)]),
+            Some(3.0)
+        );
+        assert_eq!(
+            deepest_rate([(10.0, 2.0), (500.0, f64::INFINITY)]),
+            Some(2.0)
+        );
+        assert_eq!(deepest_rate([(500.0, 0.0), (10.0, f64::NAN)]), None);
+        assert_eq!(deepest_rate(Vec::<(f64, f64)>::new()), None);
+    }
+}
@@ -1,9 +1,11 @@
-use crate::{global, instructions};
+use crate::{global, instructions, streaming, system_program, token_2022_program, token_program};
 use anchor_client::{
     solana_client::nonblocking::rpc_client::RpcClient,
     solana_sdk::{
         address_lookup_table::{AddressLookupTableAccount, state::AddressLookupTable},
-        commitment_config::CommitmentLevel,
+        commitment_config::{CommitmentConfig, CommitmentLevel},
+        hash::Hash,
+        nonce::state::{Data as NonceData, State as NonceState, Versions as NonceVersions},
         pubkey::Pubkey,
         signature::Keypair,
         signer::Signer,
@@ -14,8 +16,10 @@ use futures::future::try_join_all;
 use spl_token::solana_program::program_pack::Pack;
 use spl_token::state::Account as TokenAccount;
 use std::sync::Arc;
+use tracing::info;
 
 pub mod send;
+pub mod temp_ata;
 
 pub async fn get_token_amount(rpc_client: &RpcClient, token_account: &Pubkey) -> Result<u64> {
     let account_info = rpc_client.get_account(&token_account).await?;
@@ -74,6 +78,29 @@ pub async fn fetch_alt_account(
     Ok(alt_account)
 }
 
+/// State of an initialized durable nonce account.
+pub async fn get_nonce_data(nonce_account: &Pubkey) -> Result<NonceData> {
+    let rpc_client = global::get_rpc_client();
+    let account = rpc_client.get_account(nonce_account).await?;
+    if account.owner != system_program() {
+        return Err(anyhow!("{} is not a nonce account", nonce_account));
+    }
+    let versions: NonceVersions = bincode::deserialize(&account.data)?;
+
+    match versions.state() {
+        NonceState::Initialized(data) => Ok(data.clone()),
+        NonceState::Uninitialized => Err(anyhow!(
+            "Nonce account {} is not initialized",
+            nonce_account
+        )),
+    }
+}
+
+/// Current durable nonce stored in `nonce_account`, used as the transaction's blockhash.
+pub async fn get_nonce_hash(nonce_account: &Pubkey) -> Result<Hash> {
+    Ok(get_nonce_data(nonce_account).await?.blockhash())
+}
+
 pub async fn create_ata_token(mint: &Pubkey) -> Result<Pubkey> {
     let payer = global::get_keypair();
     create_ata_token_with_payer(payer, mint, Some(CommitmentLevel::Processed)).await
@@ -98,11 +125,21 @@ pub async fn create_ata_token_with_payer(
                 mint,
             )?;
 
-            if let Some(_) =
-                send::send_transaction_with_payer(payer, &[ix], Some(false), preflight_commitment)
-                    .await
-                    .ok()
+            // a blockhash below the preflight commitment may not be found by the preflight
+            let blockhash_commitment = CommitmentConfig {
+                commitment: preflight_commitment.unwrap_or(CommitmentLevel::Processed),
+            };
+            if let Some(_) = send::send_transaction_with_payer(
+                payer,
+                &[ix],
+                Some(false),
+                preflight_commitment,
+                blockhash_commitment,
+            )
+            .await
+            .ok()
             {
+                temp_ata::record_created(ata, *mint);
                 return Ok(ata);
             } else {
                 return Err(anyhow!("Can't create ata {} token", ata));
@@ -113,6 +150,18 @@ pub async fn create_ata_token_with_payer(
     Ok(ata)
 }
 
+/// Create the wallet's ATA for each mint that doesn't have one yet.
+pub async fn create_missing_atas(mints: &[Pubkey]) -> Result<()> {
+    for mint in mints {
+        if check_ata_token(mint).await? {
+            continue;
+        }
+        let ata = create_ata_token(mint).await?;
+        info!("Created ATA {} - mint {}", ata, mint);
+    }
+    Ok(())
+}
+
 pub async fn check_ata_token(mint: &Pubkey) -> Result<bool> {
     let owner = global::get_pubkey();
     let ata = get_associated_token_address(&owner, mint);
@@ -134,3 +183,16 @@ pub fn get_ata_token_address(wallet: &Pubkey, mint: &Pubkey, program: &Pubkey) -
         wallet, mint, program,
     )
 }
+
+/// Token program owning `mint` according to the cached mint account, classic token by default.
+pub fn mint_token_program(mint: &Pubkey) -> Pubkey {
+    match streaming::global_data::get_mint_account(mint) {
+        Some(account) if account.owner == token_2022_program() => token_2022_program(),
+        _ => token_program(),
+    }
+}
+
+/// ATA of `wallet` for `mint`, derived under the token program that owns the mint.
+pub fn get_user_token_address(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_ata_token_address(wallet, mint, &mint_token_program(mint))
+}
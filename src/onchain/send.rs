@@ -2,19 +2,26 @@ use std::sync::Arc;
 
 use crate::global;
 use anchor_client::{
-    solana_client::rpc_config::RpcSendTransactionConfig,
+    solana_client::rpc_config::{
+        RpcSendTransactionConfig, RpcSimulateTransactionAccountsConfig,
+        RpcSimulateTransactionConfig,
+    },
     solana_sdk::{
+        account::Account,
         address_lookup_table::AddressLookupTableAccount,
         commitment_config::{CommitmentConfig, CommitmentLevel},
         hash::Hash,
         instruction::Instruction,
         message::{VersionedMessage, v0},
+        pubkey::Pubkey,
         signature::{Keypair, Signature},
         signer::Signer,
         transaction::{Transaction, VersionedTransaction},
     },
 };
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use spl_token::solana_program::program_pack::Pack;
+use spl_token::state::Account as TokenAccount;
 
 pub async fn send_arb_tx(
     blockhash: Hash,
@@ -46,23 +53,91 @@ pub async fn send_arb_tx(
     Ok(signature)
 }
 
+/// Simulate the arb transaction and return what `token_account` holds afterwards, `None`
+/// when the account doesn't exist after it (a temporary wSOL account the route closes).
+/// `replace_blockhash` has to be off for durable nonce transactions, the nonce advance
+/// checks it.
+pub async fn simulate_arb_tx(
+    blockhash: Hash,
+    replace_blockhash: bool,
+    instructions: &[Instruction],
+    alt_accounts: &[AddressLookupTableAccount],
+    token_account: &Pubkey,
+) -> Result<Option<u64>> {
+    let payer = global::get_keypair();
+    let wallet = global::get_pubkey();
+    let message = v0::Message::try_compile(&wallet, instructions, &alt_accounts, blockhash)?;
+    let versioned_tx = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&*payer])?;
+
+    let rpc = global::get_rpc_client();
+    let result = rpc
+        .simulate_transaction_with_config(
+            &versioned_tx,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: replace_blockhash,
+                commitment: Some(CommitmentConfig::processed()),
+                accounts: Some(RpcSimulateTransactionAccountsConfig {
+                    encoding: None,
+                    addresses: vec![token_account.to_string()],
+                }),
+                ..Default::default()
+            },
+        )
+        .await?
+        .value;
+
+    if let Some(err) = result.err {
+        return Err(anyhow!("Simulation failed: {}", err));
+    }
+
+    let account = result
+        .accounts
+        .and_then(|accounts| accounts.into_iter().next().flatten())
+        .and_then(|account| account.decode::<Account>());
+    match account {
+        Some(account) => {
+            // token-2022 accounts carry extensions after the base layout
+            let data = account.data.get(..TokenAccount::LEN).ok_or_else(|| {
+                anyhow!("Simulated account {} is not a token account", token_account)
+            })?;
+            let token_account = TokenAccount::unpack_from_slice(data)
+                .map_err(|e| anyhow!("Failed to unpack simulated token account: {}", e))?;
+            Ok(Some(token_account.amount))
+        }
+        None => Ok(None),
+    }
+}
+
 pub async fn send_transaction(
     instructions: &[Instruction],
     skip_preflight: Option<bool>,
+    blockhash_commitment: CommitmentConfig,
 ) -> Result<Signature> {
     let payer = global::get_keypair();
-    send_transaction_with_payer(payer, instructions, skip_preflight, Some(CommitmentLevel::Processed)).await
+    send_transaction_with_payer(
+        payer,
+        instructions,
+        skip_preflight,
+        Some(CommitmentLevel::Processed),
+        blockhash_commitment,
+    )
+    .await
 }
 
+/// `blockhash_commitment` trades latency for durability: a processed blockhash is the newest
+/// but may belong to a fork that gets dropped, a confirmed one is a slot or two older and
+/// survives. Preflight at a commitment above the blockhash's can fail with blockhash not found.
 pub async fn send_transaction_with_payer(
     payer: Arc<Keypair>,
     instructions: &[Instruction],
     skip_preflight: Option<bool>,
-    preflight_commitment: Option<CommitmentLevel>
+    preflight_commitment: Option<CommitmentLevel>,
+    blockhash_commitment: CommitmentConfig,
 ) -> Result<Signature> {
     let rpc_client = global::get_rpc_client();
     let (recent, _) = rpc_client
-        .get_latest_blockhash_with_commitment(CommitmentConfig::processed())
+        .get_latest_blockhash_with_commitment(blockhash_commitment)
         .await?;
     let tx =
         Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &[&*payer], recent);
@@ -0,0 +1,156 @@
+use super::send;
+use crate::{arb::ata_worker::AtaWorker, global, pool_index, wsol_mint};
+use anchor_client::solana_sdk::{
+    commitment_config::CommitmentConfig, instruction::Instruction, pubkey::Pubkey,
+};
+use anyhow::Result;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use spl_token::solana_program::program_pack::Pack;
+use spl_token::state::Account as TokenAccount;
+use std::collections::HashSet;
+use tokio::time::Duration;
+use tracing::{info, warn};
+
+// closes per transaction, each only references the account, the wallet and the token program
+const CLOSES_PER_TX: usize = 8;
+
+// ATA -> mint, for every ATA this session created
+static CREATED: Lazy<DashMap<Pubkey, Pubkey>> = Lazy::new(DashMap::new);
+
+/// Remember an ATA the bot created, only these are ever closed. Routes record the ATAs they
+/// create inline when built, those whose transaction never landed are dropped as missing.
+pub fn record_created(ata: Pubkey, mint: Pubkey) {
+    CREATED.insert(ata, mint);
+}
+
+/// Created ATAs that may be closed: never the base mints or wSOL, and with `only_unused` not
+/// while an indexed pool still trades the mint, it would only be created again.
+fn candidates(
+    created: &[(Pubkey, Pubkey)],
+    kept_mints: &HashSet<Pubkey>,
+    only_unused: bool,
+    in_use: impl Fn(&Pubkey) -> bool,
+) -> Vec<(Pubkey, Pubkey)> {
+    created
+        .iter()
+        .filter(|(_, mint)| !kept_mints.contains(mint))
+        .filter(|(_, mint)| !only_unused || !in_use(mint))
+        .copied()
+        .collect()
+}
+
+/// Close the empty ATAs the bot created for intermediate mints, returning their rent to the
+/// wallet and marking them missing for the ATA worker. Returns how many were closed.
+pub async fn close_empty(only_unused: bool) -> Result<usize> {
+    let mut kept_mints: HashSet<Pubkey> = global::get_base_mints()?.into_iter().collect();
+    kept_mints.insert(wsol_mint());
+
+    let created: Vec<(Pubkey, Pubkey)> = CREATED
+        .iter()
+        .map(|entry| (*entry.key(), *entry.value()))
+        .collect();
+    let candidates = candidates(&created, &kept_mints, only_unused, |mint| {
+        !pool_index::find_by_mint(mint).is_empty()
+    });
+    if candidates.is_empty() {
+        return Ok(0);
+    }
+
+    let owner = global::get_pubkey();
+    let rpc = global::get_rpc_client();
+    let atas: Vec<Pubkey> = candidates.iter().map(|(ata, _)| *ata).collect();
+    let mut closable: Vec<(Pubkey, Instruction)> = Vec::new();
+    for chunk in atas.chunks(100) {
+        let accounts = rpc.get_multiple_accounts(chunk).await?;
+        for (ata, account) in chunk.iter().zip(accounts) {
+            let Some(account) = account else {
+                // closed elsewhere or never created, nothing left to reclaim
+                forget(ata);
+                continue;
+            };
+            let empty = TokenAccount::unpack(&account.data)
+                .map(|token_account| token_account.amount == 0)
+                .unwrap_or(false);
+            if !empty {
+                continue;
+            }
+
+            let ix =
+                spl_token::instruction::close_account(&account.owner, ata, &owner, &owner, &[])?;
+            closable.push((*ata, ix));
+        }
+    }
+
+    let mut closed = 0;
+    for batch in closable.chunks(CLOSES_PER_TX) {
+        let instructions: Vec<Instruction> = batch.iter().map(|(_, ix)| ix.clone()).collect();
+        match send::send_transaction(&instructions, Some(false), CommitmentConfig::processed())
+            .await
+        {
+            Ok(signature) => {
+                for (ata, _) in batch {
+                    forget(ata);
+                }
+                closed += batch.len();
+                info!("Closed {} empty ATAs in {}", batch.len(), signature);
+            }
+            Err(e) => warn!("Failed to close {} empty ATAs: {}", batch.len(), e),
+        }
+    }
+
+    Ok(closed)
+}
+
+// routes must create the ATA again before using it
+fn forget(ata: &Pubkey) {
+    if let Some((_, mint)) = CREATED.remove(ata) {
+        AtaWorker::set_ata_state(mint, false);
+    }
+}
+
+/// Periodically close created ATAs no indexed pool trades anymore, `delay` of 0 leaves them
+/// for the cleanup on shutdown.
+pub fn start_cleanup(delay: u64) {
+    if delay == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(delay)).await;
+            if let Err(e) = close_empty(true).await {
+                warn!("Temporary ATA cleanup failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_base_mints_and_mints_in_use() {
+        let (base, traded, unused) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let created: Vec<(Pubkey, Pubkey)> = [base, traded, unused]
+            .iter()
+            .map(|mint| (Pubkey::new_unique(), *mint))
+            .collect();
+        let kept_mints = HashSet::from([base]);
+        let in_use = |mint: &Pubkey| *mint == traded;
+
+        let mints = |only_unused| -> Vec<Pubkey> {
+            candidates(&created, &kept_mints, only_unused, in_use)
+                .iter()
+                .map(|(_, mint)| *mint)
+                .collect()
+        };
+        assert_eq!(mints(true), vec![unused]);
+        assert_eq!(mints(false), vec![traded, unused]);
+    }
+}
@@ -0,0 +1,47 @@
+use crate::{global, onchain};
+use tokio::time::Duration;
+use tracing::{error, info};
+
+pub async fn balance_refresher(refresh_interval: Duration) {
+    info!("starting base mint balance refresher");
+
+    let wallet = global::get_pubkey();
+    let base_mint = global::get_base_mint();
+
+    loop {
+        tokio::time::sleep(refresh_interval).await;
+
+        match onchain::get_ata_token_amount(&wallet, &base_mint).await {
+            std::result::Result::Ok(amount) => {
+                let previous = global::get_base_mint_amount();
+                global::set_base_mint_amount(amount);
+                if amount != previous {
+                    info!("Base mint balance {} -> {}", previous, amount);
+                }
+            }
+            Err(e) => {
+                error!("Failed to refresh base mint balance: {:?}", e);
+            }
+        }
+
+        if global::wraps_native_sol() {
+            match global::get_rpc_client().get_balance(&wallet).await {
+                std::result::Result::Ok(lamports) => global::set_native_lamports(lamports),
+                Err(e) => error!("Failed to refresh native SOL balance: {:?}", e),
+            }
+        }
+    }
+}
+
+/// Keep the cached base mint balance, and the native one when it is wrapped, fresh. `delay` of
+/// 0 keeps the startup values.
+pub fn start_balance_refresher(delay: u64) {
+    if delay == 0 {
+        return;
+    }
+
+    let refresh_interval = Duration::from_secs(delay);
+    tokio::spawn(async move {
+        balance_refresher(refresh_interval).await;
+    });
+}
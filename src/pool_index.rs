@@ -1,13 +1,22 @@
 use crate::{
-    arb::{Hop, PoolType, Route, route::HopVecExt},
+    arb::{
+        Hop, PoolType, Route,
+        route::{HopVecExt, first_hop_allowed},
+    },
     global,
     streaming::{self, AccountDataType, global_data},
-    token_program, wsol_mint,
+    token_program, watcher, wsol_mint,
 };
 use anchor_client::solana_sdk::pubkey::Pubkey;
 use dashmap::DashMap;
 use once_cell::sync::Lazy;
-use std::{collections::HashSet, str::FromStr, sync::Arc};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+};
+use tracing::warn;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum TokenPoolType {
@@ -22,6 +31,23 @@ pub enum TokenPoolType {
     Solfi,
 }
 
+impl TokenPoolType {
+    /// Name of the pool's DEX as in `watcher.programs`.
+    pub fn dex_name(&self) -> &'static str {
+        match self {
+            TokenPoolType::Dlmm => "meteora_dlmm",
+            TokenPoolType::Dammv2 => "meteora_damm",
+            TokenPoolType::PumpAmm => "pumpfun",
+            TokenPoolType::RaydiumAmm => "raydium_amm",
+            TokenPoolType::RaydiumCpmm => "raydium_cpmm",
+            TokenPoolType::RaydiumClmm => "raydium_clmm",
+            TokenPoolType::Whirlpool => "whirlpool",
+            TokenPoolType::Vertigo => "vertigo",
+            TokenPoolType::Solfi => "solfi",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TokenPool {
     pub pool_type: TokenPoolType,
@@ -153,6 +179,12 @@ impl PoolIndex {
             return false;
         }
 
+        // a pool trading a mint against itself would be a self-cycle in the route graph
+        if pool.mint_a == pool.mint_b {
+            warn!("Skip pool {}: identical mints {}", pool_key, pool.mint_a);
+            return false;
+        }
+
         let arc_pool = Arc::new(pool.clone());
         self.by_pool.insert(pool_key, arc_pool);
         self.by_mint.entry(pool.mint_a).or_default().push(pool_key);
@@ -211,8 +243,12 @@ impl PoolIndex {
 
     fn _generate_routes(&self) -> Vec<Route> {
         let base_mint: Pubkey = *global::get_base_mint().as_ref();
-        let bot_config = &global::get_config().bot;
+        let config = global::get_config();
+        let bot_config = &config.bot;
         let max_hops: usize = bot_config.max_hops as usize;
+        let route_direction = bot_config.route_direction;
+        let base_mints = global::get_base_mints().unwrap_or_else(|_| vec![base_mint]);
+        let allow_first_hop = |to: &Pubkey| first_hop_allowed(route_direction, to, &base_mints);
 
         if max_hops == 0 {
             return Vec::new();
@@ -227,6 +263,32 @@ impl PoolIndex {
         let mut used_pools: HashSet<Pubkey> = HashSet::new();
         let mut path: Vec<Hop> = Vec::with_capacity(max_hops);
         let mut seen_signatures: HashSet<u64> = HashSet::new();
+        let mut tvl_cache: HashMap<(Pubkey, Pubkey), f64> = HashMap::new();
+
+        // deep pools first, so routes through them are generated before the budget runs out
+        fn ranked_pools(
+            cur_mint: Pubkey,
+            pool_keys: &[Pubkey],
+            by_pool: &DashMap<Pubkey, Arc<TokenPool>>,
+            tvl_cache: &mut HashMap<(Pubkey, Pubkey), f64>,
+        ) -> Vec<Pubkey> {
+            let mut ranked: Vec<(Pubkey, f64)> = pool_keys
+                .iter()
+                .map(|pool_key| {
+                    let tvl = *tvl_cache.entry((*pool_key, cur_mint)).or_insert_with(|| {
+                        by_pool
+                            .get(pool_key)
+                            .and_then(|pool| pool.to_pool_type())
+                            .map(|pool_type| pool_type.estimated_tvl(&cur_mint))
+                            .unwrap_or(0.0)
+                    });
+                    (*pool_key, tvl)
+                })
+                .collect();
+
+            ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+            ranked.into_iter().map(|(pool_key, _)| pool_key).collect()
+        }
 
         fn dfs(
             cur_mint: Pubkey,
@@ -238,7 +300,9 @@ impl PoolIndex {
             path: &mut Vec<Hop>,
             routes: &mut Vec<Route>,
             seen_signatures: &mut HashSet<u64>,
+            tvl_cache: &mut HashMap<(Pubkey, Pubkey), f64>,
             base_mint: Pubkey,
+            allow_first_hop: &dyn Fn(&Pubkey) -> bool,
         ) {
             if depth > 0 && cur_mint == base_mint {
                 if depth <= max_hops {
@@ -262,8 +326,10 @@ impl PoolIndex {
             let Some(pool_keys_guard) = by_mint.get(&cur_mint) else {
                 return;
             };
+            let pool_keys: Vec<Pubkey> = pool_keys_guard.clone();
+            drop(pool_keys_guard);
 
-            for pool_key in pool_keys_guard.iter() {
+            for pool_key in ranked_pools(cur_mint, &pool_keys, by_pool, tvl_cache).iter() {
                 if used_pools.contains(pool_key) {
                     continue;
                 }
@@ -277,6 +343,10 @@ impl PoolIndex {
                     continue;
                 };
 
+                if depth == 0 && !allow_first_hop(&next_mint) {
+                    continue;
+                }
+
                 used_pools.insert(p.pool);
                 path.push(Hop {
                     from: cur_mint,
@@ -296,7 +366,9 @@ impl PoolIndex {
                     path,
                     routes,
                     seen_signatures,
+                    tvl_cache,
                     base_mint,
+                    allow_first_hop,
                 );
 
                 // backtrack
@@ -315,7 +387,9 @@ impl PoolIndex {
             &mut path,
             &mut routes,
             &mut seen_signatures,
+            &mut tvl_cache,
             base_mint,
+            &allow_first_hop,
         );
 
         routes
@@ -348,6 +422,19 @@ pub fn find_by_pair(mint_a: &Pubkey, mint_b: &Pubkey) -> Vec<Pubkey> {
         .unwrap_or_default()
 }
 
+/// Every indexed pool trading `mint_a` against `mint_b`, across all DEXes and in either order.
+pub fn pools_for_pair(mint_a: &Pubkey, mint_b: &Pubkey) -> Vec<(Pubkey, TokenPoolType)> {
+    find_by_pair(mint_a, mint_b)
+        .into_iter()
+        .filter_map(|pool_key| {
+            POOL_INDEX
+                .by_pool
+                .get(&pool_key)
+                .map(|pool| (pool_key, pool.pool_type))
+        })
+        .collect()
+}
+
 pub fn get(pool: &Pubkey) -> Option<Arc<TokenPool>> {
     POOL_INDEX.by_pool.get(pool).map(|v| v.clone())
 }
@@ -393,6 +480,14 @@ pub fn count_invalid_pools() -> i32 {
     invalid_count
 }
 
+/// Pools ready to quote, indexed with their state loaded, and pools still warming up: indexed
+/// while their state loads, or discovered and queued to be loaded.
+pub fn readiness() -> (usize, usize) {
+    let loading = count_invalid_pools() as usize;
+    let ready = pool_count().saturating_sub(loading);
+    (ready, loading + watcher::pool_queue_depth())
+}
+
 pub fn get_relevent_pools(pool_pk: &Pubkey) -> Vec<Arc<TokenPool>> {
     let mut pools: Vec<Arc<TokenPool>> = vec![];
     if let Some(pool) = get(pool_pk) {
@@ -442,3 +537,27 @@ pub fn is_reach_max() -> bool {
     let max_routes: usize = watcher_config.max_routes as usize;
     count() > max_pools || routes_count() > max_routes
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_pool_with_identical_mints() {
+        let index = PoolIndex::new();
+        let mint = Pubkey::new_unique();
+        let pool = TokenPool {
+            pool_type: TokenPoolType::RaydiumCpmm,
+            mint_a: mint,
+            mint_b: mint,
+            pool: Pubkey::new_unique(),
+        };
+
+        assert!(!index.insert(pool.clone()));
+        assert!(!index.by_pool.contains_key(&pool.pool));
+        assert!(index.by_mint.get(&mint).is_none());
+        assert!(index.by_pair.is_empty());
+        assert!(index.routes.is_empty());
+        assert!(index.route_by_mint.is_empty());
+    }
+}
@@ -86,3 +86,48 @@ checked_impl!(i128, u32);
 checked_impl!(usize, u32);
 checked_impl!(U256, usize);
 checked_impl!(U512, usize);
+
+pub trait SafePow: Sized {
+    fn safe_pow(self, exp: u32) -> Result<Self>;
+}
+
+macro_rules! checked_pow_impl {
+    ($($t:ty),*) => {
+        $(
+            impl SafePow for $t {
+                #[inline(always)]
+                fn safe_pow(self, exp: u32) -> Result<$t> {
+                    match self.checked_pow(exp) {
+                        Some(result) => Ok(result),
+                        None => Err(anyhow!("Math overflow")),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+checked_pow_impl!(u16, i32, u32, u64, i64, u128, i128, usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_pow_squares_largest_u64_in_u128() {
+        let base = u64::MAX as u128;
+        assert_eq!(base.safe_pow(2).unwrap(), base * base);
+    }
+
+    #[test]
+    fn safe_pow_errors_past_u128_square_boundary() {
+        assert!((1u128 << 64).safe_pow(2).is_err());
+        assert!(u128::MAX.safe_pow(2).is_err());
+    }
+
+    #[test]
+    fn safe_pow_handles_trivial_exponents() {
+        assert_eq!(u128::MAX.safe_pow(1).unwrap(), u128::MAX);
+        assert_eq!(u128::MAX.safe_pow(0).unwrap(), 1);
+    }
+}
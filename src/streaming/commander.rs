@@ -1,6 +1,7 @@
+use anyhow::Result;
 use std::time::Instant;
 use tokio::sync::mpsc;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use super::*;
 use crate::{pool_index, streaming::watcher::DataWatcher};
@@ -72,6 +73,11 @@ pub async fn run_command_processor(
             }
             WatcherCommand::GetMetrics => {
                 print_metrics(&watcher, start_time, command_count);
+                if let Some(path) = &global::get_config().grpc.subscriptions_path {
+                    if let Err(e) = write_subscriptions(&watcher, path) {
+                        warn!("Failed to write subscriptions to {}: {}", path, e);
+                    }
+                }
             }
             WatcherCommand::Stop => {
                 info!("Received stop command");
@@ -86,6 +92,15 @@ pub async fn run_command_processor(
     info!("Commander loop stopped after {} commands", command_count);
 }
 
+// written to a temporary file first so readers never see a partial snapshot
+fn write_subscriptions(watcher: &DataWatcher, path: &str) -> Result<()> {
+    let snapshot = serde_json::to_vec_pretty(&watcher.get_subscriptions())?;
+    let tmp_path = format!("{}.tmp", path);
+    std::fs::write(&tmp_path, snapshot)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
 #[inline]
 fn print_metrics(watcher: &DataWatcher, start_time: Instant, command_count: u64) {
     let metrics = watcher.get_metrics();
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::str::FromStr;
 
 use super::*;
@@ -16,6 +17,20 @@ pub fn get_clock() -> Option<Clock> {
     }
 }
 
+/// Wait until the first clock update arrives, `None` if it doesn't within `timeout`.
+pub async fn wait_for_clock(timeout: Duration) -> Option<Clock> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if let Some(clock) = get_clock() {
+            return Some(clock);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return None;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
 pub fn get_account(pubkey: &Pubkey) -> Option<AccountDataType> {
     ACCOUNT_DATA.get(pubkey).map(|entry| entry.value().clone())
 }
@@ -24,6 +39,34 @@ pub fn get_mint_account(pubkey: &Pubkey) -> Option<Account> {
     MINT_DATA.get(pubkey).map(|entry| entry.value().clone())
 }
 
+#[inline]
+pub fn add_mint_account(pubkey: Pubkey, account: Account) {
+    MINT_DATA.insert(pubkey, account);
+}
+
+// mints.decimals, known without loading the mint account
+static CONFIGURED_DECIMALS: Lazy<HashMap<Pubkey, u8>> =
+    Lazy::new(global::get_configured_mint_decimals);
+
+pub fn get_mint_decimals(pubkey: &Pubkey) -> Option<u8> {
+    if let Some(decimals) = CONFIGURED_DECIMALS.get(pubkey) {
+        return Some(*decimals);
+    }
+
+    if let Some(account) = get_mint_account(pubkey) {
+        return price::read_decimals(&account.data);
+    }
+
+    // Mint accounts are streamed into ACCOUNT_DATA, promote them to the mint cache on first use
+    if let Some(AccountDataType::Account(account)) = get_account(pubkey) {
+        let decimals = price::read_decimals(&account.data)?;
+        add_mint_account(*pubkey, account);
+        return Some(decimals);
+    }
+
+    None
+}
+
 pub fn get_account_type(pubkey: &Pubkey) -> AccountTypeInfo {
     AccountTypeInfo::from_pubkey(pubkey)
 }
@@ -82,10 +125,32 @@ pub fn add_account_type(key: Pubkey, account_type: AccountTypeInfo) {
     _safe_insert_type(key, account_type);
 }
 
+/// Index an account loaded without a slot. Streamed data already stored for it is at least as
+/// fresh, so it is kept.
 #[inline]
 pub fn add_accounts(key: Pubkey, account: AccountDataType, account_type: AccountTypeInfo) {
     ACCOUNT_TYPE_MAP.insert(key, account_type);
-    ACCOUNT_DATA.insert(key, account);
+    if !ACCOUNT_SLOTS.contains_key(&key) {
+        ACCOUNT_DATA.insert(key, account);
+    }
+}
+
+/// Store `data` as of `slot`, or return the newer slot already stored when the update arrived
+/// late and must not overwrite fresher data.
+pub fn store_account(pubkey: Pubkey, data: AccountDataType, slot: u64) -> Option<u64> {
+    // the slot entry stays locked until the data is in, so two writers can't interleave
+    let mut recorded = ACCOUNT_SLOTS.entry(pubkey).or_insert(0);
+    if slot < *recorded {
+        return Some(*recorded);
+    }
+    *recorded = slot;
+    ACCOUNT_DATA.insert(pubkey, data);
+    None
+}
+
+/// Stop tracking the slot of an account that is no longer streamed.
+pub fn forget_account_slot(pubkey: &Pubkey) {
+    ACCOUNT_SLOTS.remove(pubkey);
 }
 
 pub fn account_count() -> usize {
@@ -94,6 +159,7 @@ pub fn account_count() -> usize {
 
 pub fn clear_all() {
     ACCOUNT_DATA.clear();
+    ACCOUNT_SLOTS.clear();
     ACCOUNT_TYPE_MAP.clear();
 }
 
@@ -109,11 +175,70 @@ pub fn nonexists_pubkeys(pubkeys: &[Pubkey]) -> Vec<String> {
 }
 
 #[inline]
-pub fn update_price(pubkey: &Pubkey, from_mint: Pubkey, atob: f64) {
-    PRICE_DATA.insert(*pubkey, (from_mint, atob));
+pub fn update_price(pubkey: &Pubkey, from_mint: Pubkey, to_mint: Pubkey, atob: f64) {
+    let human_atob = match (get_mint_decimals(&from_mint), get_mint_decimals(&to_mint)) {
+        (Some(base_decimals), Some(quote_decimals)) => {
+            price::human(atob, base_decimals, quote_decimals)
+        }
+        _ => 0f64,
+    };
+    let previous = PRICE_DATA.insert(*pubkey, (from_mint, atob, human_atob));
+    if previous.map_or(true, |(_, old_atob, _)| old_atob != atob) {
+        price::socket::publish(pubkey, from_mint, to_mint, atob, human_atob);
+    }
+}
+
+#[inline]
+pub fn remove_price(pubkey: &Pubkey) {
+    PRICE_DATA.remove(pubkey);
 }
 
 #[inline]
 pub fn get_price(pubkey: &Pubkey) -> Option<(Pubkey, f64)> {
-    PRICE_DATA.get(pubkey).map(|entry| entry.value().clone())
+    PRICE_DATA
+        .get(pubkey)
+        .map(|entry| (entry.value().0, entry.value().1))
+}
+
+#[inline]
+pub fn get_human_price(pubkey: &Pubkey) -> Option<(Pubkey, f64)> {
+    PRICE_DATA
+        .get(pubkey)
+        .map(|entry| (entry.value().0, entry.value().2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_updates_older_than_the_stored_slot() {
+        let store = |pubkey, slot| store_account(pubkey, AccountDataType::Empty, slot);
+        let pubkey = Pubkey::new_unique();
+
+        assert_eq!(store(pubkey, 100), None);
+        assert_eq!(store(pubkey, 100), None);
+        assert_eq!(store(pubkey, 99), Some(100));
+        assert_eq!(store(pubkey, 101), None);
+        assert_eq!(store(pubkey, 100), Some(101));
+        // tracked per account
+        assert_eq!(store(Pubkey::new_unique(), 1), None);
+
+        forget_account_slot(&pubkey);
+        assert!(!ACCOUNT_SLOTS.contains_key(&pubkey));
+        assert_eq!(store(pubkey, 50), None);
+    }
+
+    #[test]
+    fn slotless_loads_keep_streamed_data() {
+        let pubkey = Pubkey::new_unique();
+        store_account(pubkey, AccountDataType::Empty, 100);
+
+        add_accounts(
+            pubkey,
+            AccountDataType::Account(Account::default()),
+            AccountTypeInfo::Account,
+        );
+        assert!(matches!(get_account(&pubkey), Some(AccountDataType::Empty)));
+    }
 }
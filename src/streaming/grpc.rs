@@ -1,6 +1,7 @@
 use anyhow::{Result, anyhow};
 use dashmap::DashMap;
 use futures_util::StreamExt;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
@@ -33,6 +34,16 @@ impl Interceptor for TokenInterceptor {
     }
 }
 
+// an invalid or missing x-token is rejected with one of these codes
+fn is_auth_error(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<tonic::Status>().is_some_and(|status| {
+        matches!(
+            status.code(),
+            tonic::Code::Unauthenticated | tonic::Code::PermissionDenied
+        )
+    })
+}
+
 #[derive(Debug, Clone)]
 pub struct GrpcConfig {
     pub endpoint: String,
@@ -340,6 +351,22 @@ impl GrpcClient {
             .await
             {
                 Ok(()) => break,
+                Err(e) if is_auth_error(&e) => {
+                    // retrying with the same token can't succeed, leave the data to RPC polling
+                    let rpc_url = crate::global::get_rpc_client().url();
+                    error!(
+                        "gRPC authentication failed: {}. Check grpc.token in config.toml, \
+                         falling back to RPC polling of {} every {}ms",
+                        e,
+                        // the query string may hold an API key
+                        rpc_url.split('?').next().unwrap_or_default(),
+                        super::polling::POLLING_INTERVAL_MS
+                    );
+                    subscription_state
+                        .is_running
+                        .store(false, Ordering::Relaxed);
+                    break;
+                }
                 Err(e) => {
                     error!("Subscription failed: {}, retrying...", e);
                     tokio::time::sleep(Duration::from_millis(1000)).await;
@@ -395,7 +422,7 @@ impl GrpcClient {
 
                             // Update slot
                             if let Some(slot) = Self::extract_slot(&update) {
-                                subscription_state.last_update_slot.store(slot, Ordering::Relaxed);
+                                subscription_state.last_update_slot.fetch_max(slot, Ordering::Relaxed);
                             }
 
                             // Process immediately
@@ -410,7 +437,7 @@ impl GrpcClient {
                         }
                         Some(Err(e)) => {
                             error!("Stream error: {}", e);
-                            return Err(anyhow!("Stream error: {}", e));
+                            return Err(anyhow::Error::from(e).context("Stream error"));
                         }
                         None => {
                             warn!("Stream ended");
@@ -549,6 +576,41 @@ impl GrpcClient {
         }
     }
 
+    /// Accounts currently subscribed, including ones still waiting for the next batch flush.
+    pub fn subscribed_accounts(&self) -> Vec<String> {
+        let mut accounts: Vec<String> = self
+            .subscription_state
+            .accounts
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect();
+        accounts.sort_unstable();
+        accounts
+    }
+
+    /// Programs currently subscribed, including ones still waiting for the next batch flush.
+    pub fn subscribed_programs(&self) -> Vec<String> {
+        let mut programs: Vec<String> = self
+            .subscription_state
+            .programs
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect();
+        programs.sort_unstable();
+        programs
+    }
+
+    pub fn get_subscriptions(&self) -> SubscriptionSnapshot {
+        SubscriptionSnapshot {
+            last_update_slot: self
+                .subscription_state
+                .last_update_slot
+                .load(Ordering::Relaxed),
+            accounts: self.subscribed_accounts(),
+            programs: self.subscribed_programs(),
+        }
+    }
+
     pub fn force_immediate_update(&self) {
         self.flush_batch_now();
     }
@@ -564,6 +626,13 @@ impl GrpcClient {
     }
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionSnapshot {
+    pub last_update_slot: u64,
+    pub accounts: Vec<String>,
+    pub programs: Vec<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct SubscriptionMetrics {
     pub accounts_count: usize,
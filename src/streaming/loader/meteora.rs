@@ -41,10 +41,81 @@ impl MeteoraLoader {
     }
 }
 
+/// Side of the active bin the price has been moving towards lately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    // active_id falling, swaps for y
+    Left,
+    // active_id rising, swaps for x
+    Right,
+    Neutral,
+}
+
+// each active_id move adds a step towards its side and the score decays by a quarter per move,
+// three moves the same way make a trend and one move back ends it
+const TREND_STEP: i32 = 4;
+const TREND_THRESHOLD: i32 = 8;
+
+#[derive(Debug, Clone, Copy)]
+struct ActiveIdTrend {
+    active_id: i32,
+    score: i32,
+}
+
+impl ActiveIdTrend {
+    fn record(&mut self, active_id: i32) {
+        let step = (active_id - self.active_id).signum();
+        if step != 0 {
+            self.score = self.score - self.score / 4 + step * TREND_STEP;
+            self.active_id = active_id;
+        }
+    }
+
+    fn direction(&self) -> Direction {
+        if self.score > TREND_THRESHOLD {
+            Direction::Right
+        } else if self.score < -TREND_THRESHOLD {
+            Direction::Left
+        } else {
+            Direction::Neutral
+        }
+    }
+}
+
+// dlmm pool -> recent movement of its active bin
+static ACTIVE_ID_TRENDS: Lazy<DashMap<Pubkey, ActiveIdTrend>> = Lazy::new(DashMap::new);
+
+/// Track the active bin of a DLMM pool, called on every pool update.
+pub fn record_active_id(pool_address: Pubkey, active_id: i32) {
+    ACTIVE_ID_TRENDS
+        .entry(pool_address)
+        .and_modify(|trend| trend.record(active_id))
+        .or_insert(ActiveIdTrend {
+            active_id,
+            score: 0,
+        });
+}
+
+/// Side to preload an extra bin array on, the pool's address is enough since the keys are
+/// needed before its `MeteoraDlmmData` is assembled.
+pub fn preferred_preload_direction(pool_address: &Pubkey) -> Direction {
+    ACTIVE_ID_TRENDS
+        .get(pool_address)
+        .map_or(Direction::Neutral, |trend| trend.direction())
+}
+
+// bin arrays loaded on each side of the active bin, plus one on the trending side
+const DLMM_BIN_ARRAYS_PER_SIDE: u8 = 3;
+
 #[inline]
 pub fn get_dlmm_bin_array_keys(address: Pubkey, lb_pair: &LbPair) -> Result<Vec<Pubkey>> {
-    let left_bins = get_bin_array_pubkeys_for_swap(address, lb_pair, None, true, 3)?;
-    let right_bins = get_bin_array_pubkeys_for_swap(address, lb_pair, None, false, 3)?;
+    let (left, right) = match preferred_preload_direction(&address) {
+        Direction::Left => (DLMM_BIN_ARRAYS_PER_SIDE + 1, DLMM_BIN_ARRAYS_PER_SIDE),
+        Direction::Right => (DLMM_BIN_ARRAYS_PER_SIDE, DLMM_BIN_ARRAYS_PER_SIDE + 1),
+        Direction::Neutral => (DLMM_BIN_ARRAYS_PER_SIDE, DLMM_BIN_ARRAYS_PER_SIDE),
+    };
+    let left_bins = get_bin_array_pubkeys_for_swap(address, lb_pair, None, true, left.into())?;
+    let right_bins = get_bin_array_pubkeys_for_swap(address, lb_pair, None, false, right.into())?;
 
     Ok(util::concat(&left_bins, &right_bins))
 }
@@ -65,3 +136,30 @@ pub fn get_bin_arrays(pubkeys: &[Pubkey]) -> Option<HashMap<Pubkey, BinArray>> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trend_needs_repeated_moves_and_ends_on_a_reversal() {
+        let mut trend = ActiveIdTrend {
+            active_id: 100,
+            score: 0,
+        };
+
+        trend.record(101);
+        trend.record(101);
+        trend.record(102);
+        assert_eq!(trend.direction(), Direction::Neutral);
+        trend.record(105);
+        assert_eq!(trend.direction(), Direction::Right);
+
+        trend.record(104);
+        assert_eq!(trend.direction(), Direction::Neutral);
+        for active_id in (98..104).rev() {
+            trend.record(active_id);
+        }
+        assert_eq!(trend.direction(), Direction::Left);
+    }
+}
@@ -3,8 +3,15 @@ use crate::{
     arb::{RaydiumAmmData, RaydiumClmmData, RaydiumCpmmData},
     dex::raydium::{amm, clmm, cpmm},
 };
+use dashmap::DashSet;
 use std::collections::VecDeque;
 
+// the current tick array plus the five a quote walks into
+const QUOTE_TICK_ARRAYS: i32 = 6;
+
+// pools whose bitmap extension fetch is in flight or done, a failed fetch is removed again
+static BITMAP_EXT_REQUESTED: Lazy<DashSet<Pubkey>> = Lazy::new(DashSet::new);
+
 pub struct RaydiumLoader;
 
 impl RaydiumLoader {
@@ -62,6 +69,11 @@ impl RaydiumLoader {
                 token_1_vault,
                 token_1_amount,
             };
+            // fees above a vault mean the vault update hasn't caught up with the pool one (or
+            // hasn't arrived yet), skip the pool until it does rather than quote a made-up reserve
+            if !pool_state.is_consistent_with(&vaults) {
+                return None;
+            }
 
             Some(RaydiumCpmmData {
                 pool_address: *pool_address,
@@ -78,23 +90,28 @@ impl RaydiumLoader {
         if let Some(AccountDataType::RaydiumClmmPool(pool_state)) =
             global_data::get_account(pool_address)
         {
-            let tick_array_bitmap_ext_op = get_bitmap_ext(pool_address);
-            if let Some(tick_array_bitmap_ext) = tick_array_bitmap_ext_op {
-                let left_ticks =
-                    get_tick_arrays(pool_address, &pool_state, &tick_array_bitmap_ext, false);
-                let right_ticks =
-                    get_tick_arrays(pool_address, &pool_state, &tick_array_bitmap_ext, true);
-
-                Some(RaydiumClmmData {
-                    pool_address: *pool_address,
-                    pool_state,
-                    tick_array_bitmap_ext,
-                    left_ticks,
-                    right_ticks,
-                })
-            } else {
-                None
-            }
+            let tick_array_bitmap_ext = match get_bitmap_ext(pool_address) {
+                Some(tick_array_bitmap_ext) => tick_array_bitmap_ext,
+                // away from the boundary a quote stays within the default bitmap
+                None if !near_bitmap_boundary(&pool_state) => {
+                    clmm::tick_array_bitmap_extension::TickArrayBitmapExtension::empty(
+                        *pool_address,
+                    )
+                }
+                None => return None,
+            };
+            let left_ticks =
+                get_tick_arrays(pool_address, &pool_state, &tick_array_bitmap_ext, false);
+            let right_ticks =
+                get_tick_arrays(pool_address, &pool_state, &tick_array_bitmap_ext, true);
+
+            Some(RaydiumClmmData {
+                pool_address: *pool_address,
+                pool_state,
+                tick_array_bitmap_ext,
+                left_ticks,
+                right_ticks,
+            })
         } else {
             None
         }
@@ -112,6 +129,56 @@ pub fn get_bitmap_ext(
     }
 }
 
+/// Whether a quote from the current tick can reach tick arrays outside the range the pool's
+/// default bitmap covers, which are only tracked in the bitmap extension account.
+pub fn near_bitmap_boundary(pool_state: &clmm::PoolState) -> bool {
+    let reach =
+        clmm::tick_array::TickArrayState::tick_count(pool_state.tick_spacing) * QUOTE_TICK_ARRAYS;
+    pool_state.is_overflow_default_tickarray_bitmap(vec![
+        pool_state.tick_current.saturating_sub(reach),
+        pool_state.tick_current.saturating_add(reach),
+    ])
+}
+
+/// Fetch and store the bitmap extension of a pool that moved near the default bitmap boundary
+/// without it, returns the account to subscribe to. Each pool has one fetch at a time, a failed
+/// one is retried on a later update.
+pub async fn load_bitmap_ext_if_needed(
+    pool_address: &Pubkey,
+    pool_state: &clmm::PoolState,
+) -> Option<Pubkey> {
+    if !near_bitmap_boundary(pool_state) || get_bitmap_ext(pool_address).is_some() {
+        return None;
+    }
+
+    let (bitmap_ext, _) = clmm::pda::derive_tick_array_bitmap_extension(pool_address).ok()?;
+    if !BITMAP_EXT_REQUESTED.insert(*pool_address) {
+        return None;
+    }
+    match clmm::util::fetch_bitmap_extension_state(global::get_rpc_client(), &bitmap_ext).await {
+        std::result::Result::Ok(bitmap_state) => {
+            global_data::add_accounts(
+                bitmap_ext,
+                AccountDataType::RaydiumTickArrayBitmapExt(bitmap_state),
+                AccountTypeInfo::RaydiumTickArrayBitmapExt,
+            );
+            info!(
+                "Loaded tick array bitmap extension {} for pool {} near the bitmap boundary",
+                bitmap_ext, pool_address
+            );
+            Some(bitmap_ext)
+        }
+        Err(e) => {
+            BITMAP_EXT_REQUESTED.remove(pool_address);
+            warn!(
+                "Failed to load tick array bitmap extension for pool {}: {}",
+                pool_address, e
+            );
+            None
+        }
+    }
+}
+
 #[inline]
 fn get_market_state(market: &Pubkey) -> Option<amm::serum::MarketState> {
     match global_data::get_account(market) {
@@ -135,11 +202,12 @@ fn get_tick_arrays(
     tick_array_bitmap_ext: &clmm::tick_array_bitmap_extension::TickArrayBitmapExtension,
     a_to_b: bool,
 ) -> VecDeque<clmm::tick_array::TickArrayState> {
-    let tick_pks = clmm::swap_util::get_cur_and_next_five_tick_array(
+    let tick_pks = clmm::swap_util::get_cur_and_next_tick_arrays(
         *pool_address,
         &pool_state,
         &tick_array_bitmap_ext,
         a_to_b,
+        global::get_clmm_tick_arrays(),
     );
     let mut tick_arrays = VecDeque::new();
     for tick_pk in tick_pks {
@@ -151,3 +219,30 @@ fn get_tick_arrays(
     }
     tick_arrays
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_state(tick_spacing: u16, tick_current: i32) -> clmm::PoolState {
+        let mut pool = clmm::PoolState::deserialize(&[0u8; clmm::POOL_ACCOUNT_LEN]).unwrap();
+        pool.tick_spacing = tick_spacing;
+        pool.tick_current = tick_current;
+        pool
+    }
+
+    #[test]
+    fn near_bitmap_boundary_when_a_quote_can_leave_the_default_bitmap() {
+        // at tick spacing 1 the default bitmap covers [-30720, 30720) and a quote reaches
+        // 6 arrays of 60 ticks, 360 ticks, either way
+        assert!(!near_bitmap_boundary(&pool_state(1, 0)));
+        assert!(!near_bitmap_boundary(&pool_state(1, 30_300)));
+        assert!(near_bitmap_boundary(&pool_state(1, 30_360)));
+        assert!(!near_bitmap_boundary(&pool_state(1, -30_360)));
+        assert!(near_bitmap_boundary(&pool_state(1, -30_361)));
+
+        // wider spacings cover proportionally more ticks
+        assert!(!near_bitmap_boundary(&pool_state(10, 300_000)));
+        assert!(near_bitmap_boundary(&pool_state(10, 303_600)));
+    }
+}
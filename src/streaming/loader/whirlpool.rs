@@ -15,20 +15,14 @@ impl WhirlpoolLoader {
         {
             let oracle = get_oracle(&pool_address);
             let tick_arrays =
-                whirlpool::util::get_tick_arrays_or_default(*pool_address, &pool_state).unwrap();
-            let ticks = get_tick_arrays(&pool_state, &tick_arrays);
-            let tick_data_op: Option<[(Pubkey, TickArray); 5]> = ticks.try_into().ok();
-            if let Some(tick_data) = tick_data_op {
-                Some(WhirlpoolData {
-                    pool_address: *pool_address,
-                    pool_state,
-                    oracle,
-                    tick_data,
-                })
-            } else {
-                println!("Failed to convert tick_arrays data");
-                None
-            }
+                whirlpool::util::tick_array_addresses(*pool_address, &pool_state).unwrap();
+            let tick_data = get_tick_arrays(&pool_state, &tick_arrays);
+            Some(WhirlpoolData {
+                pool_address: *pool_address,
+                pool_state,
+                oracle,
+                tick_data,
+            })
         } else {
             None
         }
@@ -47,35 +41,25 @@ fn get_oracle(pool_address: &Pubkey) -> Option<Oracle> {
 #[inline]
 fn get_tick_arrays(
     whirlpool: &whirlpool::state::Whirlpool,
-    pubkeys: &[Pubkey],
-) -> Vec<(Pubkey, TickArray)> {
-    let mut tick_arrays = Vec::with_capacity(pubkeys.len());
-    let tick_array_start_index = whirlpool::get_tick_array_start_tick_index(
-        whirlpool.tick_current_index,
-        whirlpool.tick_spacing,
-    );
-    let offset = whirlpool.tick_spacing as i32 * whirlpool::TICK_ARRAY_SIZE as i32;
-
-    let tick_array_indexes = [
-        tick_array_start_index,
-        tick_array_start_index + offset,
-        tick_array_start_index + offset * 2,
-        tick_array_start_index - offset,
-        tick_array_start_index - offset * 2,
-    ];
+    pubkeys: &[Pubkey; 5],
+) -> [(Pubkey, TickArray); 5] {
+    let tick_array_indexes = whirlpool::util::tick_array_start_indexes(whirlpool);
+    let loaded_tick_arrays = global::get_whirlpool_tick_arrays();
 
-    let mut index: usize = 0;
-    for pk in pubkeys {
-        if let Some(AccountDataType::WhirlpoolTickArray(tick_array)) = global_data::get_account(&pk)
-        {
-            tick_arrays.push((*pk, tick_array));
-        } else {
-            let tick_array = whirlpool::util::uninitialized_tick_array(tick_array_indexes[index]);
-            tick_arrays.push((*pk, tick_array));
+    std::array::from_fn(|slot| {
+        let pk = pubkeys[slot];
+        // a slot outside the configured range may still hold a stale array from before the
+        // price moved, quote it as uninitialized like the rpc loader does
+        if whirlpool::util::is_loaded_slot(slot, loaded_tick_arrays) {
+            if let Some(AccountDataType::WhirlpoolTickArray(tick_array)) =
+                global_data::get_account(&pk)
+            {
+                return (pk, tick_array);
+            }
         }
-
-        index += 1;
-    }
-
-    tick_arrays
+        (
+            pk,
+            whirlpool::util::uninitialized_tick_array(tick_array_indexes[slot]),
+        )
+    })
 }
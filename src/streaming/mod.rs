@@ -4,7 +4,7 @@ use crate::{
     config::Config,
     global, onchain,
     pool_index::{self, TokenPool},
-    dex::pumpfun::PumpAmmReader,
+    dex::pumpfun::{self, PumpAmmReader},
     streaming::{
         grpc::{GrpcClient, GrpcConfig},
         watcher::DataWatcher,
@@ -18,7 +18,7 @@ use anyhow::{Ok, Result};
 use dashmap::DashMap;
 use dlmm_interface::LbPairAccount;
 use once_cell::sync::Lazy;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use tokio::sync::mpsc;
 use tokio::time::Duration;
 use tracing::{info, warn};
@@ -49,7 +49,12 @@ static ACCOUNT_TYPE_MAP: once_cell::sync::Lazy<Arc<DashMap<Pubkey, AccountTypeIn
 static ACCOUNT_DATA: once_cell::sync::Lazy<Arc<DashMap<Pubkey, AccountDataType>>> =
     once_cell::sync::Lazy::new(|| Arc::new(DashMap::new()));
 
-static PRICE_DATA: once_cell::sync::Lazy<Arc<DashMap<Pubkey, (Pubkey, f64)>>> =
+// account -> slot of the last streamed update applied to ACCOUNT_DATA
+static ACCOUNT_SLOTS: once_cell::sync::Lazy<DashMap<Pubkey, u64>> =
+    once_cell::sync::Lazy::new(DashMap::new);
+
+// pool -> (from mint, raw atob price, decimals-adjusted atob price)
+static PRICE_DATA: once_cell::sync::Lazy<Arc<DashMap<Pubkey, (Pubkey, f64, f64)>>> =
     once_cell::sync::Lazy::new(|| Arc::new(DashMap::new()));
 
 static MINT_DATA: once_cell::sync::Lazy<Arc<DashMap<Pubkey, Account>>> =
@@ -62,6 +67,9 @@ pub static PK_TO_ALT: Lazy<Cache<Pubkey, Pubkey>> = once_cell::sync::Lazy::new(|
 pub static ALT_DATA: Lazy<Cache<Pubkey, AddressLookupTableAccount>> =
     once_cell::sync::Lazy::new(|| Cache::new());
 
+// lookup tables from bot.static_alts, loaded once at startup
+static STATIC_ALTS: OnceLock<Vec<AddressLookupTableAccount>> = OnceLock::new();
+
 const CLOCK_ACCOUNT: &str = "SysvarC1ock11111111111111111111111111111111";
 
 pub async fn start(conf: Config) -> Result<mpsc::UnboundedSender<WatcherCommand>> {
@@ -78,6 +86,9 @@ pub async fn start(conf: Config) -> Result<mpsc::UnboundedSender<WatcherCommand>
     if conf.grpc.enabled {
         watcher.start().await?;
         watcher.add_account(String::from(CLOCK_ACCOUNT));
+        // pump AMM fees are read from this account, streamed like the pools quoted with them
+        global_data::add_account_type(pumpfun::global_config(), AccountTypeInfo::PumpGlobalConfig);
+        watcher.add_account(pumpfun::global_config().to_string());
     }
 
     let (cmd_tx, cmd_rx) = mpsc::unbounded_channel::<WatcherCommand>();
@@ -106,13 +117,94 @@ pub fn retrieve_alt(mint: &Pubkey) -> Option<AddressLookupTableAccount> {
     }
 }
 
+/// Fetch and cache a per-pool lookup table, for `bot.alt_ttl_secs` when set.
 pub async fn store_lookup_table(alt_pk: &Pubkey) -> Result<()> {
+    let rpc_client = global::get_rpc_client();
+    let alt_accounts = onchain::fetch_alt_account(rpc_client, *alt_pk).await?;
+    match global::get_alt_ttl_secs() {
+        0 => ALT_DATA.forever(*alt_pk, alt_accounts),
+        ttl => ALT_DATA.set(*alt_pk, alt_accounts, Duration::from_secs(ttl)),
+    }
+    Ok(())
+}
+
+/// Fetch and cache a lookup table that is never extended, it is not refetched.
+pub async fn store_static_lookup_table(alt_pk: &Pubkey) -> Result<()> {
     let rpc_client = global::get_rpc_client();
     let alt_accounts = onchain::fetch_alt_account(rpc_client, *alt_pk).await?;
     ALT_DATA.forever(*alt_pk, alt_accounts);
     Ok(())
 }
 
+/// Refetch a cached lookup table now, e.g. right after extending it. Static tables stay
+/// cached forever, the others restart their TTL. When the fetch fails the cached table is kept
+/// for another TTL, otherwise it would expire and never be refreshed again.
+pub async fn refresh_alt(alt_pk: &Pubkey) -> Result<()> {
+    let cached = ALT_DATA.get(alt_pk);
+    let previous = cached.as_ref().map_or(0, |alt| alt.addresses.len());
+    if ALT_DATA.is_permanent(alt_pk) {
+        store_static_lookup_table(alt_pk).await?;
+    } else if let Err(e) = store_lookup_table(alt_pk).await {
+        let ttl = global::get_alt_ttl_secs();
+        if let Some(alt) = cached.filter(|_| ttl > 0) {
+            ALT_DATA.set(*alt_pk, alt, Duration::from_secs(ttl));
+        }
+        return Err(e);
+    }
+
+    let current = ALT_DATA.get(alt_pk).map_or(0, |alt| alt.addresses.len());
+    if current != previous {
+        info!(
+            "Lookup table {} refreshed, {} -> {} addresses",
+            alt_pk, previous, current
+        );
+    }
+    Ok(())
+}
+
+async fn alt_refresher(refresh_interval: Duration) {
+    info!("starting lookup table refresher");
+
+    loop {
+        tokio::time::sleep(refresh_interval).await;
+
+        // refetched a tick ahead so transactions never go out without a table that expired
+        for alt_pk in ALT_DATA.expiring_within(refresh_interval * 2) {
+            if let Err(e) = refresh_alt(&alt_pk).await {
+                warn!("Failed to refresh lookup table {}: {}", alt_pk, e);
+            }
+        }
+    }
+}
+
+/// Keep per-pool lookup tables within `ttl_secs` of the chain, 0 caches them forever.
+pub fn start_alt_refresher(ttl_secs: u64) {
+    if ttl_secs == 0 {
+        return;
+    }
+
+    let refresh_interval = Duration::from_secs((ttl_secs / 4).max(1));
+    tokio::spawn(async move {
+        alt_refresher(refresh_interval).await;
+    });
+}
+
+pub async fn store_static_alts() -> Result<()> {
+    let alt_pubkeys = global::get_static_alts()?;
+    if alt_pubkeys.is_empty() || STATIC_ALTS.get().is_some() {
+        return Ok(());
+    }
+
+    let alt_accounts = onchain::fetch_alt_accounts(&alt_pubkeys).await?;
+    info!("Loaded {} static ALTs", alt_accounts.len());
+    let _ = STATIC_ALTS.set(alt_accounts.into_iter().map(|(_, alt)| alt).collect());
+    Ok(())
+}
+
+pub fn static_alts() -> &'static [AddressLookupTableAccount] {
+    STATIC_ALTS.get().map(Vec::as_slice).unwrap_or(&[])
+}
+
 pub fn store_mint_alt(mint: Pubkey, alt_pk: Pubkey) {
     PK_TO_ALT.forever(mint, alt_pk);
 }
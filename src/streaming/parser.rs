@@ -1,13 +1,53 @@
 use super::{AccountDataType, AccountTypeInfo};
 use crate::dex::{meteora, pumpfun::PumpAmmReader, raydium, solfi, vertigo, whirlpool};
 use anchor_client::solana_sdk::{account::Account, clock::Clock, pubkey::Pubkey};
+use dashmap::DashSet;
 use dlmm_interface::{BinArrayAccount, LbPairAccount};
+use once_cell::sync::Lazy;
 use spl_token::{solana_program::program_pack::Pack, state::Account as TokenAccount};
+use tracing::warn;
+
+// pools already warned about, a layout change would otherwise warn on every update
+static SIZE_MISMATCHES: Lazy<DashSet<Pubkey>> = Lazy::new(DashSet::new);
+
+/// Data length of a pool account under the layout our deserializers read, `None` for DEXes
+/// whose pools have no fixed size (Pump AMM pools were reallocated as fields were added).
+pub fn expected_pool_len(account_type: AccountTypeInfo) -> Option<usize> {
+    match account_type {
+        AccountTypeInfo::DlmmPair => Some(meteora::dlmm::POOL_ACCOUNT_LEN),
+        AccountTypeInfo::Dammv2Pool => Some(meteora::damm::POOL_ACCOUNT_LEN),
+        AccountTypeInfo::RaydiumAmmPool => Some(raydium::amm::POOL_ACCOUNT_LEN),
+        AccountTypeInfo::RaydiumCpmmPool => Some(raydium::cpmm::POOL_ACCOUNT_LEN),
+        AccountTypeInfo::RaydiumClmmPool => Some(raydium::clmm::POOL_ACCOUNT_LEN),
+        AccountTypeInfo::Whirlpool => Some(whirlpool::POOL_ACCOUNT_LEN),
+        _ => None,
+    }
+}
+
+/// Whether a pool account has the size its DEX's layout expects. A mismatch usually means a
+/// program upgrade changed the layout, so the pool is skipped with a warning rather than misread.
+pub fn has_expected_len(pubkey: &Pubkey, account_type: AccountTypeInfo, data_len: usize) -> bool {
+    match expected_pool_len(account_type) {
+        Some(expected) if expected != data_len => {
+            if SIZE_MISMATCHES.insert(*pubkey) {
+                warn!(
+                    "Skip {:?} {}: account is {} bytes, layout expects {}",
+                    account_type, pubkey, data_len, expected
+                );
+            }
+            false
+        }
+        _ => true,
+    }
+}
 
 #[inline]
 pub fn parse_account(pubkey: &Pubkey, account: &Account) -> Option<AccountDataType> {
     let account_type = AccountTypeInfo::from_pubkey(pubkey);
     let raw_data: &[u8] = &account.data;
+    if !has_expected_len(pubkey, account_type, raw_data.len()) {
+        return None;
+    }
 
     match account_type {
         AccountTypeInfo::DlmmPair => {
@@ -25,6 +65,11 @@ pub fn parse_account(pubkey: &Pubkey, account: &Account) -> Option<AccountDataTy
                 return Some(AccountDataType::AmmPair(pool));
             }
         }
+        AccountTypeInfo::PumpGlobalConfig => {
+            if let Ok(config) = PumpAmmReader::parse_global_config_data(&raw_data[8..]) {
+                return Some(AccountDataType::PumpGlobalConfig(config));
+            }
+        }
         AccountTypeInfo::Account => {
             return Some(AccountDataType::Account(account.clone()));
         }
@@ -69,8 +114,9 @@ pub fn parse_account(pubkey: &Pubkey, account: &Account) -> Option<AccountDataTy
             }
         }
         AccountTypeInfo::RaydiumClmmPool => {
-            if let Ok(data) = raydium::clmm::PoolState::deserialize(raw_data) {
-                return Some(AccountDataType::RaydiumClmmPool(data));
+            match raydium::clmm::PoolState::deserialize(raw_data) {
+                Ok(data) => return Some(AccountDataType::RaydiumClmmPool(data)),
+                Err(e) => warn!("Skip CLMM pool {}: {}", pubkey, e),
             }
         }
         AccountTypeInfo::RaydiumTickArrayBitmapExt => {
@@ -97,11 +143,10 @@ pub fn parse_account(pubkey: &Pubkey, account: &Account) -> Option<AccountDataTy
                 return Some(AccountDataType::VertigoPool(data));
             }
         }
-        AccountTypeInfo::Whirlpool => {
-            if let Ok(data) = whirlpool::state::Whirlpool::deserialize(raw_data) {
-                return Some(AccountDataType::Whirlpool(data));
-            }
-        }
+        AccountTypeInfo::Whirlpool => match whirlpool::state::Whirlpool::deserialize(raw_data) {
+            Ok(data) => return Some(AccountDataType::Whirlpool(data)),
+            Err(e) => warn!("Skip whirlpool {}: {}", pubkey, e),
+        },
         AccountTypeInfo::WhirlpoolOracle => {
             if let Ok(data) = whirlpool::state::oracle::Oracle::deserialize(raw_data) {
                 return Some(AccountDataType::WhirlpoolOracle(data));
@@ -117,3 +162,21 @@ pub fn parse_account(pubkey: &Pubkey, account: &Account) -> Option<AccountDataTy
 
     Some(AccountDataType::Unknown(raw_data.to_vec()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pools_of_another_size_are_skipped() {
+        let pubkey = Pubkey::new_unique();
+        let (account_type, len) = (AccountTypeInfo::Whirlpool, whirlpool::POOL_ACCOUNT_LEN);
+
+        assert!(has_expected_len(&pubkey, account_type, len));
+        assert!(!has_expected_len(&pubkey, account_type, len + 8));
+        // warned once, still skipped on later updates
+        assert!(!has_expected_len(&pubkey, account_type, len - 8));
+        // sizes aren't checked for DEXes without a fixed layout
+        assert!(has_expected_len(&pubkey, AccountTypeInfo::AmmPair, 300));
+    }
+}
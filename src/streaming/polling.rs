@@ -1,5 +1,7 @@
 use super::*;
-use crate::{arb::ata_worker, default_lta, global, streaming::watcher::AccountUpdateEvent};
+use crate::{
+    arb::ata_worker, default_lta, dex::pumpfun, global, streaming::watcher::AccountUpdateEvent,
+};
 use anchor_client::solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
 use anyhow::Result;
 use futures::future::join_all;
@@ -8,6 +10,9 @@ use tokio;
 use tokio::sync::mpsc;
 use tracing::{error, info};
 
+/// How often every indexed account is refetched over RPC, the only source when gRPC is off.
+pub const POLLING_INTERVAL_MS: u64 = 10_000;
+
 pub type EventSender = mpsc::UnboundedSender<AccountUpdateEvent>;
 pub type EventReceiver = mpsc::UnboundedReceiver<AccountUpdateEvent>;
 
@@ -17,16 +22,18 @@ pub struct PollingWatcher {
 
 pub fn get_and_set_price(pool_pk: &Pubkey) {
     match pool_index::get(pool_pk) {
-        Some(pool) => {
-            if let Some(pool_type) = pool.to_pool_type() {
-                let (atob, _) = pool_type.get_price(&pool.mint_a);
-                global_data::update_price(pool_pk, pool.mint_a, atob);
-            }
-        }
+        Some(pool) => price::refresh_pool_price(pool_pk, &pool),
         None => {}
     }
 }
 
+/// Apply an update of an account quoting reads outside the pool data, the pump AMM fees.
+pub fn apply_config_update(data: &AccountDataType) {
+    if let AccountDataType::PumpGlobalConfig(config) = data {
+        pumpfun::set_fee_bps(config);
+    }
+}
+
 impl PollingWatcher {
     pub fn new() -> (Self, EventReceiver) {
         let (event_sender, event_receiver) = mpsc::unbounded_channel();
@@ -53,17 +60,22 @@ impl PollingWatcher {
             }
         };
 
+        let slot = accounts.context.slot;
         for (pubkey, account_option) in pubkeys.iter().zip(accounts.value.iter()) {
             match account_option {
                 Some(account) => {
                     if let Some(data) = parse_account(pubkey, account) {
-                        ACCOUNT_DATA.insert(pubkey.clone(), data.clone());
+                        // a streamed update may already be newer than this poll
+                        if global_data::store_account(*pubkey, data.clone(), slot).is_some() {
+                            continue;
+                        }
                         get_and_set_price(pubkey);
+                        apply_config_update(&data);
 
                         let event = AccountUpdateEvent {
                             pubkey: *pubkey,
                             data,
-                            slot: accounts.context.slot,
+                            slot,
                             receive_time: std::time::Instant::now(),
                         };
 
@@ -114,7 +126,17 @@ pub async fn start(ms: u64) -> Result<EventReceiver> {
     let event_sender = watcher.get_sender();
     let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(ms));
 
-    store_lookup_table(&default_lta()).await?;
+    store_static_lookup_table(&default_lta()).await?;
+    store_static_alts().await?;
+    // indexed so every poll refetches it with the pools
+    match pumpfun::load_fee_bps(global::get_rpc_client()).await {
+        std::result::Result::Ok(config) => global_data::add_accounts(
+            pumpfun::global_config(),
+            AccountDataType::PumpGlobalConfig(config),
+            AccountTypeInfo::PumpGlobalConfig,
+        ),
+        Err(e) => error!("Failed to load pump AMM fees, quoting with defaults: {}", e),
+    }
     global_data::add_account_type(clock_mint(), AccountTypeInfo::Clock);
     price::sync_price()?;
     ata_worker::AtaWorker::get_or_init();
@@ -0,0 +1,96 @@
+// price updater
+use super::*;
+use anyhow::Result;
+use futures::future::join_all;
+use tokio;
+use tracing::{error, info};
+
+pub mod socket;
+
+// decimals offset in both spl-token and token-2022 mint layouts
+const MINT_DECIMALS_OFFSET: usize = 44;
+
+/// Convert a raw quote/base amount ratio into a human price
+#[inline]
+pub fn human(raw: f64, base_decimals: u8, quote_decimals: u8) -> f64 {
+    raw * 10f64.powi(base_decimals as i32 - quote_decimals as i32)
+}
+
+#[inline]
+pub fn read_decimals(mint_data: &[u8]) -> Option<u8> {
+    mint_data.get(MINT_DECIMALS_OFFSET).copied()
+}
+
+/// `None` for prices no route should be built on: NaN, infinite, zero or negative.
+#[inline]
+pub fn sanitize(price: f64) -> Option<f64> {
+    (price.is_finite() && price > 0.0).then_some(price)
+}
+
+/// Store the pool's a -> b price, or drop the pool from the price view when it has no usable
+/// one (dust or empty pools, zero reserves).
+pub fn refresh_pool_price(pubkey: &Pubkey, pool: &TokenPool) {
+    if let Some(pool_type) = pool.to_pool_type() {
+        match pool_type.get_price(&pool.mint_a) {
+            Some((atob, _)) => global_data::update_price(pubkey, pool.mint_a, pool.mint_b, atob),
+            None => global_data::remove_price(pubkey),
+        }
+    }
+}
+
+async fn updater(pools: &[Arc<TokenPool>]) -> Result<()> {
+    for pool in pools {
+        refresh_pool_price(&pool.pool, pool);
+    }
+
+    Ok(())
+}
+
+async fn sync_epoch() -> Result<()> {
+    let all_pools: Vec<Arc<TokenPool>> = pool_index::get_all_pools();
+    if all_pools.is_empty() {
+        return Ok(());
+    }
+
+    let chunks: Vec<Vec<Arc<TokenPool>>> =
+        all_pools.chunks(100).map(|chunk| chunk.to_vec()).collect();
+
+    let tasks: Vec<_> = chunks.iter().map(|chunk| updater(chunk)).collect();
+    join_all(tasks).await;
+
+    Ok(())
+}
+
+pub fn sync_price() -> Result<()> {
+    if let Some(socket_path) = global::get_price_socket_path() {
+        socket::start(socket_path)?;
+    }
+
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(100));
+    tokio::spawn(async move {
+        info!("Begin sync_price thread...");
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = sync_epoch().await {
+                error!("sync_price failed: {}", e);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_keeps_only_finite_positive_prices() {
+        assert_eq!(sanitize(1.5), Some(1.5));
+        assert_eq!(sanitize(f64::MIN_POSITIVE), Some(f64::MIN_POSITIVE));
+        for price in [0.0, -0.0, -1.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(sanitize(price), None, "{}", price);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// price update exporter over a unix socket
+use super::*;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::path::Path;
+use tokio::{
+    io::AsyncWriteExt,
+    net::{UnixListener, UnixStream},
+    sync::broadcast::{self, error::RecvError},
+};
+use tracing::warn;
+
+// updates buffered per consumer, a consumer falling further behind loses the oldest ones
+const CHANNEL_CAPACITY: usize = 4096;
+
+static PRICE_SENDER: OnceCell<broadcast::Sender<PriceUpdate>> = OnceCell::new();
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceUpdate {
+    pub pool: String,
+    pub mint_a: String,
+    pub mint_b: String,
+    pub price: f64,
+    pub adjusted_price: f64,
+    pub slot: u64,
+}
+
+/// Queue a price change for connected consumers, never waits on them.
+#[inline]
+pub fn publish(pool: &Pubkey, mint_a: Pubkey, mint_b: Pubkey, price: f64, adjusted_price: f64) {
+    let Some(sender) = PRICE_SENDER.get() else {
+        return;
+    };
+    if sender.receiver_count() == 0 {
+        return;
+    }
+
+    let slot = global_data::get_clock().map_or(0, |clock| clock.slot);
+    let _ = sender.send(PriceUpdate {
+        pool: pool.to_string(),
+        mint_a: mint_a.to_string(),
+        mint_b: mint_b.to_string(),
+        price,
+        adjusted_price,
+        slot,
+    });
+}
+
+async fn serve_consumer(mut stream: UnixStream, mut receiver: broadcast::Receiver<PriceUpdate>) {
+    loop {
+        let update = match receiver.recv().await {
+            std::result::Result::Ok(update) => update,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("Price socket consumer lagging, dropped {} updates", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        let mut line = match serde_json::to_string(&update) {
+            std::result::Result::Ok(line) => line,
+            Err(e) => {
+                error!("Failed to encode price update {}", e);
+                continue;
+            }
+        };
+        line.push('\n');
+        if stream.write_all(line.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+pub fn start(socket_path: String) -> Result<()> {
+    if Path::new(&socket_path).exists() {
+        std::fs::remove_file(&socket_path)?;
+    }
+    let listener = UnixListener::bind(&socket_path)?;
+    let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+    let sender = PRICE_SENDER.get_or_init(|| sender).clone();
+
+    tokio::spawn(async move {
+        info!("Price socket listening on {}", socket_path);
+
+        loop {
+            match listener.accept().await {
+                std::result::Result::Ok((stream, _)) => {
+                    tokio::spawn(serve_consumer(stream, sender.subscribe()));
+                }
+                Err(e) => {
+                    error!("Price socket accept failed: {}", e);
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use super::{
     ACCOUNT_DATA, ACCOUNT_TYPE_MAP, AccountDataType, AccountTypeInfo, WatcherCommand, global_data,
-    util, watcher::AccountUpdateEvent,
+    price, util, watcher::AccountUpdateEvent,
 };
 use crate::{global, pool_index, dex::{raydium, whirlpool}};
 use anchor_client::solana_sdk::pubkey::Pubkey;
@@ -27,12 +27,7 @@ pub async fn signal_receiver(
 
         tokio::spawn(async move {
             match pool_index::get(&event.pubkey) {
-                Some(pool) => {
-                    if let Some(pool_type) = pool.to_pool_type() {
-                        let (atob, next_token) = pool_type.get_price(&pool.mint_a);
-                        global_data::update_price(&event.pubkey, pool.mint_a, atob);
-                    }
-                }
+                Some(pool) => price::refresh_pool_price(&event.pubkey, &pool),
                 None => {}
             }
 
@@ -61,42 +56,63 @@ pub async fn signal_receiver(
                     }
                 }
                 &AccountDataType::RaydiumClmmPool(ref pool_state) => {
-                    match super::loader::get_bitmap_ext(&event.pubkey) {
-                        Some(bitmap_state) => {
-                            let left_ticks =
-                                raydium::clmm::swap_util::get_cur_and_next_five_tick_array(
-                                    event.pubkey,
-                                    &pool_state,
-                                    &bitmap_state,
-                                    false,
-                                );
-                            let right_ticks =
-                                raydium::clmm::swap_util::get_cur_and_next_five_tick_array(
-                                    event.pubkey,
-                                    &pool_state,
-                                    &bitmap_state,
-                                    true,
-                                );
-                            let ticks = util::merge(&[&left_ticks, &right_ticks]);
-                            let new_keys = nonexists_pubkeys(&ticks);
-                            if !new_keys.is_empty() {
-                                global_data::add_accounts_type_str(
-                                    &new_keys,
-                                    AccountTypeInfo::RaydiumTickArrayState,
-                                );
-                                if let Err(e) = command_clone
-                                    .send(WatcherCommand::BatchAdd { accounts: new_keys })
-                                {
+                    let bitmap_state = match super::loader::get_bitmap_ext(&event.pubkey) {
+                        Some(bitmap_state) => bitmap_state,
+                        None if !super::loader::near_bitmap_boundary(pool_state) => {
+                            raydium::clmm::tick_array_bitmap_extension::TickArrayBitmapExtension::empty(
+                                event.pubkey,
+                            )
+                        }
+                        // tick arrays are picked up on the next update once the extension is in
+                        None => {
+                            if let Some(bitmap_ext) =
+                                super::loader::load_bitmap_ext_if_needed(&event.pubkey, pool_state)
+                                    .await
+                            {
+                                if let Err(e) = command_clone.send(WatcherCommand::BatchAdd {
+                                    accounts: vec![bitmap_ext.to_string()],
+                                }) {
                                     error!("Failed to send watcher command: {}", e);
-                                    return;
                                 }
                             }
+                            return;
+                        }
+                    };
+                    let left_ticks = raydium::clmm::swap_util::get_cur_and_next_tick_arrays(
+                        event.pubkey,
+                        &pool_state,
+                        &bitmap_state,
+                        false,
+                        global::get_clmm_tick_arrays(),
+                    );
+                    let right_ticks = raydium::clmm::swap_util::get_cur_and_next_tick_arrays(
+                        event.pubkey,
+                        &pool_state,
+                        &bitmap_state,
+                        true,
+                        global::get_clmm_tick_arrays(),
+                    );
+                    let ticks = util::merge(&[&left_ticks, &right_ticks]);
+                    let new_keys = nonexists_pubkeys(&ticks);
+                    if !new_keys.is_empty() {
+                        global_data::add_accounts_type_str(
+                            &new_keys,
+                            AccountTypeInfo::RaydiumTickArrayState,
+                        );
+                        if let Err(e) =
+                            command_clone.send(WatcherCommand::BatchAdd { accounts: new_keys })
+                        {
+                            error!("Failed to send watcher command: {}", e);
+                            return;
                         }
-                        None => {}
                     }
                 }
                 &AccountDataType::Whirlpool(ref pool_state) => {
-                    match whirlpool::util::get_tick_arrays_or_default(event.pubkey, &pool_state) {
+                    match whirlpool::util::get_tick_arrays_or_default(
+                        event.pubkey,
+                        &pool_state,
+                        global::get_whirlpool_tick_arrays(),
+                    ) {
                         std::result::Result::Ok(tick_arrays) => {
                             let new_keys = nonexists_pubkeys(&tick_arrays);
                             if !new_keys.is_empty() {
@@ -135,18 +151,24 @@ async fn add_bin_array_accounts(pubkeys: &[Pubkey]) -> Result<()> {
     let rpc_client = global::get_rpc_client();
 
     let timeout_duration = Duration::from_secs(30);
-    let accounts =
-        tokio::time::timeout(timeout_duration, rpc_client.get_multiple_accounts(pubkeys))
-            .await
-            .map_err(|_| anyhow::anyhow!("RPC timeout after 30s"))?
-            .map_err(|e| anyhow::anyhow!("RPC error: {}", e))?;
-
-    for (pubkey, account) in pubkeys.iter().zip(accounts.into_iter()) {
+    let commitment = rpc_client.commitment();
+    let accounts = tokio::time::timeout(
+        timeout_duration,
+        rpc_client.get_multiple_accounts_with_commitment(pubkeys, commitment),
+    )
+    .await
+    .map_err(|_| anyhow::anyhow!("RPC timeout after 30s"))?
+    .map_err(|e| anyhow::anyhow!("RPC error: {}", e))?;
+
+    let slot = accounts.context.slot;
+    for (pubkey, account) in pubkeys.iter().zip(accounts.value.into_iter()) {
         if let Some(data) = account {
             match BinArrayAccount::deserialize(&data.data) {
                 std::result::Result::Ok(bin_array) => {
                     ACCOUNT_TYPE_MAP.insert(*pubkey, AccountTypeInfo::BinArray);
-                    ACCOUNT_DATA.insert(*pubkey, AccountDataType::BinArray(bin_array.0));
+                    let bin_array = AccountDataType::BinArray(bin_array.0);
+                    // keeps a streamed update newer than this fetch
+                    global_data::store_account(*pubkey, bin_array, slot);
                 }
                 Err(e) => {
                     warn!("Failed to deserialize bin array for {}: {}", pubkey, e);
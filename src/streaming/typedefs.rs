@@ -10,6 +10,7 @@ pub enum AccountDataType {
     DlmmPair(LbPair),
     BinArray(BinArray),
     AmmPair(pumpfun::AmmPool),
+    PumpGlobalConfig(pumpfun::GlobalConfig),
     Account(Account),
     Clock(Clock),
     TokenAccount(TokenAccount),
@@ -38,6 +39,7 @@ impl AccountDataType {
             AccountDataType::DlmmPair(_) => "DlmmPair",
             AccountDataType::BinArray(_) => "BinArray",
             AccountDataType::AmmPair(_) => "AmmPair",
+            AccountDataType::PumpGlobalConfig(_) => "PumpGlobalConfig",
             AccountDataType::Account(_) => "Account",
             AccountDataType::Clock(_) => "Clock",
             AccountDataType::TokenAccount(_) => "TokenAccount",
@@ -66,6 +68,7 @@ pub enum AccountTypeInfo {
     DlmmPair,
     BinArray,
     AmmPair,
+    PumpGlobalConfig,
     Account,
     Clock,
     TokenAccount,
@@ -1,10 +1,11 @@
 use anchor_client::solana_sdk::{account::Account, pubkey::Pubkey};
 use anyhow::Result;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
 use tokio::sync::mpsc;
-use tracing::info;
+use tracing::{debug, info};
 use yellowstone_grpc_proto::geyser::{SubscribeUpdateAccountInfo, subscribe_update};
 use crate::arb;
 
@@ -26,6 +27,7 @@ pub struct WatcherStats {
     pub total_updates: AtomicU64,
     pub successful_parses: AtomicU64,
     pub failed_parses: AtomicU64,
+    pub stale_updates: AtomicU64,
 }
 
 impl Default for WatcherStats {
@@ -34,6 +36,7 @@ impl Default for WatcherStats {
             total_updates: AtomicU64::new(0),
             successful_parses: AtomicU64::new(0),
             failed_parses: AtomicU64::new(0),
+            stale_updates: AtomicU64::new(0),
         }
     }
 }
@@ -44,6 +47,13 @@ pub struct DataWatcher {
     stats: Arc<WatcherStats>,
 }
 
+// an unsubscribed account only changes through the loaders, so its streamed slot no longer applies
+fn forget_account_slot(account: &str) {
+    if let std::result::Result::Ok(pubkey) = Pubkey::from_str(account) {
+        global_data::forget_account_slot(&pubkey);
+    }
+}
+
 fn subscribe_account_to_account(info: &SubscribeUpdateAccountInfo) -> Account {
     let owner_arr: [u8; 32] = info.owner.as_slice().try_into().expect("owner 32 bytes");
     Account {
@@ -102,9 +112,18 @@ impl DataWatcher {
 
                 // Parse and store in one step
                 if let Some(data) = parse_account(&pubkey, &subscribe_account_to_account(account)) {
-                    // Store immediately
-                    ACCOUNT_DATA.insert(pubkey, data.clone());
+                    // Drop updates older than what is already stored
+                    let slot = account_update.slot;
+                    if let Some(recorded) = global_data::store_account(pubkey, data.clone(), slot) {
+                        stats.stale_updates.fetch_add(1, Ordering::Relaxed);
+                        debug!(
+                            "Dropped stale update for {} at slot {}, already have slot {}",
+                            pubkey, slot, recorded
+                        );
+                        return;
+                    }
                     polling::get_and_set_price(&pubkey);
+                    polling::apply_config_update(&data);
 
                     // Check arbitrage relevance with fast type detection
                     if Self::is_arbitrage_relevant(&pubkey) {
@@ -162,7 +181,7 @@ impl DataWatcher {
     pub fn remove_accounts(&self, accounts: Vec<String>) -> usize {
         let mut removed_count = 0;
         for account in accounts {
-            if self.grpc_client.remove_account(account) {
+            if self.remove_account(account) {
                 removed_count += 1;
             }
         }
@@ -186,6 +205,9 @@ impl DataWatcher {
         add_programs: Vec<String>,
         remove_programs: Vec<String>,
     ) -> bool {
+        for account in &remove_accounts {
+            forget_account_slot(account);
+        }
         self.grpc_client
             .batch_update(add_accounts, remove_accounts, add_programs, remove_programs)
     }
@@ -210,6 +232,7 @@ impl DataWatcher {
             total_updates: total,
             successful_parses: success,
             failed_parses: failed,
+            stale_updates: self.stats.stale_updates.load(Ordering::Relaxed),
             accounts_count: grpc_metrics.accounts_count,
             programs_count: grpc_metrics.programs_count,
             pending_changes: grpc_metrics.pending_changes,
@@ -218,6 +241,10 @@ impl DataWatcher {
         }
     }
 
+    pub fn get_subscriptions(&self) -> grpc::SubscriptionSnapshot {
+        self.grpc_client.get_subscriptions()
+    }
+
     pub fn last_update_slot(&self) -> u64 {
         self.grpc_client.get_metrics().last_update_slot
     }
@@ -256,6 +283,7 @@ pub struct WatcherMetrics {
     pub total_updates: u64,
     pub successful_parses: u64,
     pub failed_parses: u64,
+    pub stale_updates: u64,
     pub accounts_count: usize,
     pub programs_count: usize,
     pub pending_changes: usize,
@@ -269,6 +297,7 @@ impl DataWatcher {
     }
 
     pub fn remove_account(&self, account: String) -> bool {
+        forget_account_slot(&account);
         self.grpc_client.remove_account(account)
     }
 
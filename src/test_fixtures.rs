@@ -0,0 +1,36 @@
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use std::{fs, path::PathBuf};
+
+/// Swaps recorded from chain under `tests/fixtures/<dex>`, one JSON file each, paired with
+/// their file name. See `tests/fixtures/README.md` for the format and how to capture them.
+pub fn recorded_swaps<T: DeserializeOwned>(dex: &str) -> Vec<(String, T)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(dex);
+    let mut files: Vec<PathBuf> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+
+    files
+        .into_iter()
+        .map(|path| {
+            let name = path.display().to_string();
+            let content = fs::read_to_string(&path).unwrap();
+            let swap = serde_json::from_str(&content).unwrap_or_else(|e| panic!("{}: {}", name, e));
+            (name, swap)
+        })
+        .collect()
+}
+
+/// Account data as `getAccountInfo` returns it with the base64 encoding.
+pub fn account_data(base64_data: &str) -> Vec<u8> {
+    base64::engine::general_purpose::STANDARD
+        .decode(base64_data)
+        .unwrap()
+}
@@ -1,18 +1,74 @@
 use crate::{
-    arb::SwapRoutes,
+    arb::{SwapRoutes, ata_worker::AtaWorker, optimization},
     global,
     instructions::{self, flashloan},
     onchain,
     util::rand_u32,
+    wsol_mint,
 };
 use anchor_client::{
     solana_client::rpc_config::RpcSendTransactionConfig,
     solana_sdk::{
-        address_lookup_table::AddressLookupTableAccount, commitment_config::CommitmentLevel,
-        hash::Hash, signature::Signature, transaction::VersionedTransaction,
+        address_lookup_table::AddressLookupTableAccount,
+        commitment_config::CommitmentLevel,
+        hash::Hash,
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::Signature,
+        system_instruction::{self, SystemInstruction},
+        system_program,
+        transaction::VersionedTransaction,
     },
 };
-use tracing::{error, info};
+use anyhow::{Result, anyhow};
+use std::collections::HashSet;
+use tracing::{error, info, warn};
+
+/// Why `build_and_send` sent nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendFailure {
+    /// Rejected before sending: build error, account cap or profit after the priority fee
+    Rejected,
+    /// The simulation failed or fell short of the quote
+    Drifted,
+    /// The RPC didn't take the transaction
+    Unsent,
+}
+
+/// Unique accounts the transaction references, and how many of them can't come from a lookup table.
+fn count_tx_accounts(
+    payer: &Pubkey,
+    ixs: &[Instruction],
+    alt_accounts: &[AddressLookupTableAccount],
+) -> (usize, usize) {
+    let mut keys: HashSet<Pubkey> = HashSet::new();
+    // the payer, signers and invoked programs are always static keys
+    let mut static_keys: HashSet<Pubkey> = HashSet::new();
+    keys.insert(*payer);
+    static_keys.insert(*payer);
+
+    for ix in ixs {
+        keys.insert(ix.program_id);
+        static_keys.insert(ix.program_id);
+        for meta in &ix.accounts {
+            keys.insert(meta.pubkey);
+            if meta.is_signer {
+                static_keys.insert(meta.pubkey);
+            }
+        }
+    }
+
+    let lookup_keys: HashSet<&Pubkey> = alt_accounts
+        .iter()
+        .flat_map(|alt| alt.addresses.iter())
+        .collect();
+    let static_count = keys
+        .iter()
+        .filter(|key| static_keys.contains(*key) || !lookup_keys.contains(key))
+        .count();
+
+    (keys.len(), static_count)
+}
 
 fn adjust_cu_price(profit: i64) -> u64 {
     match profit {
@@ -28,66 +84,343 @@ fn adjust_cu_price(profit: i64) -> u64 {
     }
 }
 
+// compute units a route requests, a random base for its first two pools plus ROUTE_HOP_UNITS
+// for each pool after
+const MIN_ROUTE_BASE_UNITS: u32 = 300_000;
+const MAX_ROUTE_BASE_UNITS: u32 = 350_000;
+const ROUTE_HOP_UNITS: u32 = 120_000;
+
+/// Priority fee in lamports sending `swap_data` is expected to cost, priced on the most compute
+/// units its route requests before a flashloan. `None` without a SOL price for its base mint.
+pub fn expected_priority_fee(swap_data: &SwapRoutes) -> Option<u64> {
+    let cu_price = instructions::cu::cap_price(
+        adjust_cu_price(optimization::net_profit_lamports(swap_data, 0)?),
+        global::get_max_cu_price_micro_lamports(),
+    );
+    let route_len = swap_data.routes.len() as u32;
+    let cu_limit = MAX_ROUTE_BASE_UNITS + route_len.saturating_sub(2) * ROUTE_HOP_UNITS;
+    Some(instructions::cu::priority_fee(cu_price, cu_limit))
+}
+
+fn route_amount_in(swap_data: &SwapRoutes) -> u64 {
+    if swap_data.threshold > 0 {
+        swap_data.threshold
+    } else {
+        swap_data.amount_in
+    }
+}
+
+/// An arb transaction's instructions by role, see `into_instructions` for their order.
+struct ArbInstructions {
+    // (nonce account, authority) when the transaction uses a durable nonce
+    nonce: Option<(Pubkey, Pubkey)>,
+    // without the instruction the runtime's default limit applies
+    include_cu_limit: bool,
+    cu_limit: u32,
+    cu_price: u64,
+    // ATAs the route needs
+    setup: Vec<Instruction>,
+    // wrapping SOL or the flash borrow
+    before_swap: Vec<Instruction>,
+    swap: Instruction,
+    // unwrapping SOL or the flash repay
+    after_swap: Vec<Instruction>,
+}
+
+impl ArbInstructions {
+    /// Index in the transaction of the next instruction pushed to `before_swap`.
+    fn next_before_swap_index(&self) -> usize {
+        usize::from(self.nonce.is_some())
+            + usize::from(self.include_cu_limit)
+            + 1
+            + self.setup.len()
+            + self.before_swap.len()
+    }
+
+    /// Compute units the transaction may use, and so pays the CU price for: `cu_limit`, or the
+    /// runtime's default without the limit instruction.
+    fn compute_unit_limit(&self) -> u32 {
+        if self.include_cu_limit {
+            return self.cu_limit;
+        }
+
+        let nonce_advance = self.nonce.map(|(nonce_account, nonce_authority)| {
+            system_instruction::advance_nonce_account(&nonce_account, &nonce_authority)
+        });
+        instructions::cu::default_limit(
+            nonce_advance
+                .iter()
+                .chain(&self.setup)
+                .chain(&self.before_swap)
+                .chain([&self.swap])
+                .chain(&self.after_swap),
+        )
+    }
+
+    /// Nonce advance, CU limit, CU price, setup, before swap, swap, after swap. The runtime
+    /// requires the nonce advance first, and the compute budget goes ahead of the instructions
+    /// it pays for.
+    fn into_instructions(self) -> Vec<Instruction> {
+        let mut ixs = Vec::new();
+        if let Some((nonce_account, nonce_authority)) = self.nonce {
+            ixs.push(system_instruction::advance_nonce_account(
+                &nonce_account,
+                &nonce_authority,
+            ));
+        }
+        if self.include_cu_limit {
+            ixs.push(instructions::cu::limit_instruction(self.cu_limit));
+        }
+        ixs.push(instructions::cu::price_instruction(self.cu_price));
+        ixs.extend(self.setup);
+        ixs.extend(self.before_swap);
+        ixs.push(self.swap);
+        ixs.extend(self.after_swap);
+        ixs
+    }
+}
+
+/// Every instruction of the arb transaction paid by `payer`, in order. `nonce` is
+/// `(nonce account, authority)` when the transaction uses a durable nonce, the advance
+/// instruction is then put first as the runtime requires. `known_atas` are the payer's token
+/// accounts that already exist, see `AtaWorker::known_atas`.
+pub fn build_instructions(
+    payer: &Pubkey,
+    nonce: Option<(Pubkey, Pubkey)>,
+    swap_data: SwapRoutes,
+    alt_accounts: &[AddressLookupTableAccount],
+    user_base_amount: u64,
+    known_atas: &HashSet<Pubkey>,
+) -> Result<Vec<Instruction>> {
+    let amount_in = route_amount_in(&swap_data);
+    let mint = swap_data.mint;
+    // in lamports, the CU price tiers and the priority fee are priced in SOL whatever the base mint
+    let profit = optimization::net_profit_lamports(&swap_data, 0)
+        .ok_or_else(|| anyhow!("Route skipped: no SOL price for base mint {}", mint))?;
+    let tier_cu_price = adjust_cu_price(profit);
+    let max_cu_price = global::get_max_cu_price_micro_lamports();
+    let cu_price = instructions::cu::cap_price(tier_cu_price, max_cu_price);
+    if cu_price < tier_cu_price {
+        info!(
+            "CU price {} clamped to max_cu_price_micro_lamports {}",
+            tier_cu_price, cu_price
+        );
+    }
+    let route_len: u32 = swap_data.routes.len() as u32;
+    let route_ixs = instructions::aggregator::route(payer, swap_data, 0, known_atas)?;
+    let leg_accounts = route_ixs.leg_accounts;
+    let mut tx = ArbInstructions {
+        nonce,
+        include_cu_limit: global::include_cu_limit(),
+        cu_limit: rand_u32(MIN_ROUTE_BASE_UNITS, MAX_ROUTE_BASE_UNITS)
+            + (route_len - 2) * ROUTE_HOP_UNITS,
+        cu_price,
+        setup: route_ixs.setup,
+        before_swap: Vec::new(),
+        swap: route_ixs.swap,
+        after_swap: Vec::new(),
+    };
+
+    let auto_wrap = global::auto_wrap_sol() && mint == wsol_mint();
+    // the wSOL account is only temporary when this transaction creates it
+    let wsol_ata_created =
+        auto_wrap && !known_atas.contains(&onchain::get_associated_token_address(payer, &mint));
+
+    if auto_wrap {
+        let wrap_amount = if wsol_ata_created {
+            amount_in
+        } else {
+            amount_in.saturating_sub(user_base_amount)
+        };
+
+        if wrap_amount > 0 {
+            let wrap_ixs = instructions::token::wrap_sol(payer, wrap_amount, wsol_ata_created)
+                .map_err(|e| anyhow!("Failed to build wrap sol instructions {}", e))?;
+            tx.before_swap.extend(wrap_ixs);
+        }
+
+        if wsol_ata_created {
+            let unwrap_ix = instructions::token::unwrap_sol(payer)
+                .map_err(|e| anyhow!("Failed to build unwrap sol instruction {}", e))?;
+            tx.after_swap.push(unwrap_ix);
+        }
+    } else if amount_in > user_base_amount {
+        if let Some(kamino_reserve) = flashloan::kamino::find_reserve(&mint) {
+            // enable flashloan, the repay points back at the borrow
+            let flashloan_index = tx.next_before_swap_index() as u8;
+            let borrow_ix = flashloan::kamino::flash_borrow_reserve_liquidity(
+                payer,
+                kamino_reserve.clone(),
+                amount_in,
+            );
+            let repay_ix = flashloan::kamino::flash_repay_reserve_liquidity(
+                payer,
+                kamino_reserve,
+                amount_in,
+                flashloan_index,
+            );
+            tx.before_swap.push(borrow_ix);
+            tx.after_swap.push(repay_ix);
+            tx.cu_limit += 80_000;
+        }
+    }
+
+    if max_cu_price > 0 {
+        let priority_fee = instructions::cu::priority_fee(cu_price, tx.compute_unit_limit());
+        if profit.saturating_sub(priority_fee as i64) < global::get_minimum_profit() as i64 {
+            return Err(anyhow!(
+                "Route skipped: profit {} less priority fee {} is below the minimum profit",
+                profit,
+                priority_fee
+            ));
+        }
+    }
+
+    let ixs = tx.into_instructions();
+    let (account_count, static_count) = count_tx_accounts(payer, &ixs, alt_accounts);
+    let max_tx_accounts = global::get_max_tx_accounts();
+    if account_count > max_tx_accounts {
+        let mut legs = leg_accounts;
+        legs.sort_by(|a, b| b.1.cmp(&a.1));
+        let legs: Vec<String> = legs
+            .iter()
+            .map(|(dex, count)| format!("{} {}", dex, count))
+            .collect();
+        return Err(anyhow!(
+            "Route rejected: {} accounts ({} static) over max_tx_accounts {} - legs by accounts [{}]",
+            account_count,
+            static_count,
+            max_tx_accounts,
+            legs.join(", ")
+        ));
+    }
+
+    Ok(ixs)
+}
+
+/// Lamports the transaction moves into `token_account`, i.e. the SOL it wraps before swapping.
+fn wrapped_lamports(ixs: &[Instruction], token_account: &Pubkey) -> u64 {
+    ixs.iter()
+        .filter(|ix| {
+            ix.program_id == system_program::id()
+                && ix
+                    .accounts
+                    .get(1)
+                    .is_some_and(|meta| meta.pubkey == *token_account)
+        })
+        .filter_map(|ix| match bincode::deserialize(&ix.data) {
+            std::result::Result::Ok(SystemInstruction::Transfer { lamports }) => Some(lamports),
+            _ => None,
+        })
+        .sum()
+}
+
+/// Only a shortfall counts as drift, landing above the quote is fine.
+fn within_tolerance(predicted: u64, simulated: u64, tolerance_bps: u64) -> bool {
+    let floor = predicted as u128 * 10_000u128.saturating_sub(tolerance_bps as u128) / 10_000;
+    simulated as u128 >= floor
+}
+
+/// Simulate the transaction and check the base token it hands back is close to the quote.
+async fn check_simulated_output(
+    blockhash: Hash,
+    nonce: Option<(Pubkey, Pubkey)>,
+    mint: &Pubkey,
+    ixs: &[Instruction],
+    alt_accounts: &[AddressLookupTableAccount],
+    amount_in: u64,
+    profit: i64,
+) -> Result<()> {
+    // simulation signs with the bot wallet, so it is the payer here
+    let base_account = onchain::get_user_token_address(&global::get_pubkey(), mint);
+    let rpc = global::get_rpc_client();
+    let (before, after) = tokio::join!(
+        onchain::get_token_amount(&rpc, &base_account),
+        onchain::send::simulate_arb_tx(
+            blockhash,
+            nonce.is_none(),
+            ixs,
+            alt_accounts,
+            &base_account
+        )
+    );
+    let Some(after) = after? else {
+        warn!("Base account closed by the route, simulated output not checked");
+        return Ok(());
+    };
+    // a missing account held nothing, the route creates it
+    let before = before.unwrap_or(0);
+
+    let predicted = (amount_in as i128 + profit as i128).max(0) as u64;
+    let simulated = (after as i128 - before as i128 - wrapped_lamports(ixs, &base_account) as i128
+        + amount_in as i128)
+        .max(0) as u64;
+    let tolerance_bps = global::get_simulate_tolerance_bps();
+    if !within_tolerance(predicted, simulated, tolerance_bps) {
+        return Err(anyhow!(
+            "simulated output {} drifted from predicted {} by more than {} bps",
+            simulated,
+            predicted,
+            tolerance_bps
+        ));
+    }
+
+    Ok(())
+}
+
+/// `nonce` is `(nonce account, authority)` when `blockhash` is a durable nonce.
 pub async fn build_and_send(
     blockhash: Hash,
+    nonce: Option<(Pubkey, Pubkey)>,
     swap_data: SwapRoutes,
     alt_accounts: &Vec<AddressLookupTableAccount>,
     user_base_amount: u64,
-) -> Option<Signature> {
+) -> std::result::Result<Signature, SendFailure> {
     let profit = swap_data.profit;
-    let amount_in = if swap_data.threshold > 0 {
-        swap_data.threshold
-    } else {
-        swap_data.amount_in
-    };
+    let amount_in = route_amount_in(&swap_data);
+    let payer = global::get_pubkey();
     let mint = swap_data.mint;
-    let mut ixs = vec![instructions::cu::price_instruction(adjust_cu_price(
-        swap_data.profit,
-    ))];
-    let route_len: u32 = swap_data.routes.len() as u32;
-    let swap_ix = instructions::aggregator::route(swap_data, 0).unwrap();
-    let mut cu_limit = rand_u32(300_000, 350_000);
-    let extra_cu: u32 = (route_len - 2) * 120_000;
-    cu_limit += extra_cu;
-
-    if amount_in > user_base_amount {
-        match flashloan::kamino::find_reserve(&mint) {
-            Some(kamino_reserve) => {
-                // enable flashloan
-                let payer = global::get_pubkey();
-                let flashloan_index = (ixs.len() as u8) + 1;
-                ixs.push(flashloan::kamino::flash_borrow_reserve_liquidity(
-                    &payer,
-                    kamino_reserve.clone(),
-                    amount_in,
-                ));
-                ixs.push(swap_ix);
-                ixs.push(flashloan::kamino::flash_repay_reserve_liquidity(
-                    &payer,
-                    kamino_reserve,
-                    amount_in,
-                    flashloan_index,
-                ));
-                cu_limit += 80_000;
-            }
-            None => {
-                ixs.push(swap_ix);
-            }
+    let known_atas = AtaWorker::known_atas(&swap_data.routes);
+    let ixs = match build_instructions(
+        &payer,
+        nonce,
+        swap_data,
+        alt_accounts,
+        user_base_amount,
+        &known_atas,
+    ) {
+        std::result::Result::Ok(ixs) => ixs,
+        Err(e) => {
+            error!("{}", e);
+            return Err(SendFailure::Rejected);
         }
-    } else {
-        ixs.push(swap_ix);
-    }
+    };
 
-    ixs.insert(0, instructions::cu::limit_instruction(cu_limit));
+    if global::simulate_before_send() && profit >= global::get_simulate_min_profit() as i64 {
+        let checked = check_simulated_output(
+            blockhash,
+            nonce,
+            &mint,
+            &ixs,
+            alt_accounts,
+            amount_in,
+            profit,
+        )
+        .await;
+        if let Err(e) = checked {
+            warn!("Route skipped, {}", e);
+            return Err(SendFailure::Drifted);
+        }
+    }
 
     let signature = match onchain::send::send_arb_tx(blockhash, &ixs, &alt_accounts).await {
         std::result::Result::Ok(sig) => {
             info!("Transaction hash {}", sig.to_string());
-            Some(sig)
+            std::result::Result::Ok(sig)
         }
         Err(e) => {
             error!("An error occus {}", e);
-            None
+            Err(SendFailure::Unsent)
         }
     };
 
@@ -95,3 +428,112 @@ pub async fn build_and_send(
 
     return signature;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tolerance_only_rejects_shortfalls() {
+        assert!(within_tolerance(1_000_000, 1_000_000, 0));
+        assert!(within_tolerance(1_000_000, 2_000_000, 0));
+        assert!(!within_tolerance(1_000_000, 999_999, 0));
+        assert!(within_tolerance(1_000_000, 995_000, 50));
+        assert!(!within_tolerance(1_000_000, 994_999, 50));
+    }
+
+    #[test]
+    fn wrapped_lamports_counts_transfers_into_the_account() {
+        let payer = Pubkey::new_unique();
+        let ata = Pubkey::new_unique();
+        let ixs = vec![
+            instructions::cu::price_instruction(1),
+            system_instruction::transfer(&payer, &ata, 700),
+            system_instruction::transfer(&payer, &Pubkey::new_unique(), 300),
+        ];
+        assert_eq!(wrapped_lamports(&ixs, &ata), 700);
+        assert_eq!(wrapped_lamports(&ixs[..1], &ata), 0);
+    }
+
+    #[test]
+    fn compute_budget_precedes_the_route() {
+        let marker = || Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        let (setup, wrap, swap, unwrap) = (marker(), marker(), marker(), marker());
+        let (nonce_account, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let tx = |nonce, include_cu_limit| ArbInstructions {
+            nonce,
+            include_cu_limit,
+            cu_limit: 300_000,
+            cu_price: 5_000,
+            setup: vec![setup.clone()],
+            before_swap: vec![wrap.clone()],
+            swap: swap.clone(),
+            after_swap: vec![unwrap.clone()],
+        };
+        let limit = instructions::cu::limit_instruction(300_000);
+        let price = instructions::cu::price_instruction(5_000);
+        let route = vec![setup.clone(), wrap.clone(), swap.clone(), unwrap.clone()];
+
+        let ixs = tx(None, true).into_instructions();
+        assert_eq!(
+            ixs,
+            [vec![limit.clone(), price.clone()], route.clone()].concat()
+        );
+
+        let ixs = tx(None, false).into_instructions();
+        assert_eq!(ixs, [vec![price.clone()], route.clone()].concat());
+
+        let advance = system_instruction::advance_nonce_account(&nonce_account, &authority);
+        let ixs = tx(Some((nonce_account, authority)), true).into_instructions();
+        assert_eq!(ixs, [vec![advance, limit, price], route].concat());
+    }
+
+    #[test]
+    fn priority_fee_is_paid_on_the_default_limit_without_the_instruction() {
+        let marker = || Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        let tx = |nonce, include_cu_limit, setup: usize| ArbInstructions {
+            nonce,
+            include_cu_limit,
+            cu_limit: 300_000,
+            cu_price: 5_000,
+            setup: (0..setup).map(|_| marker()).collect(),
+            before_swap: vec![marker()],
+            swap: marker(),
+            after_swap: Vec::new(),
+        };
+        let nonce = Some((Pubkey::new_unique(), Pubkey::new_unique()));
+
+        assert_eq!(tx(None, true, 1).compute_unit_limit(), 300_000);
+        // setup, wrap and swap, the price instruction doesn't count
+        assert_eq!(tx(None, false, 1).compute_unit_limit(), 600_000);
+        assert_eq!(tx(nonce, false, 1).compute_unit_limit(), 800_000);
+        // capped at the transaction maximum
+        assert_eq!(tx(None, false, 8).compute_unit_limit(), 1_400_000);
+        assert_eq!(
+            instructions::cu::priority_fee(5_000, tx(None, false, 1).compute_unit_limit()),
+            3_000
+        );
+    }
+
+    #[test]
+    fn before_swap_index_tracks_the_layout() {
+        for nonce in [None, Some((Pubkey::new_unique(), Pubkey::new_unique()))] {
+            for include_cu_limit in [true, false] {
+                let mut tx = ArbInstructions {
+                    nonce,
+                    include_cu_limit,
+                    cu_limit: 300_000,
+                    cu_price: 5_000,
+                    setup: vec![instructions::cu::price_instruction(1)],
+                    before_swap: vec![instructions::cu::price_instruction(2)],
+                    swap: instructions::cu::price_instruction(3),
+                    after_swap: Vec::new(),
+                };
+                let borrow = instructions::cu::price_instruction(4);
+                let index = tx.next_before_swap_index();
+                tx.before_swap.push(borrow.clone());
+                assert_eq!(tx.into_instructions()[index], borrow);
+            }
+        }
+    }
+}
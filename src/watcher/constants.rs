@@ -1,7 +1,9 @@
+use crate::dex::{meteora, pumpfun, raydium, solfi, vertigo, whirlpool};
 use anchor_client::solana_sdk::pubkey::Pubkey;
 use serde::{Deserialize, Deserializer};
 use std::fs;
 use std::str::FromStr;
+use tracing::warn;
 
 fn deserialize_pubkey<'de, D>(deserializer: D) -> Result<Pubkey, D::Error>
 where
@@ -49,3 +51,64 @@ fn _load_programs(path: &str) -> anyhow::Result<Vec<(Pubkey, String, Option<Stri
 lazy_static::lazy_static! {
     pub static ref PROGRAMS_TO_WATCH: Vec<(Pubkey, String, Option<String>, bool)> = _load_programs("programs.toml").unwrap();
 }
+
+fn dex_programs() -> Vec<(&'static str, Pubkey)> {
+    vec![
+        ("pumpfun", pumpfun::program_id()),
+        ("meteora_dlmm", meteora::dlmm::program_id()),
+        ("meteora_damm", meteora::damm::program_id()),
+        ("raydium_amm", raydium::amm::program_id()),
+        ("raydium_cpmm", raydium::cpmm::program_id()),
+        ("raydium_clmm", raydium::clmm::program_id()),
+        ("whirlpool", whirlpool::program_id()),
+        ("vertigo", vertigo::program_id()),
+        ("solfi", solfi::program_id()),
+    ]
+}
+
+/// Program id of a DEX by its name, e.g. "raydium_cpmm".
+pub fn dex_program_id(name: &str) -> Option<Pubkey> {
+    dex_programs()
+        .into_iter()
+        .find(|(dex, _)| dex.eq_ignore_ascii_case(name.trim()))
+        .map(|(_, program_id)| program_id)
+}
+
+// resolve `watcher.programs` entries (program id or name) against programs.toml and known DEXes
+pub fn resolve_programs(entries: &[String]) -> Vec<(Pubkey, String, Option<String>, bool)> {
+    let mut programs: Vec<(Pubkey, String, Option<String>, bool)> =
+        Vec::with_capacity(entries.len());
+    let dex_programs = dex_programs();
+
+    for entry in entries {
+        let entry = entry.trim();
+        let address = Pubkey::from_str(entry).ok();
+
+        let watched = PROGRAMS_TO_WATCH.iter().find(|program| {
+            Some(program.0) == address || program.1.eq_ignore_ascii_case(entry)
+        });
+        if let Some(program) = watched {
+            programs.push(program.clone());
+            continue;
+        }
+
+        let dex = dex_programs.iter().find(|(name, program_id)| {
+            Some(*program_id) == address || name.eq_ignore_ascii_case(entry)
+        });
+        match dex {
+            Some((name, program_id)) => {
+                programs.push((
+                    *program_id,
+                    name.to_string(),
+                    Some(String::from("DEX")),
+                    true,
+                ));
+            }
+            None => {
+                warn!("Unknown program {} in watcher.programs, skip", entry);
+            }
+        }
+    }
+
+    programs
+}
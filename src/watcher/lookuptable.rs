@@ -4,8 +4,10 @@ use base64::Engine;
 use dashmap::DashMap;
 use parking_lot::RwLock;
 use serde_json::{Value, json};
+use std::future::Future;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, Semaphore};
 use tracing::warn;
 
 #[derive(Debug, Clone)]
@@ -36,6 +38,10 @@ impl LookupTableCacheEntry {
 #[derive(Debug)]
 pub struct LookupTableCache {
     cache: Arc<DashMap<Pubkey, LookupTableCacheEntry>>,
+    // one lock per table being fetched, later requests wait on it instead of fetching again
+    in_flight: Arc<DashMap<Pubkey, Arc<Mutex<()>>>>,
+    // bounds fetches of distinct tables
+    fetch_permits: Arc<Semaphore>,
     rpc_endpoint: String,
     stats: Arc<RwLock<CacheStats>>,
 }
@@ -60,7 +66,7 @@ impl CacheStats {
 }
 
 impl LookupTableCache {
-    pub fn new(rpc_endpoint: String) -> Self {
+    pub fn new(rpc_endpoint: String, max_concurrent_fetches: usize) -> Self {
         let cache = Arc::new(DashMap::new());
 
         let cache_clone = cache.clone();
@@ -70,6 +76,8 @@ impl LookupTableCache {
 
         Self {
             cache,
+            in_flight: Arc::new(DashMap::new()),
+            fetch_permits: Arc::new(Semaphore::new(max_concurrent_fetches.max(1))),
             rpc_endpoint,
             stats: Arc::new(RwLock::new(CacheStats::default())),
         }
@@ -79,23 +87,55 @@ impl LookupTableCache {
         &self,
         lookup_table_key: &Pubkey,
     ) -> Result<Vec<Pubkey>, Box<dyn std::error::Error>> {
+        self.get_or_fetch(lookup_table_key, || {
+            self.fetch_lookup_table_from_rpc(lookup_table_key)
+        })
+        .await
+    }
+
+    fn cached_accounts(&self, lookup_table_key: &Pubkey) -> Option<Vec<Pubkey>> {
+        if let Some(entry) = self
+            .cache
+            .get(lookup_table_key)
+            .filter(|entry| !entry.is_expired())
+        {
+            return Some(entry.accounts.clone());
+        }
+        self.cache
+            .remove_if(lookup_table_key, |_, entry| entry.is_expired());
+        None
+    }
+
+    /// Cached accounts of a table, or `fetch` them. Concurrent requests for a table that isn't
+    /// cached share one fetch, and at most `max_concurrent_fetches` distinct tables are fetched
+    /// at once so discovery storms on cold tables don't hammer the RPC.
+    async fn get_or_fetch<F, Fut>(
+        &self,
+        lookup_table_key: &Pubkey,
+        fetch: F,
+    ) -> Result<Vec<Pubkey>, Box<dyn std::error::Error>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Vec<Pubkey>, Box<dyn std::error::Error>>>,
+    {
         {
             let mut stats = self.stats.write();
             stats.total_requests += 1;
             stats.cache_size = self.cache.len();
         }
 
-        if let Some(entry) = self.cache.get(lookup_table_key) {
-            if !entry.is_expired() {
-                {
-                    let mut stats = self.stats.write();
-                    stats.cache_hits += 1;
-                }
+        if let Some(accounts) = self.cached_accounts(lookup_table_key) {
+            self.stats.write().cache_hits += 1;
+            return Ok(accounts);
+        }
 
-                return Ok(entry.accounts.clone());
-            } else {
-                self.cache.remove(lookup_table_key);
-            }
+        let lock = self.in_flight.entry(*lookup_table_key).or_default().clone();
+        let _fetching = lock.lock().await;
+
+        // the request holding the lock before us may have fetched it
+        if let Some(accounts) = self.cached_accounts(lookup_table_key) {
+            self.stats.write().cache_hits += 1;
+            return Ok(accounts);
         }
 
         {
@@ -104,12 +144,18 @@ impl LookupTableCache {
             stats.rpc_calls += 1;
         }
 
-        let accounts = self.fetch_lookup_table_from_rpc(lookup_table_key).await?;
-
-        let cache_entry = LookupTableCacheEntry::new(accounts.clone());
-        self.cache.insert(*lookup_table_key, cache_entry);
+        let result = {
+            let _permit = self.fetch_permits.acquire().await?;
+            fetch().await
+        };
+        if let Ok(accounts) = &result {
+            let cache_entry = LookupTableCacheEntry::new(accounts.clone());
+            self.cache.insert(*lookup_table_key, cache_entry);
+        }
+        // waiters still hold the lock's Arc, later requests find the table cached
+        self.in_flight.remove(lookup_table_key);
 
-        Ok(accounts)
+        result
     }
 
     async fn fetch_lookup_table_from_rpc(
@@ -263,8 +309,41 @@ impl LookupTableCache {
     fn clone(&self) -> Self {
         Self {
             cache: self.cache.clone(),
+            in_flight: self.in_flight.clone(),
+            fetch_permits: self.fetch_permits.clone(),
             rpc_endpoint: self.rpc_endpoint.clone(),
             stats: self.stats.clone(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn concurrent_requests_share_one_fetch() {
+        let cache = LookupTableCache::new(String::new(), 4);
+        let key = Pubkey::new_unique();
+        let table = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let fetches = AtomicUsize::new(0);
+
+        let (fetches_ref, table_ref) = (&fetches, &table);
+        let requests = (0..16).map(|_| {
+            cache.get_or_fetch(&key, move || async move {
+                fetches_ref.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                Ok(table_ref.clone())
+            })
+        });
+        let results = futures::future::join_all(requests).await;
+
+        assert_eq!(fetches.load(Ordering::Relaxed), 1);
+        for accounts in results {
+            assert_eq!(accounts.unwrap(), table);
+        }
+        assert_eq!(cache.get_stats().rpc_calls, 1);
+        assert_eq!(cache.get_stats().cache_hits, 15);
+    }
+}
@@ -12,7 +12,14 @@ use lockfree::stack::Stack;
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 use serde_json::{Value, json};
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::{
+    collections::HashSet,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
 use tokio::sync::mpsc;
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 use tracing::{debug, error, info, warn};
@@ -24,9 +31,73 @@ mod parser;
 mod processor;
 mod transaction;
 
+pub use processor::pin_pools;
+
 pub static SIG_QUEUE: Lazy<Arc<Stack<String>>> = Lazy::new(|| Arc::new(Stack::new()));
-pub static POOL_QUEUE: Lazy<Arc<SegQueue<(Pubkey, AccountDataType, Option<Pubkey>)>>> =
-    Lazy::new(|| Arc::new(SegQueue::new()));
+// pool, its data type and the lookup table it was found with
+pub type PoolQueueItem = (Pubkey, AccountDataType, Option<Pubkey>);
+
+// only touched through pool_queue_push / pool_queue_pop so the counters stay in sync
+static POOL_QUEUE: Lazy<Arc<SegQueue<PoolQueueItem>>> = Lazy::new(|| Arc::new(SegQueue::new()));
+static POOL_QUEUE_DEPTH: AtomicUsize = AtomicUsize::new(0);
+static POOL_QUEUE_PUSHED: AtomicU64 = AtomicU64::new(0);
+static POOL_QUEUE_POPPED: AtomicU64 = AtomicU64::new(0);
+static POOL_QUEUE_DROPPED: AtomicU64 = AtomicU64::new(0);
+// smoothed batch latency shared by all batch workers, in milliseconds
+static BATCH_LATENCY_MS: AtomicU64 = AtomicU64::new(0);
+
+// watcher.ignore_signers, a restart picks up changes
+static IGNORED_SIGNERS: Lazy<HashSet<Pubkey>> = Lazy::new(global::get_ignored_signers);
+const MIN_BATCH_BACKOFF_MS: u64 = 10;
+const MAX_BATCH_BACKOFF_MS: u64 = 2_000;
+
+/// Pool queue counters since the previous `take_pool_queue_stats`, depth is the current one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PoolQueueStats {
+    pub pushed: u64,
+    pub popped: u64,
+    pub dropped: u64,
+    pub depth: usize,
+}
+
+/// Queue a discovered pool for loading, dropped when `watcher.max_pool_queue` is reached.
+pub fn pool_queue_push(item: PoolQueueItem) -> bool {
+    let max_pool_queue = global::get_max_pool_queue();
+    // the slot is counted before the item is queued, so a pop never takes an item the depth
+    // doesn't count yet, and checking and counting in one step keeps concurrent pushes in bound
+    let reserved = POOL_QUEUE_DEPTH.fetch_update(Ordering::AcqRel, Ordering::Acquire, |depth| {
+        (max_pool_queue == 0 || depth < max_pool_queue).then_some(depth + 1)
+    });
+    if reserved.is_err() {
+        POOL_QUEUE_DROPPED.fetch_add(1, Ordering::Relaxed);
+        return false;
+    }
+
+    POOL_QUEUE.push(item);
+    POOL_QUEUE_PUSHED.fetch_add(1, Ordering::Relaxed);
+    true
+}
+
+pub fn pool_queue_pop() -> Option<PoolQueueItem> {
+    let item = POOL_QUEUE.pop()?;
+    POOL_QUEUE_DEPTH.fetch_sub(1, Ordering::AcqRel);
+    POOL_QUEUE_POPPED.fetch_add(1, Ordering::Relaxed);
+    Some(item)
+}
+
+#[inline]
+pub fn pool_queue_depth() -> usize {
+    POOL_QUEUE_DEPTH.load(Ordering::Relaxed)
+}
+
+pub fn take_pool_queue_stats() -> PoolQueueStats {
+    PoolQueueStats {
+        pushed: POOL_QUEUE_PUSHED.swap(0, Ordering::Relaxed),
+        popped: POOL_QUEUE_POPPED.swap(0, Ordering::Relaxed),
+        dropped: POOL_QUEUE_DROPPED.swap(0, Ordering::Relaxed),
+        depth: pool_queue_depth(),
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct ProgramInfo {
@@ -139,13 +210,17 @@ impl SolanaTransactionWatcher {
         self.connection_healthy
             .store(true, std::sync::atomic::Ordering::Relaxed);
 
+        let ping_interval = Duration::from_secs(global::get_watcher_ping_interval_secs());
+        // without any message for this long the connection is assumed dead
+        let message_timeout = Duration::from_secs(global::get_watcher_message_timeout_secs());
+
         // Start heartbeat task using shared channel
         let (heartbeat_tx, mut heartbeat_rx) = mpsc::unbounded_channel::<()>();
         let heartbeat_handle = {
             let healthy = self.connection_healthy.clone();
 
             tokio::spawn(async move {
-                let mut interval = tokio::time::interval(Duration::from_secs(25));
+                let mut interval = tokio::time::interval(ping_interval);
                 let mut ping_count = 0u32;
 
                 loop {
@@ -219,7 +294,6 @@ impl SolanaTransactionWatcher {
 
         // Message processing loop with timeout detection
         let mut last_message_time = std::time::Instant::now();
-        let message_timeout = Duration::from_secs(90); // 1.5 minutes without any message
         let mut ping_counter = 0u32;
 
         loop {
@@ -462,24 +536,30 @@ impl SolanaTransactionWatcher {
     }
 }
 
+/// `tx_fetch_urls` are the endpoints transactions are fetched from, in the order they are tried.
 pub async fn start_batch_processing(
     rpc_endpoint: &str,
+    tx_fetch_urls: Vec<String>,
     num_workers: usize,
     batch_size: usize,
 ) -> Result<()> {
     let rpc_endpoint = rpc_endpoint.to_string();
+    let tx_fetch_urls = Arc::new(tx_fetch_urls);
 
-    let shared_lookup_cache = Arc::new(lookuptable::LookupTableCache::new(rpc_endpoint.clone()));
+    let shared_lookup_cache = Arc::new(lookuptable::LookupTableCache::new(
+        rpc_endpoint.clone(),
+        global::get_alt_fetch_concurrency(),
+    ));
     let mut handles = Vec::new();
 
     for worker_id in 0..num_workers {
-        let rpc_endpoint_clone = rpc_endpoint.clone();
+        let tx_fetch_urls_clone = tx_fetch_urls.clone();
         let lookup_cache_clone = shared_lookup_cache.clone();
 
         let handle = tokio::spawn(async move {
             process_queue_batch_worker(
                 worker_id,
-                &rpc_endpoint_clone,
+                tx_fetch_urls_clone,
                 batch_size,
                 lookup_cache_clone,
             )
@@ -500,7 +580,7 @@ pub async fn start_batch_processing(
 
 async fn process_queue_batch_worker(
     worker_id: usize,
-    rpc_endpoint: &str,
+    tx_fetch_urls: Arc<Vec<String>>,
     batch_size: usize,
     shared_lookup_cache: Arc<lookuptable::LookupTableCache>,
 ) -> Result<()> {
@@ -525,17 +605,18 @@ async fn process_queue_batch_worker(
             break;
         }
 
+        let started = tokio::time::Instant::now();
         let tasks: Vec<_> = batch
             .into_iter()
             .map(|signature| {
-                let rpc_endpoint_clone = rpc_endpoint.to_string();
+                let tx_fetch_urls_clone = tx_fetch_urls.clone();
                 let lookup_cache_clone = shared_lookup_cache.clone();
 
                 tokio::spawn(async move {
                     process_single_signature(
                         worker_id,
                         &signature,
-                        &rpc_endpoint_clone,
+                        &tx_fetch_urls_clone,
                         &lookup_cache_clone,
                     )
                     .await
@@ -548,19 +629,43 @@ async fn process_queue_batch_worker(
                 eprintln!("Worker {}: Batch task failed: {}", worker_id, e);
             }
         }
+
+        tokio::time::sleep(batch_backoff(started.elapsed())).await;
     }
 
     Ok(())
 }
 
+/// Fold the latest batch latency into the shared average and return how long the
+/// worker should pause, so workers slow down together when the RPC gets slow.
+fn batch_backoff(elapsed: Duration) -> Duration {
+    let sample = elapsed.as_millis() as u64;
+    let previous = BATCH_LATENCY_MS.load(Ordering::Relaxed);
+    let latency = if previous == 0 {
+        sample
+    } else {
+        (previous * 4 + sample) / 5
+    };
+    BATCH_LATENCY_MS.store(latency, Ordering::Relaxed);
+
+    Duration::from_millis((latency / 2).clamp(MIN_BATCH_BACKOFF_MS, MAX_BATCH_BACKOFF_MS))
+}
+
 async fn process_single_signature(
     _worker_id: usize,
     signature: &str,
-    rpc_endpoint: &str,
+    tx_fetch_urls: &[String],
     shared_lookup_cache: &Arc<lookuptable::LookupTableCache>,
 ) -> Result<()> {
     let (details, alt_accounts) =
-        transaction::fetch_transaction_details(rpc_endpoint, signature).await?;
+        transaction::fetch_transaction_details(tx_fetch_urls, signature).await?;
+
+    if let Some(fee_payer) = details.fee_payer() {
+        if IGNORED_SIGNERS.contains(fee_payer) {
+            debug!("Skip {}, fee payer {} is ignored", signature, fee_payer);
+            return Ok(());
+        }
+    }
 
     if details.is_arbitrage {
         let details = transaction::fetch_accounts_from_alt(
@@ -598,7 +703,7 @@ async fn process_single_signature(
 
         for (account_info_op, pubkey) in account_data.iter().zip(accounts.iter()) {
             match account_info_op {
-                Some(account) => match parser::get_pool_type(&account) {
+                Some(account) => match parser::get_pool_type(pubkey, &account) {
                     AccountDataType::Empty => {}
                     pool_type => {
                         let alt_address = find_alt_address(
@@ -619,8 +724,8 @@ async fn process_single_signature(
         }
 
         for pool in pool_data {
-            if !pool_index::has_pool(&pool.0) {
-                POOL_QUEUE.push(pool);
+            if !pool_index::has_pool(&pool.0) && !pool_queue_push(pool) {
+                debug!("Pool queue full, dropped a pool");
             }
         }
     }
@@ -678,7 +783,7 @@ async fn begin_watch_unit(
                 processed,
                 cached,
                 pool_index::count(),
-                POOL_QUEUE.len(),
+                pool_queue_depth(),
             );
         }
     });
@@ -707,9 +812,13 @@ pub async fn monitoring(
     chunk_size: usize,
 ) -> Result<()> {
     let rpc_endpoint = conf.rpc.url.to_string();
+    let tx_fetch_urls = conf.rpc.tx_fetch_endpoints();
+    let batch_workers = conf.watcher.batch_workers.max(1);
+    let batch_size = conf.watcher.batch_size.max(1);
 
     tokio::spawn(async move {
-        let _ = start_batch_processing(&rpc_endpoint, 10, 5).await;
+        let _ =
+            start_batch_processing(&rpc_endpoint, tx_fetch_urls, batch_workers, batch_size).await;
     });
 
     if let Some(command) = command_op {
@@ -718,7 +827,18 @@ pub async fn monitoring(
         });
     }
 
-    for programs in constants::PROGRAMS_TO_WATCH.clone().chunks(chunk_size) {
+    let watch_programs = match &conf.watcher.programs {
+        Some(entries) if !entries.is_empty() => {
+            let programs = constants::resolve_programs(entries);
+            if programs.is_empty() {
+                warn!("No valid program in watcher.programs, nothing to watch");
+            }
+            programs
+        }
+        _ => constants::PROGRAMS_TO_WATCH.clone(),
+    };
+
+    for programs in watch_programs.chunks(chunk_size) {
         let websocket_url = conf.rpc.websocket_url.to_string();
         let programs = programs.to_vec();
 
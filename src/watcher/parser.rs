@@ -1,11 +1,11 @@
 use crate::{
     dex::{meteora, pumpfun, raydium, solfi, vertigo, whirlpool},
-    streaming::AccountDataType,
+    streaming::{AccountDataType, AccountTypeInfo, parser::has_expected_len},
 };
-use anchor_client::solana_sdk::account::Account;
+use anchor_client::solana_sdk::{account::Account, pubkey::Pubkey};
 use dlmm_interface::LbPairAccount;
 
-pub fn get_pool_type(account: &Account) -> AccountDataType {
+pub fn get_pool_type(pubkey: &Pubkey, account: &Account) -> AccountDataType {
     if account.data.len() < 8 {
         return AccountDataType::Empty;
     }
@@ -14,7 +14,9 @@ pub fn get_pool_type(account: &Account) -> AccountDataType {
     let owner = &account.owner;
 
     if *owner == meteora::dlmm::program_id() {
-        if data[0..8] == meteora::dlmm::POOL_DISCRIMINATOR {
+        if data[0..8] == meteora::dlmm::POOL_DISCRIMINATOR
+            && has_expected_len(pubkey, AccountTypeInfo::DlmmPair, data.len())
+        {
             if let Ok(data) = LbPairAccount::deserialize(data) {
                 return AccountDataType::DlmmPair(data.0);
             }
@@ -23,7 +25,9 @@ pub fn get_pool_type(account: &Account) -> AccountDataType {
     }
 
     if *owner == meteora::damm::program_id() {
-        if data[0..8] == meteora::damm::POOL_DISCRIMINATOR {
+        if data[0..8] == meteora::damm::POOL_DISCRIMINATOR
+            && has_expected_len(pubkey, AccountTypeInfo::Dammv2Pool, data.len())
+        {
             if let Ok(data) = meteora::damm::Pool::deserialize(data) {
                 return AccountDataType::Dammv2Pool(data);
             }
@@ -32,7 +36,9 @@ pub fn get_pool_type(account: &Account) -> AccountDataType {
     }
 
     if *owner == pumpfun::program_id() {
-        if data[0..8] == pumpfun::POOL_DISCRIMINATOR {
+        if data[0..8] == pumpfun::POOL_DISCRIMINATOR
+            && has_expected_len(pubkey, AccountTypeInfo::AmmPair, data.len())
+        {
             if let Ok(pool) = pumpfun::PumpAmmReader::parse_pool_data(&data[8..]) {
                 return AccountDataType::AmmPair(pool);
             }
@@ -41,7 +47,9 @@ pub fn get_pool_type(account: &Account) -> AccountDataType {
     }
 
     if *owner == raydium::amm::program_id() {
-        if data[0..8] == raydium::amm::POOL_DISCRIMINATOR {
+        if data[0..8] == raydium::amm::POOL_DISCRIMINATOR
+            && has_expected_len(pubkey, AccountTypeInfo::RaydiumAmmPool, data.len())
+        {
             if let Ok(data) = raydium::amm::AmmInfo::deserialize(data) {
                 return AccountDataType::RaydiumAmmPool(data);
             }
@@ -50,7 +58,9 @@ pub fn get_pool_type(account: &Account) -> AccountDataType {
     }
 
     if *owner == raydium::cpmm::program_id() {
-        if data[0..8] == raydium::cpmm::POOL_DISCRIMINATOR {
+        if data[0..8] == raydium::cpmm::POOL_DISCRIMINATOR
+            && has_expected_len(pubkey, AccountTypeInfo::RaydiumCpmmPool, data.len())
+        {
             if let Ok(data) = raydium::cpmm::PoolState::deserialize(data) {
                 return AccountDataType::RaydiumCpmmPool(data);
             }
@@ -59,7 +69,9 @@ pub fn get_pool_type(account: &Account) -> AccountDataType {
     }
 
     if *owner == raydium::clmm::program_id() {
-        if data[0..8] == raydium::clmm::POOL_DISCRIMINATOR {
+        if data[0..8] == raydium::clmm::POOL_DISCRIMINATOR
+            && has_expected_len(pubkey, AccountTypeInfo::RaydiumClmmPool, data.len())
+        {
             if let Ok(data) = raydium::clmm::PoolState::deserialize(data) {
                 return AccountDataType::RaydiumClmmPool(data);
             }
@@ -68,7 +80,9 @@ pub fn get_pool_type(account: &Account) -> AccountDataType {
     }
 
     if *owner == whirlpool::program_id() {
-        if data[0..8] == whirlpool::POOL_DISCRIMINATOR {
+        if data[0..8] == whirlpool::POOL_DISCRIMINATOR
+            && has_expected_len(pubkey, AccountTypeInfo::Whirlpool, data.len())
+        {
             if let Ok(data) = whirlpool::state::Whirlpool::deserialize(data) {
                 return AccountDataType::Whirlpool(data);
             }
@@ -77,7 +91,9 @@ pub fn get_pool_type(account: &Account) -> AccountDataType {
     }
 
     if *owner == vertigo::program_id() {
-        if data[0..8] == vertigo::POOL_DISCRIMINATOR {
+        if data[0..8] == vertigo::POOL_DISCRIMINATOR
+            && has_expected_len(pubkey, AccountTypeInfo::VertigoPool, data.len())
+        {
             if let Ok(data) = vertigo::Pool::deserialize(data) {
                 return AccountDataType::VertigoPool(data);
             }
@@ -86,8 +102,10 @@ pub fn get_pool_type(account: &Account) -> AccountDataType {
     }
 
     if *owner == solfi::program_id() {
-        if data[0..8] == solfi::POOL_DISCRIMINATOR {
-            if let Ok(data) = solfi::Pool::deserialize(owner, data) {
+        if data[0..8] == solfi::POOL_DISCRIMINATOR
+            && has_expected_len(pubkey, AccountTypeInfo::SolfiPool, data.len())
+        {
+            if let Ok(data) = solfi::Pool::deserialize(pubkey, data) {
                 return AccountDataType::SolfiPool(data);
             }
         }
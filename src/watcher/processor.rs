@@ -1,4 +1,4 @@
-use super::POOL_QUEUE;
+use super::{constants, parser, pool_queue_pop};
 use crate::{
     global::{self, get_base_mint},
     inserter,
@@ -6,13 +6,17 @@ use crate::{
     streaming::{self, AccountDataType, WatcherCommand, global_data},
     wsol_mint,
 };
-use anchor_client::solana_sdk::pubkey::Pubkey;
-use anyhow::Result;
+use anchor_client::solana_sdk::{account::Account, pubkey::Pubkey};
+use anyhow::{Result, anyhow};
 use std::sync::Arc;
 use tokio::sync::mpsc;
-use tracing::error;
+use tracing::{error, info, warn};
 
 const ENABLED_LOG: bool = false;
+// pools a loader pops at once, the mints of the whole batch are fetched in one call. Up from 5
+// so a burst shares more of those calls. Each pool's other accounts, such as its lookup table,
+// vaults and configs, are still fetched by its own task, so a batch costs 10 concurrent loads.
+const POOL_BATCH_SIZE: usize = 10;
 
 pub async fn handle_batch_process(
     command: mpsc::UnboundedSender<WatcherCommand>,
@@ -49,7 +53,7 @@ async fn batch_worker(
         let mut batch = Vec::new();
 
         for _ in 0..batch_size {
-            if let Some(item) = POOL_QUEUE.pop() {
+            if let Some(item) = pool_queue_pop() {
                 batch.push(item);
             } else {
                 break;
@@ -61,10 +65,19 @@ async fn batch_worker(
             continue;
         }
 
+        batch.retain(|(pool_pk, _, _)| !pool_index::has_pool(pool_pk));
+        let token_pools: Vec<TokenPool> = batch
+            .iter()
+            .filter_map(|(pool_pk, pool_data, _)| pool_data.to_token_pool(*pool_pk))
+            .collect();
+        // items fall back to fetching their own mints if this fails
+        if let Err(e) = prefetch_mints(&token_pools).await {
+            warn!("Worker {} failed to prefetch batch mints: {}", worker_id, e);
+        }
+
         let tasks: Vec<_> = batch
             .into_iter()
             .enumerate()
-            .filter(|(_, (pool_pk, _, _))| !pool_index::has_pool(&pool_pk))
             .map(|(idx, (pool_pk, pool_data, alt_op))| {
                 let command_clone = command.clone();
 
@@ -138,12 +151,42 @@ async fn process_pool_item(
     Ok(())
 }
 
+fn cached_mint(mint: &Pubkey) -> Option<Account> {
+    match global_data::get_account(mint) {
+        Some(AccountDataType::Account(account)) => Some(account),
+        _ => global_data::get_mint_account(mint),
+    }
+}
+
+/// Fetch the mints of a batch of pools that aren't cached yet in one call and cache them,
+/// so a burst of discovered pools doesn't cost a round trip per pool.
+async fn prefetch_mints(token_pools: &[TokenPool]) -> Result<()> {
+    let mut mints: Vec<Pubkey> = token_pools
+        .iter()
+        .flat_map(|pool| [pool.mint_a, pool.mint_b])
+        .filter(|mint| cached_mint(mint).is_none())
+        .collect();
+    mints.sort();
+    mints.dedup();
+    if mints.is_empty() {
+        return Ok(());
+    }
+
+    let accounts = global::get_rpc_client()
+        .get_multiple_accounts(&mints)
+        .await?;
+    for (mint, account) in mints.into_iter().zip(accounts) {
+        if let Some(account) = account {
+            global_data::add_mint_account(mint, account);
+        }
+    }
+
+    Ok(())
+}
+
 async fn is_native_pool(pool: &TokenPool) -> Result<bool> {
     let token_program = crate::token_program();
-    if let (Some(AccountDataType::Account(a)), Some(AccountDataType::Account(b))) = (
-        global_data::get_account(&pool.mint_a),
-        global_data::get_account(&pool.mint_b),
-    ) {
+    if let (Some(a), Some(b)) = (cached_mint(&pool.mint_a), cached_mint(&pool.mint_b)) {
         return Ok(a.owner == token_program && b.owner == token_program);
     }
 
@@ -157,6 +200,51 @@ async fn is_native_pool(pool: &TokenPool) -> Result<bool> {
         .all(|opt| matches!(opt, Some(acc) if acc.owner == token_program)))
 }
 
+/// Index `bot.pinned_pools` and subscribe their accounts, so they are quoted from startup
+/// instead of waiting for discovery to see an arb through them.
+pub async fn pin_pools(command: &mpsc::UnboundedSender<WatcherCommand>) -> Result<()> {
+    let pinned = global::get_pinned_pools()?;
+    if pinned.is_empty() {
+        return Ok(());
+    }
+
+    let addresses: Vec<Pubkey> = pinned.iter().map(|(address, _)| *address).collect();
+    let accounts = global::get_rpc_client()
+        .get_multiple_accounts(&addresses)
+        .await?;
+
+    for ((pool_pk, dex), account) in pinned.into_iter().zip(accounts) {
+        let program_id = constants::dex_program_id(&dex)
+            .ok_or_else(|| anyhow!("Unknown DEX {} for pinned pool {}", dex, pool_pk))?;
+        let account = account.ok_or_else(|| anyhow!("Pinned pool {} not found", pool_pk))?;
+        if account.owner != program_id {
+            return Err(anyhow!(
+                "Pinned pool {} is owned by {}, not {}",
+                pool_pk,
+                account.owner,
+                dex
+            ));
+        }
+
+        let pool_data = parser::get_pool_type(&pool_pk, &account);
+        let token_pool = pool_data
+            .to_token_pool(pool_pk)
+            .ok_or_else(|| anyhow!("Pinned pool {} could not be parsed", pool_pk))?;
+        let new_keys = inserter::add_pinned(token_pool, pool_data).await?;
+        command.send(WatcherCommand::BatchAdd {
+            accounts: streaming::util::pubkeys_to_strings(&new_keys),
+        })?;
+        info!("Pinned {} pool {}", dex, pool_pk);
+    }
+
+    Ok(())
+}
+
 pub async fn run_process(command: mpsc::UnboundedSender<WatcherCommand>) -> Result<()> {
-    handle_batch_process(command, 10, 5).await
+    handle_batch_process(
+        command,
+        global::get_pool_loader_concurrency(),
+        POOL_BATCH_SIZE,
+    )
+    .await
 }
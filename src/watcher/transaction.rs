@@ -5,6 +5,7 @@ use anyhow::{Result, anyhow};
 use serde_json::{Value, json};
 use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
+use std::time::Duration;
 use tracing::warn;
 
 #[derive(Debug, Clone)]
@@ -41,6 +42,13 @@ pub struct EnhancedTransactionInfo {
     pub compute_units_consumed: Option<u64>,
 }
 
+impl EnhancedTransactionInfo {
+    /// The first signer pays the fee.
+    pub fn fee_payer(&self) -> Option<&Pubkey> {
+        self.signer_accounts.first()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AccountInfo {
     pub pubkey: Pubkey,
@@ -91,7 +99,30 @@ pub struct BalanceChange {
     pub is_signer: bool,
 }
 
+// pause before asking the next endpoint, a transaction seen at processed may just need a moment
+const TX_FETCH_FAILOVER_DELAY: Duration = Duration::from_millis(200);
+
+/// Fetch the transaction from each of `rpc_endpoints` in turn until one returns it, an endpoint
+/// lagging behind answers "Transaction not found" where another may already have it.
 pub async fn fetch_transaction_details(
+    rpc_endpoints: &[String],
+    signature: &str,
+) -> Result<(EnhancedTransactionInfo, Option<Value>)> {
+    let mut last_error = anyhow!("No endpoint to fetch transaction {} from", signature);
+    for (i, rpc_endpoint) in rpc_endpoints.iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(TX_FETCH_FAILOVER_DELAY).await;
+        }
+        match fetch_transaction_from(rpc_endpoint, signature).await {
+            Ok(details) => return Ok(details),
+            Err(e) => last_error = e,
+        }
+    }
+
+    Err(last_error)
+}
+
+async fn fetch_transaction_from(
     rpc_endpoint: &str,
     signature: &str,
 ) -> Result<(EnhancedTransactionInfo, Option<Value>)> {
@@ -571,3 +602,85 @@ pub fn extract_pubkeys(alt_accounts: Option<Value>) -> Vec<Pubkey> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(success: bool, changes: &[(Pubkey, i128)]) -> EnhancedTransactionInfo {
+        let signer = Pubkey::new_unique();
+        EnhancedTransactionInfo {
+            signature: String::new(),
+            slot: 0,
+            block_time: None,
+            program_ids: vec![],
+            success,
+            fee: None,
+            logs: vec![],
+            err: None,
+            is_arbitrage: false,
+            all_accounts: vec![],
+            writable_accounts: vec![],
+            signer_accounts: vec![signer],
+            lookup_table_accounts: vec![],
+            pre_token_balances: vec![],
+            post_token_balances: vec![],
+            signer_token_balance_changes: changes
+                .iter()
+                .map(|(mint, change_amount)| TokenBalanceChange {
+                    account: Pubkey::new_unique(),
+                    mint: *mint,
+                    owner: signer,
+                    pre_amount: String::new(),
+                    post_amount: String::new(),
+                    change_amount: *change_amount,
+                    decimals: 0,
+                    ui_change: None,
+                    is_signer: true,
+                })
+                .collect(),
+            pre_balances: vec![],
+            post_balances: vec![],
+            signer_balance_changes: vec![],
+            compute_units_consumed: None,
+        }
+    }
+
+    // classified against wSOL as the base mint
+    fn is_arb(success: bool, changes: &[(Pubkey, i128)]) -> bool {
+        is_arbitrage_tx(&tx(success, changes), &wsol_mint())
+    }
+
+    #[test]
+    fn profitable_arb_only_grows_the_base_mint() {
+        let (base, other) = (wsol_mint(), Pubkey::new_unique());
+
+        assert!(is_arb(true, &[(base, 5_000), (other, 0)]));
+        // a loss or break-even isn't an arb to copy
+        assert!(!is_arb(true, &[(base, -5_000), (other, 0)]));
+        assert!(!is_arb(true, &[(base, 0), (other, 0)]));
+        // a single token account can't be a round trip
+        assert!(!is_arb(true, &[(base, 5_000)]));
+    }
+
+    #[test]
+    fn failed_arb_leaves_every_balance_unchanged() {
+        let (base, other) = (wsol_mint(), Pubkey::new_unique());
+
+        assert!(is_arb(false, &[(base, 0), (other, 0)]));
+        assert!(!is_arb(false, &[(base, -5_000), (other, 0)]));
+    }
+
+    #[test]
+    fn swaps_and_transfers_of_other_mints_are_not_arbs() {
+        let (base, other, third) = (wsol_mint(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        // buying a token with the base mint
+        assert!(!is_arb(true, &[(base, -5_000), (other, 70)]));
+        // profitable in the base mint but keeping another token
+        assert!(!is_arb(true, &[(base, 5_000), (other, 70)]));
+        // transfers of mints other than the base
+        assert!(!is_arb(true, &[(other, -70), (third, 40)]));
+        assert!(!is_arb(true, &[(other, 0), (third, 0)]));
+    }
+}